@@ -0,0 +1,1063 @@
+//! Code generators behind `chio add`.
+//!
+//! Generators patch the files emitted by `chio init` in place. They only ever
+//! insert new lines next to known anchors, so anything the user has written
+//! around those anchors is kept as is.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bench;
+use crate::config::ChioConfig;
+use crate::content::templates;
+use crate::name::{crate_name, KEYWORDS};
+use crate::template::{self, TemplateContext, Value};
+use crate::TestFramework;
+
+/// A scaffolded program found on disk.
+pub struct Project {
+    pub root: PathBuf,
+    /// Root of the workspace when the project is one of its programs.
+    pub workspace_root: Option<PathBuf>,
+    pub crate_name: String,
+    pub test_framework: TestFramework,
+    /// Contents of `Chio.toml`, absent for projects created before it existed.
+    pub config: Option<ChioConfig>,
+}
+
+impl Project {
+    /// Opens the project rooted at `root`, reading its package name from
+    /// `Cargo.toml` and its test framework from `Chio.toml`, or from the
+    /// dev-dependencies when there is no `Chio.toml`.
+    pub fn open(root: &Path) -> Result<Self> {
+        Self::open_in(root, None)
+    }
+
+    /// Opens the workspace program in `programs/<name>`.
+    pub fn open_member(workspace_root: &Path, name: &str) -> Result<Self> {
+        let root = workspace_root.join("programs").join(name);
+        if !root.is_dir() {
+            anyhow::bail!(
+                "No program '{}' in {}",
+                name,
+                workspace_root.join("programs").display()
+            );
+        }
+        Self::open_in(&root, Some(workspace_root))
+    }
+
+    fn open_in(root: &Path, workspace_root: Option<&Path>) -> Result<Self> {
+        let manifest_path = root.join("Cargo.toml");
+        let manifest = fs::read_to_string(&manifest_path).with_context(|| {
+            format!(
+                "Failed to read {}. Run this command from the project root.",
+                manifest_path.display()
+            )
+        })?;
+
+        let package_name = package_name(&manifest).ok_or_else(|| {
+            anyhow::anyhow!("No package name found in {}", manifest_path.display())
+        })?;
+
+        let config = ChioConfig::load(workspace_root.unwrap_or(root))?;
+        let configured = config
+            .as_ref()
+            .and_then(|config| config.program_config(&package_name));
+        let test_framework = match configured {
+            Some(program) => program.test_framework,
+            None if manifest.contains("litesvm") => TestFramework::Litesvm,
+            None => TestFramework::Mollusk,
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            workspace_root: workspace_root.map(Path::to_path_buf),
+            crate_name: crate_name(&package_name),
+            test_framework,
+            config,
+        })
+    }
+
+    /// The directory holding `Chio.toml` and `target/`: the workspace root
+    /// for workspace programs, the project root otherwise.
+    pub fn base_dir(&self) -> &Path {
+        self.workspace_root.as_deref().unwrap_or(&self.root)
+    }
+
+    /// Template context with the crate name and test framework of the
+    /// project, and the `workspace` feature for workspace programs.
+    pub fn template_context(&self) -> TemplateContext {
+        TemplateContext::new(&self.crate_name)
+            .with_test_framework(self.test_framework)
+            .with_feature("workspace", self.workspace_root.is_some())
+    }
+
+    /// Returns the address passed to `declare_id!` in `src/lib.rs`.
+    pub fn program_id(&self) -> Result<String> {
+        let lib_path = self.root.join("src").join("lib.rs");
+        let lib_rs = fs::read_to_string(&lib_path)
+            .with_context(|| format!("Failed to read {}", lib_path.display()))?;
+
+        declared_program_id(&lib_rs)
+            .ok_or_else(|| anyhow::anyhow!("No declare_id! found in {}", lib_path.display()))
+    }
+}
+
+/// Files touched by a generator, relative to the project root.
+#[derive(Debug, Default)]
+pub struct Generated {
+    pub created: Vec<PathBuf>,
+    pub updated: Vec<PathBuf>,
+}
+
+/// Reads the `name` key of the `[package]` table of a `Cargo.toml`.
+pub fn package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim() == "name" {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Returns the address passed to the first `declare_id!` in `src`.
+pub fn declared_program_id(src: &str) -> Option<String> {
+    let start = src.find("declare_id!(\"")? + "declare_id!(\"".len();
+    let end = src[start..].find('"')?;
+    Some(src[start..start + end].to_string())
+}
+
+/// Converts a snake_case name into the PascalCase used for types and variants.
+pub fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Returns the discriminators and variants mapped by the `TryFrom<&u8>` impl
+/// in `instructions/mod.rs`.
+pub fn instruction_variants(mod_rs: &str) -> Vec<(u8, String)> {
+    mod_rs
+        .lines()
+        .filter_map(|line| {
+            let (disc, rest) = line.trim().split_once("=>")?;
+            let disc = disc.trim().parse::<u8>().ok()?;
+            let variant = rest
+                .trim()
+                .strip_prefix("Ok(ProgramInstruction::")?
+                .split(')')
+                .next()?;
+            Some((disc, variant.to_string()))
+        })
+        .collect()
+}
+
+/// Returns the discriminator following the highest one already in use.
+pub fn next_discriminator(mod_rs: &str) -> Result<u8> {
+    match instruction_variants(mod_rs)
+        .iter()
+        .map(|(disc, _)| *disc)
+        .max()
+    {
+        Some(u8::MAX) => anyhow::bail!("All 256 instruction discriminators are already in use"),
+        Some(max) => Ok(max + 1),
+        None => Ok(0),
+    }
+}
+
+/// Registers a new instruction module, `ProgramInstruction` variant and
+/// discriminator arm in the contents of `instructions/mod.rs`.
+pub fn patch_instructions_mod(
+    src: &str,
+    module: &str,
+    variant: &str,
+    discriminator: u8,
+) -> Result<String> {
+    if instruction_variants(src).iter().any(|(_, v)| v == variant) {
+        anyhow::bail!("ProgramInstruction::{} already exists", variant);
+    }
+
+    let src = register_module(src, module, "instructions/mod.rs")?;
+
+    let (_, close) = find_block(&src, "enum ProgramInstruction")
+        .context("Could not find `enum ProgramInstruction` in instructions/mod.rs")?;
+    // The handler template only reads a signer; its account meta keeps the
+    // IDL in step with it until the handler grows more accounts.
+    let item = format!(
+        "#[account(0, signer, name = \"signer\", desc = \"Signer of the instruction\")]\n{},",
+        variant
+    );
+    let src = insert_block_item(&src, close, &item, true);
+
+    let (open, close) = find_block(&src, "impl TryFrom<&u8> for ProgramInstruction").context(
+        "Could not find `impl TryFrom<&u8> for ProgramInstruction` in instructions/mod.rs",
+    )?;
+    let fallback = src[open..close]
+        .find("_ =>")
+        .map(|offset| open + offset)
+        .context("Could not find the `_ =>` fallback arm in instructions/mod.rs")?;
+    let line_start = src[..fallback].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &src[line_start..fallback];
+
+    Ok(format!(
+        "{}{}{} => Ok(ProgramInstruction::{}),\n{}",
+        &src[..line_start],
+        indent,
+        discriminator,
+        variant,
+        &src[line_start..]
+    ))
+}
+
+/// Declares and glob re-exports a new module in the contents of
+/// `states/mod.rs`.
+pub fn patch_states_mod(src: &str, module: &str) -> Result<String> {
+    register_module(src, module, "states/mod.rs")
+}
+
+/// Adds a dispatch arm for a new instruction to the contents of
+/// `entrypoint.rs`.
+pub fn patch_entrypoint(src: &str, module: &str, variant: &str) -> Result<String> {
+    let (_, close) = find_block(src, "match ProgramInstruction::try_from")
+        .context("Could not find `match ProgramInstruction::try_from` in entrypoint.rs")?;
+
+    let arm = format!(
+        "ProgramInstruction::{variant} => {{\n    msg!(\"{module}\");\n    instructions::{module}(accounts, instruction_data)\n}}"
+    );
+    Ok(insert_block_item(src, close, &arm, false))
+}
+
+/// Adds `member` to the `members` array of a workspace `Cargo.toml`. The
+/// array is rewritten on one line; existing members are left as they are.
+pub fn patch_workspace_members(manifest: &str, member: &str) -> Result<String> {
+    const MEMBERS: &str = "members = [";
+    let start = manifest
+        .find(MEMBERS)
+        .context("Could not find `members = [...]` in the workspace Cargo.toml")?
+        + MEMBERS.len();
+    let len = manifest[start..]
+        .find(']')
+        .context("Unterminated `members` array in the workspace Cargo.toml")?;
+
+    let quoted = format!("\"{}\"", member);
+    let mut entries: Vec<&str> = manifest[start..start + len]
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    if entries.contains(&quoted.as_str()) {
+        anyhow::bail!("{} is already a workspace member", member);
+    }
+    entries.push(&quoted);
+
+    Ok(format!(
+        "{}{}{}",
+        &manifest[..start],
+        entries.join(", "),
+        &manifest[start + len..]
+    ))
+}
+
+/// Generates a new instruction handler and wires it into the program.
+pub fn add_instruction(project: &Project, name: &str) -> Result<Generated> {
+    validate_ident(name)?;
+    let struct_name = to_pascal_case(name);
+
+    let instructions_dir = Path::new("src").join("instructions");
+    let mod_path = instructions_dir.join("mod.rs");
+    let entrypoint_path = Path::new("src").join("entrypoint.rs");
+    let handler_path = instructions_dir.join(format!("{}.rs", name));
+    let test_path = Path::new("tests").join(format!("{}.rs", name));
+
+    for path in [&handler_path, &test_path] {
+        if project.root.join(path).exists() {
+            anyhow::bail!("{} already exists", path.display());
+        }
+    }
+
+    let mod_rs = read(project, &mod_path)?;
+    let discriminator = next_discriminator(&mod_rs)?;
+    let mod_rs = patch_instructions_mod(&mod_rs, name, &struct_name, discriminator)?;
+    let entrypoint_rs = patch_entrypoint(&read(project, &entrypoint_path)?, name, &struct_name)?;
+
+    let context = project
+        .template_context()
+        .set("name", name)
+        .set("struct_name", struct_name.as_str())
+        .set("discriminator", discriminator.to_string());
+    let test_rs = match project.test_framework {
+        TestFramework::Mollusk => template::render(
+            templates::unit_tests::MOLLUSK_INSTRUCTION_TEST_RS,
+            &context.clone().with_program_id(&project.program_id()?),
+        )?,
+        TestFramework::Litesvm => {
+            template::render(templates::unit_tests::LITESVM_INSTRUCTION_TEST_RS, &context)?
+        }
+    };
+    let handler_rs = template::render(templates::instructions::HANDLER_RS, &context)?;
+
+    // Mollusk projects benchmark every instruction; projects generated
+    // before the harness existed have none to patch.
+    let bench_path = Path::new("benches").join(format!("{}.rs", bench::BENCH_NAME));
+    let bench_rs = if project.test_framework == TestFramework::Mollusk
+        && project.root.join(&bench_path).exists()
+    {
+        let case_rs = template::render(templates::benches::INSTRUCTION_CASE_RS, &context)?;
+        Some(patch_bench(&read(project, &bench_path)?, name, &case_rs)?)
+    } else {
+        None
+    };
+
+    // Everything is rendered before the first write so a failed patch leaves
+    // the project untouched.
+    fs::create_dir_all(project.root.join("tests"))?;
+    write(project, &handler_path, &handler_rs)?;
+    write(project, &test_path, &test_rs)?;
+    write(project, &mod_path, &mod_rs)?;
+    write(project, &entrypoint_path, &entrypoint_rs)?;
+    let mut updated = vec![mod_path, entrypoint_path];
+    if let Some(bench_rs) = bench_rs {
+        write(project, &bench_path, &bench_rs)?;
+        updated.push(bench_path);
+    }
+
+    Ok(Generated {
+        created: vec![handler_path, test_path],
+        updated,
+    })
+}
+
+/// Adds the case function of a new instruction and its entry in `cases` to
+/// the contents of `benches/compute_units.rs`.
+pub fn patch_bench(src: &str, name: &str, case_rs: &str) -> Result<String> {
+    let entry = format!("(\"{}\", {}(&mollusk)),", name, name);
+    if src.lines().any(|line| line.trim() == entry) {
+        anyhow::bail!(
+            "'{}' is already benchmarked in benches/compute_units.rs",
+            name
+        );
+    }
+
+    let main = src
+        .find("\nfn main()")
+        .context("Could not find `fn main()` in benches/compute_units.rs")?
+        + 1;
+    let src = format!("{}{}\n{}", &src[..main], case_rs, &src[main..]);
+
+    let indent = src
+        .lines()
+        .rev()
+        .find(|line| line.trim_start().starts_with("(\""))
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .context(
+            "Could not find any `(\"name\", case(&mollusk)),` entry in benches/compute_units.rs",
+        )?;
+    insert_after_last_line(&src, "(\"", &format!("{}{}", indent, entry)).context(
+        "Could not find any `(\"name\", case(&mollusk)),` entry in benches/compute_units.rs",
+    )
+}
+
+/// Primitive types a zero-copy state field can have.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    Pubkey,
+    Bytes(usize),
+}
+
+impl FieldType {
+    /// Parses a Rust type as written on the command line, e.g. `u64`,
+    /// `Pubkey` or `[u8; 32]`.
+    pub fn parse(ty: &str) -> Result<Self> {
+        let compact: String = ty.chars().filter(|c| !c.is_whitespace()).collect();
+        let field_type = match compact.as_str() {
+            "u8" => Self::U8,
+            "i8" => Self::I8,
+            "u16" => Self::U16,
+            "i16" => Self::I16,
+            "u32" => Self::U32,
+            "i32" => Self::I32,
+            "u64" => Self::U64,
+            "i64" => Self::I64,
+            "Pubkey" => Self::Pubkey,
+            "bool" => anyhow::bail!(
+                "bool is not allowed in zero-copy state: any byte other than 0 or 1 is undefined behaviour. Use u8 instead."
+            ),
+            "u128" | "i128" => anyhow::bail!(
+                "{} is 16-byte aligned on the host but 8-byte aligned on SBF, so the layout would differ between tests and the program. Use [u8; 16] instead.",
+                compact
+            ),
+            other => {
+                let len = other
+                    .strip_prefix("[u8;")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|len| len.parse::<usize>().ok())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Unsupported field type '{}'. Use u8-u64, i8-i64, Pubkey or [u8; N].",
+                            ty
+                        )
+                    })?;
+                Self::Bytes(len)
+            }
+        };
+        Ok(field_type)
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 => 4,
+            Self::U64 | Self::I64 => 8,
+            Self::Pubkey => 32,
+            Self::Bytes(len) => *len,
+        }
+    }
+
+    pub fn align(&self) -> usize {
+        match self {
+            Self::Pubkey | Self::Bytes(_) => 1,
+            other => other.size(),
+        }
+    }
+
+    pub fn rust_type(&self) -> String {
+        match self {
+            Self::U8 => "u8".to_string(),
+            Self::I8 => "i8".to_string(),
+            Self::U16 => "u16".to_string(),
+            Self::I16 => "i16".to_string(),
+            Self::U32 => "u32".to_string(),
+            Self::I32 => "i32".to_string(),
+            Self::U64 => "u64".to_string(),
+            Self::I64 => "i64".to_string(),
+            Self::Pubkey => "Pubkey".to_string(),
+            Self::Bytes(len) => format!("[u8; {}]", len),
+        }
+    }
+}
+
+/// A field of a generated state struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateField {
+    pub name: String,
+    pub ty: FieldType,
+}
+
+impl StateField {
+    /// Parses a `name:Type` field specification.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, ty) = spec
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Invalid field '{}'. Expected NAME:TYPE", spec))?;
+        let name = name.trim();
+        // A leading underscore marks explicit padding fields.
+        validate_ident(name.trim_start_matches('_'))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            ty: FieldType::parse(ty)?,
+        })
+    }
+}
+
+/// Returns the size of the `#[repr(C)]` struct made of `fields`.
+///
+/// Layouts the zero-copy helpers in `states/utils.rs` cannot cast safely are
+/// refused: any padding, between fields or at the end, would be read as
+/// uninitialised bytes, and account data is only guaranteed to be 8-byte
+/// aligned.
+pub fn state_layout(fields: &[StateField]) -> Result<usize> {
+    if fields.is_empty() {
+        anyhow::bail!("A state needs at least one field, e.g. --field owner:Pubkey");
+    }
+
+    let mut offset = 0;
+    for (i, field) in fields.iter().enumerate() {
+        if fields[..i].iter().any(|f| f.name == field.name) {
+            anyhow::bail!("Duplicate field '{}'", field.name);
+        }
+        if offset % field.ty.align() != 0 {
+            anyhow::bail!(
+                "Field '{}: {}' would start at offset {} but needs {}-byte alignment, leaving padding. \
+                Reorder the fields, largest alignment first: {}",
+                field.name,
+                field.ty.rust_type(),
+                offset,
+                field.ty.align(),
+                suggested_order(fields)
+            );
+        }
+        offset += field.ty.size();
+    }
+
+    let align = fields.iter().map(|f| f.ty.align()).max().unwrap_or(1);
+    if offset % align != 0 {
+        anyhow::bail!(
+            "The fields add up to {} bytes, which the {}-byte alignment pads to {}. \
+            Add {} byte(s) of explicit padding, e.g. --field _padding:[u8; {}]",
+            offset,
+            align,
+            offset.next_multiple_of(align),
+            offset.next_multiple_of(align) - offset,
+            offset.next_multiple_of(align) - offset
+        );
+    }
+
+    Ok(offset)
+}
+
+fn suggested_order(fields: &[StateField]) -> String {
+    let mut sorted = fields.to_vec();
+    sorted.sort_by_key(|f| std::cmp::Reverse(f.ty.align()));
+    sorted
+        .iter()
+        .map(|f| format!("--field {}:{}", f.name, f.ty.rust_type()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Converts a PascalCase type name into the snake_case used for modules,
+/// keeping acronyms together: `HTTPConfig` becomes `http_config`.
+pub fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let after_word = i > 0 && !chars[i - 1].is_ascii_uppercase();
+            let ends_acronym = i > 0
+                && chars[i - 1].is_ascii_uppercase()
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|next| next.is_ascii_lowercase());
+            if after_word || ends_acronym {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Longest seed `find_program_address` accepts.
+pub const MAX_SEED_LEN: usize = 32;
+
+fn validate_seed(seed: &str) -> Result<()> {
+    if seed.is_empty() || seed.len() > MAX_SEED_LEN {
+        anyhow::bail!(
+            "Invalid seed '{}'. Seeds are 1 to {} bytes long.",
+            seed,
+            MAX_SEED_LEN
+        );
+    }
+    if !seed.chars().all(|c| c.is_ascii_graphic() || c == ' ') || seed.contains(['"', '\\']) {
+        anyhow::bail!(
+            "Invalid seed {:?}. Use printable ASCII characters other than quotes and backslashes.",
+            seed
+        );
+    }
+    Ok(())
+}
+
+/// Generates a zero-copy account state struct and registers it in
+/// `states/mod.rs`.
+pub fn add_state(
+    project: &Project,
+    name: &str,
+    fields: &[StateField],
+    seed: Option<&str>,
+) -> Result<Generated> {
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid_name {
+        anyhow::bail!(
+            "Invalid state name '{}'. Use PascalCase: letters and digits, starting with an uppercase letter.",
+            name
+        );
+    }
+    state_layout(fields)?;
+
+    let module = to_snake_case(name);
+    if KEYWORDS.contains(&module.as_str()) {
+        anyhow::bail!(
+            "Invalid state name '{}'. Its module `{}` is a Rust keyword.",
+            name,
+            module
+        );
+    }
+    let seed = seed.unwrap_or(&module);
+    validate_seed(seed)?;
+    let mod_path = Path::new("src").join("states").join("mod.rs");
+    let state_path = Path::new("src")
+        .join("states")
+        .join(format!("{}.rs", module));
+
+    if project.root.join(&state_path).exists() {
+        anyhow::bail!("{} already exists", state_path.display());
+    }
+
+    let mod_rs = patch_states_mod(&read(project, &mod_path)?, &module)?;
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|f| {
+            BTreeMap::from([
+                ("name".to_string(), Value::from(f.name.as_str())),
+                ("ty".to_string(), Value::from(f.ty.rust_type())),
+            ])
+        })
+        .collect();
+    let context = project
+        .template_context()
+        .set("struct_name", name)
+        .set("seed", seed)
+        .set("fields", fields);
+
+    write(
+        project,
+        &state_path,
+        &template::render(templates::states::ACCOUNT_STATE_RS, &context)?,
+    )?;
+    write(project, &mod_path, &mod_rs)?;
+
+    Ok(Generated {
+        created: vec![state_path],
+        updated: vec![mod_path],
+    })
+}
+
+/// A variant of the program's `MyProgramError` enum.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ErrorVariant {
+    pub code: u32,
+    pub name: String,
+    #[serde(rename = "msg")]
+    pub message: String,
+}
+
+const ERRORS_BEGIN: &str =
+    "// chio:errors:begin - generated by `chio add error`, edits here are overwritten";
+const ERRORS_END: &str = "// chio:errors:end";
+
+/// Reads the variants of `MyProgramError` from the contents of `errors.rs`.
+///
+/// Variants without an explicit discriminant get the implicit one Rust would
+/// assign. A variant's `///` doc comment is its message; undocumented
+/// variants get their name split into words.
+pub fn parse_errors(src: &str) -> Result<Vec<ErrorVariant>> {
+    let (open, close) = find_block(src, "enum MyProgramError")
+        .context("Could not find `enum MyProgramError` in errors.rs")?;
+
+    let mut variants: Vec<ErrorVariant> = Vec::new();
+    let mut doc = Vec::new();
+    for line in src[open + 1..close].lines() {
+        let line = line.trim();
+        if let Some(text) = line.strip_prefix("///") {
+            doc.push(text.trim().to_string());
+            continue;
+        }
+        let (line, _) = split_line_comment(line);
+        if line.is_empty() || line.starts_with("#[") {
+            continue;
+        }
+
+        for part in line.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, code) = match part.split_once('=') {
+                Some((name, code)) => {
+                    let code = code.trim().parse::<u32>().with_context(|| {
+                        format!(
+                            "Unsupported discriminant for MyProgramError::{}",
+                            name.trim()
+                        )
+                    })?;
+                    (name.trim(), code)
+                }
+                None => (
+                    part,
+                    variants.last().map_or(0, |v: &ErrorVariant| v.code + 1),
+                ),
+            };
+            if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                anyhow::bail!(
+                    "MyProgramError variants must be fieldless, found '{}'",
+                    part
+                );
+            }
+
+            let message = if doc.is_empty() {
+                humanize(name)
+            } else {
+                doc.join(" ")
+            };
+            variants.push(ErrorVariant {
+                code,
+                name: name.to_string(),
+                message,
+            });
+            doc.clear();
+        }
+    }
+
+    Ok(variants)
+}
+
+/// Splits a line into its code and its trailing `//` comment.
+fn split_line_comment(line: &str) -> (&str, Option<&str>) {
+    match line.find("//") {
+        Some(at) => (line[..at].trim_end(), Some(&line[at..])),
+        None => (line, None),
+    }
+}
+
+/// Rewrites the contents of `errors.rs` so every `MyProgramError` variant
+/// carries an explicit code, appends `new` if given, and regenerates the
+/// message helpers.
+pub fn patch_errors(src: &str, new: Option<&ErrorVariant>) -> Result<String> {
+    let mut variants = parse_errors(src)?;
+    if let Some(new) = new {
+        if let Some(existing) = variants
+            .iter()
+            .find(|v| v.name == new.name || v.code == new.code)
+        {
+            anyhow::bail!(
+                "MyProgramError::{} already uses {}",
+                existing.name,
+                if existing.name == new.name {
+                    "that name".to_string()
+                } else {
+                    format!("code {}", new.code)
+                }
+            );
+        }
+    }
+
+    let (open, close) = find_block(src, "enum MyProgramError")
+        .context("Could not find `enum MyProgramError` in errors.rs")?;
+
+    let mut body = String::new();
+    let mut codes = variants.iter();
+    for line in src[open + 1..close].split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with("#[") {
+            body.push_str(line);
+            continue;
+        }
+
+        let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+        let (code, comment) = split_line_comment(trimmed);
+        let count = code.split(',').filter(|p| !p.trim().is_empty()).count();
+        for i in 0..count {
+            let variant = codes
+                .next()
+                .context("MyProgramError changed while patching")?;
+            body.push_str(&format!("{}{} = {},", indent, variant.name, variant.code));
+            match comment {
+                Some(comment) if i + 1 == count => body.push_str(&format!(" {}\n", comment)),
+                _ => body.push('\n'),
+            }
+        }
+    }
+
+    if let Some(new) = new {
+        body = body.trim_end().to_string();
+        body.push_str(&format!(
+            "\n    /// {}\n    {} = {},\n",
+            new.message, new.name, new.code
+        ));
+        variants.push(new.clone());
+    }
+
+    let src = format!("{}{}{}", &src[..open + 1], body, &src[close..]);
+    let variants: Vec<_> = variants
+        .iter()
+        .map(|v| {
+            BTreeMap::from([
+                ("name".to_string(), Value::from(v.name.as_str())),
+                (
+                    "message".to_string(),
+                    Value::from(format!("{:?}", v.message)),
+                ),
+            ])
+        })
+        .collect();
+    let helpers = template::render(
+        templates::ERRORS_HELPERS_RS,
+        &TemplateContext::default().set("variants", variants),
+    )?;
+    let generated = format!("{}\n{}{}", ERRORS_BEGIN, helpers, ERRORS_END);
+
+    let src = match (src.find(ERRORS_BEGIN), src.find(ERRORS_END)) {
+        (Some(begin), Some(end)) if begin < end => format!(
+            "{}{}{}",
+            &src[..begin],
+            generated,
+            &src[end + ERRORS_END.len()..]
+        ),
+        _ => format!("{}\n\n{}\n", src.trim_end(), generated),
+    };
+
+    Ok(src)
+}
+
+/// Renders the error table clients use to map `ProgramError::Custom(n)`
+/// back to a `MyProgramError` variant.
+pub fn errors_json(variants: &[ErrorVariant]) -> Result<String> {
+    #[derive(serde::Serialize)]
+    struct ErrorTable<'a> {
+        errors: &'a [ErrorVariant],
+    }
+
+    Ok(serde_json::to_string_pretty(&ErrorTable { errors: variants })? + "\n")
+}
+
+/// Where `chio add error` writes the error table, relative to the project
+/// root. Crate names have no dots, so it never collides with the IDL that
+/// `chio idl build` writes to `idl/<crate_name>.json`.
+pub fn errors_table_path() -> PathBuf {
+    Path::new("idl").join("errors.table.json")
+}
+
+/// Appends a variant to `MyProgramError` and regenerates the error table at
+/// [`errors_table_path`].
+pub fn add_error(
+    project: &Project,
+    name: &str,
+    code: Option<u32>,
+    message: Option<&str>,
+) -> Result<Generated> {
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid_name {
+        anyhow::bail!(
+            "Invalid error name '{}'. Use PascalCase: letters and digits, starting with an uppercase letter.",
+            name
+        );
+    }
+    if message.is_some_and(|m| m.contains('\n')) {
+        anyhow::bail!("Error messages must fit on a single line");
+    }
+
+    let errors_path = Path::new("src").join("errors.rs");
+    let json_path = errors_table_path();
+    let errors_rs = read(project, &errors_path)?;
+
+    let existing = parse_errors(&errors_rs)?;
+    let code = match code {
+        Some(code) => code,
+        None => existing.iter().map(|v| v.code).max().map_or(Ok(0), |max| {
+            max.checked_add(1)
+                .context("No error code left after u32::MAX")
+        })?,
+    };
+    let variant = ErrorVariant {
+        code,
+        name: name.to_string(),
+        message: message.map_or_else(|| humanize(name), str::to_string),
+    };
+
+    let errors_rs = patch_errors(&errors_rs, Some(&variant))?;
+    let json = errors_json(&parse_errors(&errors_rs)?)?;
+
+    let json_existed = project.root.join(&json_path).exists();
+    fs::create_dir_all(project.root.join("idl"))?;
+    write(project, &errors_path, &errors_rs)?;
+    write(project, &json_path, &json)?;
+
+    let mut generated = Generated {
+        created: Vec::new(),
+        updated: vec![errors_path],
+    };
+    if json_existed {
+        generated.updated.push(json_path);
+    } else {
+        generated.created.push(json_path);
+    }
+    Ok(generated)
+}
+
+/// Splits a PascalCase name into a sentence, e.g. `PdaMismatch` becomes
+/// `Pda mismatch`.
+fn humanize(name: &str) -> String {
+    let words = to_snake_case(name).replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+fn read(project: &Project, path: &Path) -> Result<String> {
+    fs::read_to_string(project.root.join(path))
+        .with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn write(project: &Project, path: &Path, contents: &str) -> Result<()> {
+    fs::write(project.root.join(path), contents)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn validate_ident(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let starts_ok = chars.next().is_some_and(|c| c.is_ascii_lowercase());
+    let rest_ok = chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+
+    if !starts_ok || !rest_ok {
+        anyhow::bail!(
+            "Invalid name '{}'. Use snake_case: lowercase letters, digits and underscores, starting with a letter.",
+            name
+        );
+    }
+    if KEYWORDS.contains(&name) {
+        anyhow::bail!("Invalid name '{}'. Rust keywords cannot be used.", name);
+    }
+    Ok(())
+}
+
+fn register_module(src: &str, module: &str, file: &str) -> Result<String> {
+    if src
+        .lines()
+        .any(|line| line.trim() == format!("pub mod {};", module))
+    {
+        anyhow::bail!("Module '{}' is already declared in {}", module, file);
+    }
+
+    let src = insert_after_last_line(src, "pub mod ", &format!("pub mod {};", module))
+        .with_context(|| format!("Could not find any `pub mod` declaration in {}", file))?;
+    insert_after_last_line(&src, "pub use ", &format!("pub use {}::*;", module))
+        .with_context(|| format!("Could not find any `pub use` re-export in {}", file))
+}
+
+/// Inserts `line` after the last line starting with `prefix`.
+fn insert_after_last_line(src: &str, prefix: &str, line: &str) -> Option<String> {
+    let mut offset = 0;
+    let mut insert_at = None;
+    for current in src.split_inclusive('\n') {
+        offset += current.len();
+        if current.trim_start().starts_with(prefix) {
+            insert_at = Some(offset);
+        }
+    }
+
+    let insert_at = insert_at?;
+    let (head, tail) = src.split_at(insert_at);
+    if head.ends_with('\n') {
+        Some(format!("{}{}\n{}", head, line, tail))
+    } else {
+        Some(format!("{}\n{}{}", head, line, tail))
+    }
+}
+
+/// Inserts `item` as the last entry of the block closed at byte `close`,
+/// indented one level deeper than the closing brace. A trailing comma is
+/// added to the previous entry when it lacks one; entries ending in `}` only
+/// get one when `comma_after_brace` is set, as match arms with a block body
+/// do not need it.
+fn insert_block_item(src: &str, close: usize, item: &str, comma_after_brace: bool) -> String {
+    let line_start = src[..close].rfind('\n').map_or(0, |i| i + 1);
+    let closing_indent: String = src[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ')
+        .collect();
+    let indent = format!("{}    ", closing_indent);
+
+    let mut head = src[..close].trim_end().to_string();
+    let tail = &src[close..];
+    let needs_comma = match head.chars().last() {
+        Some('{') | Some(',') => false,
+        Some('}') => comma_after_brace,
+        _ => true,
+    };
+    if needs_comma {
+        head.push(',');
+    }
+
+    let item: Vec<String> = item
+        .lines()
+        .map(|line| format!("{}{}", indent, line))
+        .collect();
+
+    format!("{}\n{}\n{}{}", head, item.join("\n"), closing_indent, tail)
+}
+
+/// Finds the first `{ ... }` block following `header`, returning the byte
+/// offsets of its opening and closing braces. String literals, character
+/// literals and comments are skipped while matching braces.
+pub(crate) fn find_block(src: &str, header: &str) -> Option<(usize, usize)> {
+    let start = src.find(header)? + header.len();
+    let open = start + src[start..].find('{')?;
+
+    let bytes = src.as_bytes();
+    let mut depth = 0usize;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'\'' if bytes.get(i + 2) == Some(&b'\'') => i += 2,
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') => {
+                i += 2;
+                while i < bytes.len() && bytes[i] != b'\'' {
+                    i += 1;
+                }
+            }
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((open, i));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
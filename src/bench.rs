@@ -0,0 +1,249 @@
+//! Compute-unit benchmarks behind `chio bench`.
+//!
+//! Mollusk projects are generated with a `benches/compute_units.rs` harness
+//! that runs every instruction through `mollusk-svm-bencher`. The bencher
+//! writes its results to `benches/compute_units.md`, newest table first:
+//!
+//! ```text
+//! #### Compute Units: 2025-01-10 09:30:12.345 UTC
+//!
+//! | Name | CUs | Delta |
+//! |------|------|-------|
+//! | initialize | 1592 | -- |
+//! ```
+//!
+//! Committing that file makes it the baseline: `chio bench` compares the
+//! latest run with the table in the last commit, so a change in compute
+//! units shows up in review.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::ChioConfig;
+use crate::runner::{CommandRunner, ProcessCommand};
+use crate::scripts::{self, Script};
+use crate::TestFramework;
+
+/// The bench target generated for Mollusk projects.
+pub const BENCH_NAME: &str = "compute_units";
+
+/// Where the bencher writes its report, relative to the program's crate.
+pub fn report_path() -> PathBuf {
+    Path::new("benches").join(format!("{}.md", BENCH_NAME))
+}
+
+/// The compute units one benchmarked instruction consumed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BenchResult {
+    pub name: String,
+    pub compute_units: u64,
+}
+
+/// Parses the most recent table of a bencher report. Rows whose compute
+/// units are not a number are skipped.
+pub fn parse_report(report: &str) -> Vec<BenchResult> {
+    let mut lines = report.lines().map(str::trim);
+    let header = lines.by_ref().find(|line| {
+        let cells = cells(line);
+        cells.len() >= 2 && cells[0].eq_ignore_ascii_case("name")
+    });
+    if header.is_none() {
+        return Vec::new();
+    }
+
+    lines
+        .skip_while(|line| line.starts_with('|') && line.contains("---"))
+        .take_while(|line| line.starts_with('|'))
+        .filter_map(|line| {
+            let cells = cells(line);
+            let compute_units = cells.get(1)?.replace([',', '_'], "").parse().ok()?;
+            Some(BenchResult {
+                name: cells[0].to_string(),
+                compute_units,
+            })
+        })
+        .collect()
+}
+
+fn cells(line: &str) -> Vec<&str> {
+    line.trim_matches('|').split('|').map(str::trim).collect()
+}
+
+/// A benchmark in the latest run, the baseline, or both.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparison {
+    pub name: String,
+    /// Compute units in the latest run, `None` when the benchmark was removed.
+    pub compute_units: Option<u64>,
+    /// Compute units in the baseline, `None` when the benchmark is new.
+    pub baseline: Option<u64>,
+}
+
+impl Comparison {
+    /// Change in compute units since the baseline.
+    pub fn delta(&self) -> Option<i64> {
+        Some(self.compute_units? as i64 - self.baseline? as i64)
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.delta().is_some_and(|delta| delta > 0)
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.compute_units, self.baseline) {
+            (Some(current), Some(baseline)) => {
+                let delta = current as i64 - baseline as i64;
+                if delta == 0 {
+                    write!(f, "{} CUs, unchanged", current)
+                } else {
+                    write!(
+                        f,
+                        "{} CUs, {:+} ({:+.1}%) from {}",
+                        current,
+                        delta,
+                        delta as f64 * 100.0 / baseline.max(1) as f64,
+                        baseline
+                    )
+                }
+            }
+            (Some(current), None) => write!(f, "{} CUs, new", current),
+            (None, Some(baseline)) => write!(f, "removed, was {} CUs", baseline),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Compares the latest run with the baseline, in the order of the latest
+/// run followed by the benchmarks it no longer has.
+pub fn compare(current: &[BenchResult], baseline: &[BenchResult]) -> Vec<Comparison> {
+    let find = |results: &[BenchResult], name: &str| {
+        results
+            .iter()
+            .find(|result| result.name == name)
+            .map(|result| result.compute_units)
+    };
+
+    let mut comparisons: Vec<Comparison> = current
+        .iter()
+        .map(|result| Comparison {
+            name: result.name.clone(),
+            compute_units: Some(result.compute_units),
+            baseline: find(baseline, &result.name),
+        })
+        .collect();
+    comparisons.extend(
+        baseline
+            .iter()
+            .filter(|result| find(current, &result.name).is_none())
+            .map(|result| Comparison {
+                name: result.name.clone(),
+                compute_units: None,
+                baseline: Some(result.compute_units),
+            }),
+    );
+    comparisons
+}
+
+/// The report of the crate in `dir` as of the last commit, or `None` when it
+/// was never committed or `dir` is not in a git repository.
+pub fn committed_report(runner: &dyn CommandRunner, dir: &Path) -> Option<String> {
+    let spec = format!("HEAD:./{}", report_path().display());
+    let command = ProcessCommand::new("git", ["show", spec.as_str()]).current_dir(dir);
+    runner
+        .output(&command)
+        .ok()
+        .filter(|output| output.is_success())
+        .map(|output| output.stdout)
+}
+
+/// The benchmarks of one program compared with its baseline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramBench {
+    pub program: String,
+    /// The report, relative to the project root.
+    pub report: PathBuf,
+    /// Whether a committed report was found to compare with.
+    pub has_baseline: bool,
+    pub comparisons: Vec<Comparison>,
+}
+
+/// Runs the bench script in `root`, limited to the selected workspace
+/// `program`, then compares the report of every Mollusk program with the one
+/// in the last commit.
+pub fn run_bench(
+    runner: &dyn CommandRunner,
+    root: &Path,
+    program: Option<&str>,
+) -> Result<Vec<ProgramBench>> {
+    let programs = bench_programs(root, program)?;
+    scripts::run_script(runner, root, Script::Bench, program)?;
+
+    programs
+        .into_iter()
+        .map(|(name, dir)| {
+            let report = dir.join(report_path());
+            let current = fs::read_to_string(root.join(&report)).with_context(|| {
+                format!(
+                    "{} was not written. Check that {} is registered as a [[bench]] with harness = false.",
+                    report.display(),
+                    Path::new("benches").join(format!("{}.rs", BENCH_NAME)).display()
+                )
+            })?;
+            let baseline = committed_report(runner, &root.join(&dir));
+            let comparisons = compare(
+                &parse_report(&current),
+                &baseline.as_deref().map(parse_report).unwrap_or_default(),
+            );
+            Ok(ProgramBench {
+                program: name,
+                report,
+                has_baseline: baseline.is_some(),
+                comparisons,
+            })
+        })
+        .collect()
+}
+
+/// The Mollusk programs to benchmark, with their crate directories relative
+/// to `root`.
+fn bench_programs(root: &Path, program: Option<&str>) -> Result<Vec<(String, PathBuf)>> {
+    let config = ChioConfig::load(root)?;
+    let programs: Vec<(String, TestFramework, PathBuf)> = match &config {
+        Some(config) if config.is_workspace() => {
+            let members = match program {
+                Some(name) => vec![config.member(name)?],
+                None => config.program_configs(),
+            };
+            members
+                .into_iter()
+                .map(|member| {
+                    let dir = Path::new("programs").join(&member.name);
+                    (member.name.clone(), member.test_framework, dir)
+                })
+                .collect()
+        }
+        Some(config) => config
+            .program_configs()
+            .into_iter()
+            .map(|program| (program.name.clone(), program.test_framework, PathBuf::new()))
+            .collect(),
+        None => vec![(".".to_string(), TestFramework::Mollusk, PathBuf::new())],
+    };
+
+    let mollusk: Vec<(String, PathBuf)> = programs
+        .into_iter()
+        .filter(|(_, test_framework, _)| *test_framework == TestFramework::Mollusk)
+        .map(|(name, _, dir)| (name, dir))
+        .collect();
+    if mollusk.is_empty() {
+        anyhow::bail!(
+            "'chio bench' measures compute units with mollusk-svm-bencher, \
+            and no program here is tested with Mollusk"
+        );
+    }
+    Ok(mollusk)
+}
@@ -0,0 +1,240 @@
+//! Options for `chio build`, forwarded to `cargo build-sbf`.
+//!
+//! The same options live under `[build]` in `Chio.toml`, so everyone on a
+//! project builds with the same features, architecture and cargo arguments.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{ChioConfig, CONFIG_FILE};
+use crate::runner::{self, CommandRunner, ProcessCommand};
+use crate::scripts::{self, Script};
+
+/// Where `cargo build-sbf` writes programs when `--sbf-out-dir` is not set.
+pub const DEFAULT_DEPLOY_DIR: &str = "target/deploy";
+
+/// SBF architecture passed to `cargo build-sbf --arch`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SbfArch {
+    Sbfv1,
+    Sbfv2,
+}
+
+impl SbfArch {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Sbfv1 => "sbfv1",
+            Self::Sbfv2 => "sbfv2",
+        }
+    }
+}
+
+/// Options forwarded to `cargo build-sbf`. Anything left unset falls back to
+/// `[build]` in `Chio.toml`.
+#[derive(Clone, Debug, Default, PartialEq, clap::Args, Serialize, Deserialize)]
+pub struct BuildOptions {
+    /// Cargo features to enable (comma separated or repeatable)
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    /// Do not enable the default features
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub no_default_features: bool,
+    /// SBF architecture to build for
+    #[arg(long, value_enum)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arch: Option<SbfArch>,
+    /// Directory the program and its keypair are written to, target/deploy by default (not saved)
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sbf_out_dir: Option<PathBuf>,
+    /// Cargo.toml of the program to build
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_path: Option<PathBuf>,
+    /// Build with debug information
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub debug: bool,
+    /// Build without debug information, even when Chio.toml enables it
+    #[arg(long, conflicts_with = "debug")]
+    #[serde(skip)]
+    pub release: bool,
+    /// Fail the build when a program .so is larger than this many bytes
+    #[arg(long, value_name = "BYTES")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
+    /// Extra arguments passed to cargo after `--`
+    #[arg(last = true)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl BuildOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fills the options not given on the command line from `Chio.toml`.
+    /// Configured flags stay on, except `debug` under `--release`.
+    pub fn or_config(&self, config: &ChioConfig) -> Self {
+        let defaults = &config.build;
+        let mut options = self.clone();
+        if options.features.is_empty() {
+            options.features = defaults.features.clone();
+        }
+        options.no_default_features |= defaults.no_default_features;
+        if options.arch.is_none() {
+            options.arch = defaults.arch;
+        }
+        if options.sbf_out_dir.is_none() {
+            options.sbf_out_dir = defaults.sbf_out_dir.clone();
+        }
+        if options.manifest_path.is_none() {
+            options.manifest_path = defaults.manifest_path.clone();
+        }
+        options.debug |= defaults.debug && !options.release;
+        if options.max_size.is_none() {
+            options.max_size = defaults.max_size;
+        }
+        if options.args.is_empty() {
+            options.args = defaults.args.clone();
+        }
+        options
+    }
+
+    /// Directory the built programs end up in, relative to the project root.
+    pub fn deploy_dir(&self) -> PathBuf {
+        self.sbf_out_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DEPLOY_DIR))
+    }
+
+    /// Directory cargo builds into, relative to the project root: the
+    /// `--target-dir` passed to cargo after `--`, else `CARGO_TARGET_DIR`,
+    /// else `target`.
+    pub fn target_dir(&self) -> PathBuf {
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            if let Some(dir) = arg.strip_prefix("--target-dir=") {
+                return PathBuf::from(dir);
+            }
+            if arg == "--target-dir" {
+                if let Some(dir) = args.next() {
+                    return PathBuf::from(dir);
+                }
+            }
+        }
+        std::env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("target"))
+    }
+
+    /// The arguments appended to the build script. `max_size` is checked by
+    /// chio after the build and not passed on.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if let Some(arch) = self.arch {
+            args.push("--arch".to_string());
+            args.push(arch.as_str().to_string());
+        }
+        if let Some(dir) = &self.sbf_out_dir {
+            args.push("--sbf-out-dir".to_string());
+            args.push(dir.display().to_string());
+        }
+        if let Some(manifest) = &self.manifest_path {
+            args.push("--manifest-path".to_string());
+            args.push(manifest.display().to_string());
+        }
+        if self.debug {
+            args.push("--debug".to_string());
+        }
+        if !self.args.is_empty() {
+            args.push("--".to_string());
+            args.extend(self.args.iter().cloned());
+        }
+        args
+    }
+}
+
+/// The options `chio build` runs with in `root`: `options` on top of the
+/// `[build]` section of `Chio.toml`.
+pub fn resolve_options(root: &Path, options: &BuildOptions) -> Result<BuildOptions> {
+    Ok(match ChioConfig::load(root)? {
+        Some(config) => options.or_config(&config),
+        None => options.clone(),
+    })
+}
+
+/// The directory `chio build` writes programs to in `root`.
+pub fn deploy_dir(root: &Path) -> Result<PathBuf> {
+    Ok(resolve_options(root, &BuildOptions::default())?.deploy_dir())
+}
+
+/// The build script of `root` with `options` appended, limited to the
+/// selected workspace `program`.
+pub fn build_process(
+    root: &Path,
+    program: Option<&str>,
+    options: &BuildOptions,
+) -> Result<ProcessCommand> {
+    let options = resolve_options(root, options)?;
+    if program.is_some() && options.manifest_path.is_some() {
+        anyhow::bail!(
+            "--manifest-path cannot be combined with --program, which already selects the manifest"
+        );
+    }
+
+    let mut command = scripts::script_process(root, Script::Build, program)?;
+    command
+        .args
+        .extend(options.args().into_iter().map(Into::into));
+    Ok(command)
+}
+
+/// Runs the build script of `root` with `options`, failing when it does.
+pub fn run_build(
+    runner: &dyn CommandRunner,
+    root: &Path,
+    program: Option<&str>,
+    options: &BuildOptions,
+) -> Result<()> {
+    let command = build_process(root, program, options)?;
+    runner::run_checked(runner, &command, "Build")
+}
+
+/// Records `options`, on top of the current defaults, as the project's build
+/// defaults in `Chio.toml`. An output directory other than `target/deploy` is
+/// refused: the program keypair, the generated tests and benches all expect
+/// the programs there.
+pub fn save_defaults(root: &Path, options: &BuildOptions) -> Result<()> {
+    let Some(mut config) = ChioConfig::load(root)? else {
+        anyhow::bail!("No {} found; run 'chio init' first", CONFIG_FILE);
+    };
+    let defaults = BuildOptions {
+        release: false,
+        ..options.or_config(&config)
+    };
+    if defaults.deploy_dir() != Path::new(DEFAULT_DEPLOY_DIR) {
+        anyhow::bail!(
+            "--sbf-out-dir cannot be saved: the program keypair and the generated tests expect programs in {}. Pass it to each build instead.",
+            DEFAULT_DEPLOY_DIR
+        );
+    }
+    config.build = defaults;
+    config.save(root)
+}
@@ -0,0 +1,594 @@
+//! Client generation behind `chio client generate`.
+//!
+//! Clients are rendered from the same [`Idl`] `chio idl build` writes, so
+//! they follow the program source: every `ProgramInstruction` gets a builder,
+//! every state struct a decoder and, when it has a `SEED`, a PDA finder, and
+//! every `MyProgramError` variant its code and message.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::add::{self, FieldType, Generated, Project};
+use crate::content::templates::clients;
+use crate::idl::{to_camel_case, Idl, IdlAccount, IdlAccountMeta, IdlStruct, PdaSeed};
+use crate::template::{self, TemplateContext, Value};
+
+/// A language `chio client generate` writes a client in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Language {
+    Rust,
+    /// TypeScript, built on @solana/kit
+    Js,
+}
+
+/// Generates the client of `project` in `language`.
+pub fn generate(project: &Project, language: Language) -> Result<Generated> {
+    match language {
+        Language::Rust => generate_rust(project),
+        Language::Js => generate_js(project),
+    }
+}
+
+/// Where the Rust client is generated, relative to the project root.
+pub fn rust_client_dir() -> PathBuf {
+    Path::new("clients").join("rust")
+}
+
+/// Renders the files of the Rust client crate, relative to its directory.
+pub fn render_rust_client(idl: &Idl) -> Result<Vec<(PathBuf, String)>> {
+    let types = idl
+        .instructions
+        .iter()
+        .filter_map(|instruction| {
+            let args = instruction.args.as_ref()?;
+            let doc = format!(
+                "Data of `ProgramInstruction::{}`, following its discriminator.",
+                instruction.name
+            );
+            Some(
+                rust_struct(args, &doc, &[])
+                    .map(|code| item([("code", code.trim_end().to_string())])),
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let instructions = idl
+        .instructions
+        .iter()
+        .map(|instruction| {
+            let accounts: Vec<_> = instruction.accounts.iter().map(rust_account_meta).collect();
+            let docs = instruction_docs(&instruction.name, &instruction.docs);
+            let mut params = vec![format!(
+                "{}: &{}Accounts",
+                if accounts.is_empty() {
+                    "_accounts"
+                } else {
+                    "accounts"
+                },
+                instruction.name
+            )];
+            if let Some(args) = &instruction.args {
+                params.push(format!("args: &{}", args.name));
+            }
+            BTreeMap::from([
+                ("name".to_string(), Value::from(instruction.name.as_str())),
+                (
+                    "fn_name".to_string(),
+                    add::to_snake_case(&instruction.name).into(),
+                ),
+                (
+                    "const_name".to_string(),
+                    add::to_snake_case(&instruction.name)
+                        .to_ascii_uppercase()
+                        .into(),
+                ),
+                (
+                    "discriminator".to_string(),
+                    instruction.discriminator.to_string().into(),
+                ),
+                ("docs".to_string(), doc_lines(&docs).into()),
+                ("params".to_string(), params.join(", ").into()),
+                ("accounts".to_string(), accounts.into()),
+                (
+                    "args".to_string(),
+                    instruction
+                        .args
+                        .as_ref()
+                        .map_or_else(String::new, |args| args.name.clone())
+                        .into(),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let has_accounts = idl.instructions.iter().any(|ix| !ix.accounts.is_empty());
+    let instructions_rs = template::render(
+        clients::rust::INSTRUCTIONS_RS,
+        &TemplateContext::new(&idl.name)
+            .with_feature("account_metas", has_accounts)
+            .with_feature(
+                "pubkey",
+                has_accounts
+                    || has_pubkey_field(idl.instructions.iter().filter_map(|ix| ix.args.as_ref())),
+            )
+            .set("types", types)
+            .set("instructions", instructions),
+    )?;
+
+    let accounts = idl
+        .accounts
+        .iter()
+        .map(|account| {
+            rust_struct(&account.ty, &account_doc(account), &account.seeds)
+                .map(|code| item([("code", code.trim_end().to_string())]))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let accounts_rs = template::render(
+        clients::rust::ACCOUNTS_RS,
+        &TemplateContext::new(&idl.name)
+            .with_feature(
+                "pubkey",
+                idl.accounts.iter().any(|account| !account.seeds.is_empty())
+                    || has_pubkey_field(idl.accounts.iter().map(|account| &account.ty)),
+            )
+            .set("accounts", accounts),
+    )?;
+
+    let errors = idl
+        .errors
+        .iter()
+        .map(|error| {
+            item([
+                ("name", error.name.clone()),
+                ("code", error.code.to_string()),
+                ("doc", error.message.clone()),
+                ("message", format!("{:?}", error.message)),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let errors_rs = template::render(
+        clients::rust::ERRORS_RS,
+        &TemplateContext::new(&idl.name).set("errors", errors),
+    )?;
+
+    let context = TemplateContext::new(&idl.name)
+        .with_program_id(&idl.address)
+        .set("version", idl.version.as_str());
+    let src = Path::new("src");
+    Ok(vec![
+        (
+            PathBuf::from("Cargo.toml"),
+            template::render(clients::rust::CARGO_TOML, &context)?,
+        ),
+        (
+            PathBuf::from(".gitignore"),
+            clients::rust::GITIGNORE.to_string(),
+        ),
+        (
+            src.join("lib.rs"),
+            template::render(clients::rust::LIB_RS, &context)?,
+        ),
+        (src.join("instructions.rs"), instructions_rs),
+        (src.join("accounts.rs"), accounts_rs),
+        (src.join("errors.rs"), errors_rs),
+    ])
+}
+
+/// Generates the Rust client of `project` in [`rust_client_dir`].
+pub fn generate_rust(project: &Project) -> Result<Generated> {
+    write_files(
+        project,
+        &rust_client_dir(),
+        render_rust_client(&Idl::parse(project)?)?,
+    )
+}
+
+/// Where the TypeScript client is generated, relative to the project root.
+pub fn js_client_dir() -> PathBuf {
+    Path::new("clients").join("js")
+}
+
+/// Renders the files of the TypeScript client package, relative to its
+/// directory. Nothing is installed or compiled, so no network is needed.
+pub fn render_js_client(idl: &Idl) -> Result<Vec<(PathBuf, String)>> {
+    let types = idl
+        .instructions
+        .iter()
+        .filter_map(|instruction| {
+            let args = instruction.args.as_ref()?;
+            let doc = format!(
+                "Data of `ProgramInstruction::{}`, following its discriminator.",
+                instruction.name
+            );
+            Some(
+                js_struct(args, &doc, &[])
+                    .map(|code| item([("code", code.trim_end().to_string())])),
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let instructions = idl
+        .instructions
+        .iter()
+        .map(|instruction| {
+            let accounts: Vec<_> = instruction.accounts.iter().map(js_account_meta).collect();
+            let mut params = vec![format!("accounts: {}Accounts", instruction.name)];
+            let mut data = vec![format!(
+                "{}_DISCRIMINATOR",
+                add::to_snake_case(&instruction.name).to_ascii_uppercase()
+            )];
+            if let Some(args) = &instruction.args {
+                params.push(format!("args: {}", args.name));
+                data.push(format!("...encode{}(args)", args.name));
+            }
+            BTreeMap::from([
+                ("name".to_string(), Value::from(instruction.name.as_str())),
+                (
+                    "const_name".to_string(),
+                    add::to_snake_case(&instruction.name)
+                        .to_ascii_uppercase()
+                        .into(),
+                ),
+                (
+                    "discriminator".to_string(),
+                    instruction.discriminator.to_string().into(),
+                ),
+                (
+                    "docs".to_string(),
+                    doc_lines(&instruction_docs(&instruction.name, &instruction.docs)).into(),
+                ),
+                ("params".to_string(), params.join(", ").into()),
+                ("data".to_string(), data.join(", ").into()),
+                ("accounts".to_string(), accounts.into()),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let instructions_ts = template::render(
+        clients::js::INSTRUCTIONS_TS,
+        &TemplateContext::new(&idl.name)
+            .set("types", types)
+            .set("instructions", instructions),
+    )?;
+
+    let accounts = idl
+        .accounts
+        .iter()
+        .map(|account| {
+            js_struct(&account.ty, &account_doc(account), &account.seeds)
+                .map(|code| item([("code", code.trim_end().to_string())]))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let accounts_ts = template::render(
+        clients::js::ACCOUNTS_TS,
+        &TemplateContext::new(&idl.name).set("accounts", accounts),
+    )?;
+
+    let errors = idl
+        .errors
+        .iter()
+        .map(|error| {
+            item([
+                ("name", error.name.clone()),
+                ("code", error.code.to_string()),
+                ("doc", error.message.clone()),
+                // JSON strings are valid TypeScript string literals.
+                (
+                    "message",
+                    serde_json::Value::from(error.message.as_str()).to_string(),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let errors_ts = template::render(
+        clients::js::ERRORS_TS,
+        &TemplateContext::new(&idl.name).set("errors", errors),
+    )?;
+
+    let context = TemplateContext::new(&idl.name)
+        .with_program_id(&idl.address)
+        .set(
+            "package_name",
+            format!("{}-client", idl.name.replace('_', "-")),
+        )
+        .set("version", idl.version.as_str());
+    let src = Path::new("src");
+    Ok(vec![
+        (
+            PathBuf::from("package.json"),
+            template::render(clients::js::PACKAGE_JSON, &context)?,
+        ),
+        (
+            PathBuf::from("tsconfig.json"),
+            clients::js::TSCONFIG_JSON.to_string(),
+        ),
+        (
+            PathBuf::from(".gitignore"),
+            clients::js::GITIGNORE.to_string(),
+        ),
+        (
+            src.join("index.ts"),
+            template::render(clients::js::INDEX_TS, &context)?,
+        ),
+        (
+            src.join("program.ts"),
+            template::render(clients::js::PROGRAM_TS, &context)?,
+        ),
+        (src.join("instructions.ts"), instructions_ts),
+        (src.join("accounts.ts"), accounts_ts),
+        (src.join("errors.ts"), errors_ts),
+    ])
+}
+
+/// Generates the TypeScript client of `project` in [`js_client_dir`].
+pub fn generate_js(project: &Project) -> Result<Generated> {
+    write_files(
+        project,
+        &js_client_dir(),
+        render_js_client(&Idl::parse(project)?)?,
+    )
+}
+
+/// Writes generated files to `dir`, reporting which ones were new.
+fn write_files(project: &Project, dir: &Path, files: Vec<(PathBuf, String)>) -> Result<Generated> {
+    let mut generated = Generated::default();
+    for (path, contents) in files {
+        let path = dir.join(path);
+        let full_path = project.root.join(&path);
+        let existed = full_path.exists();
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&full_path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        if existed {
+            generated.updated.push(path);
+        } else {
+            generated.created.push(path);
+        }
+    }
+    Ok(generated)
+}
+
+/// Renders a struct with its encoder and decoder, and a PDA finder when it
+/// has `seeds`.
+fn rust_struct(ty: &IdlStruct, doc: &str, seeds: &[PdaSeed]) -> Result<String> {
+    let (offsets, len) = ty.layout();
+    let fields = ty
+        .fields
+        .iter()
+        .zip(offsets)
+        .map(|(field, start)| {
+            let end = start + field.ty.size();
+            let range = format!("data[{}..{}]", start, end);
+            let (ty, encode, decode) = match field.ty {
+                FieldType::Pubkey => (
+                    "Pubkey".to_string(),
+                    format!("self.{}.as_ref()", field.name),
+                    format!("Pubkey::new_from_array({}.try_into().unwrap())", range),
+                ),
+                FieldType::Bytes(_) => (
+                    field.ty.rust_type(),
+                    format!("&self.{}", field.name),
+                    format!("{}.try_into().unwrap()", range),
+                ),
+                other => (
+                    other.rust_type(),
+                    format!("&self.{}.to_le_bytes()", field.name),
+                    format!(
+                        "{}::from_le_bytes({}.try_into().unwrap())",
+                        other.rust_type(),
+                        range
+                    ),
+                ),
+            };
+            item([
+                ("name", field.name.clone()),
+                ("ty", ty),
+                ("start", start.to_string()),
+                ("end", end.to_string()),
+                ("encode", encode),
+                ("decode", decode),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let mut context = TemplateContext::new(&ty.name)
+        .set("name", ty.name.as_str())
+        .set("doc", doc)
+        .set("len", len.to_string())
+        .set("fields", fields)
+        .set("seed", "")
+        .set("seed_params", "")
+        .set("seed_exprs", "");
+    if let Some(PdaSeed::Constant(seed)) = seeds.first() {
+        let keys: Vec<&str> = pubkey_seeds(seeds).collect();
+        let params: Vec<String> = keys.iter().map(|key| format!("{}: &Pubkey", key)).collect();
+        let exprs: Vec<String> = std::iter::once("Self::SEED.as_bytes()".to_string())
+            .chain(keys.iter().map(|key| format!("{}.as_ref()", key)))
+            .collect();
+        context = context
+            .set("seed", format!("{:?}", seed))
+            .set("seed_params", params.join(", "))
+            .set("seed_exprs", exprs.join(", "));
+    }
+    template::render(clients::rust::STRUCT_RS, &context)
+}
+
+fn rust_account_meta(account: &IdlAccountMeta) -> BTreeMap<String, Value> {
+    let new = if account.writable {
+        "AccountMeta::new"
+    } else {
+        "AccountMeta::new_readonly"
+    };
+    // Shank passes the program id in place of an omitted optional account.
+    let (ty, meta) = if account.optional {
+        (
+            "Option<Pubkey>".to_string(),
+            format!(
+                "accounts.{}.map_or(AccountMeta::new_readonly(crate::ID, false), |key| {}(key, {}))",
+                account.name, new, account.signer
+            ),
+        )
+    } else {
+        (
+            "Pubkey".to_string(),
+            format!("{}(accounts.{}, {})", new, account.name, account.signer),
+        )
+    };
+    item([
+        ("name", account.name.clone()),
+        ("doc", account.docs.join(" ")),
+        ("ty", ty),
+        ("meta", meta),
+    ])
+}
+
+/// Renders a struct type with its encoder and decoder, and a PDA finder when
+/// it has `seeds`.
+fn js_struct(ty: &IdlStruct, doc: &str, seeds: &[PdaSeed]) -> Result<String> {
+    let (offsets, len) = ty.layout();
+    let fields = ty
+        .fields
+        .iter()
+        .zip(offsets)
+        .map(|(field, start)| {
+            let name = to_camel_case(&field.name);
+            let end = start + field.ty.size();
+            let (ty, encode, decode) = match field.ty {
+                FieldType::Pubkey => (
+                    "Address".to_string(),
+                    format!("data.set(addressEncoder.encode(value.{}), {})", name, start),
+                    format!("addressDecoder.decode(data.subarray({}, {}))", start, end),
+                ),
+                FieldType::Bytes(len) => (
+                    "Uint8Array".to_string(),
+                    format!(
+                        "checkSize(\"{}.{}\", value.{}, {});\n  data.set(value.{}, {})",
+                        ty.name, name, name, len, name, start
+                    ),
+                    format!("data.slice({}, {})", start, end),
+                ),
+                other => {
+                    let (ty, accessor, little_endian) = match other {
+                        FieldType::U8 => ("number", "Uint8", ""),
+                        FieldType::I8 => ("number", "Int8", ""),
+                        FieldType::U16 => ("number", "Uint16", ", true"),
+                        FieldType::I16 => ("number", "Int16", ", true"),
+                        FieldType::U32 => ("number", "Uint32", ", true"),
+                        FieldType::I32 => ("number", "Int32", ", true"),
+                        FieldType::U64 => ("bigint", "BigUint64", ", true"),
+                        _ => ("bigint", "BigInt64", ", true"),
+                    };
+                    (
+                        ty.to_string(),
+                        format!(
+                            "view.set{}({}, value.{}{})",
+                            accessor, start, name, little_endian
+                        ),
+                        format!("view.get{}({}{})", accessor, start, little_endian),
+                    )
+                }
+            };
+            item([
+                ("name", name),
+                ("ty", ty),
+                ("encode", encode),
+                ("decode", decode),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let const_name = add::to_snake_case(&ty.name).to_ascii_uppercase();
+    let mut context = TemplateContext::new(&ty.name)
+        .set("name", ty.name.as_str())
+        .set("const_name", const_name.as_str())
+        .set("doc", doc)
+        .set("len", len.to_string())
+        .set("fields", fields)
+        .set("seed", "")
+        .set("seed_params", "")
+        .set("seed_exprs", "");
+    if let Some(PdaSeed::Constant(seed)) = seeds.first() {
+        let keys: Vec<String> = pubkey_seeds(seeds).map(to_camel_case).collect();
+        let params: Vec<String> = keys.iter().map(|key| format!("{}: Address", key)).collect();
+        let exprs: Vec<String> = std::iter::once(format!("{}_SEED", const_name))
+            .chain(
+                keys.iter()
+                    .map(|key| format!("addressEncoder.encode({})", key)),
+            )
+            .collect();
+        context = context
+            .set("seed", serde_json::Value::from(seed.as_str()).to_string())
+            .set("seed_params", params.join(", "))
+            .set("seed_exprs", exprs.join(", "));
+    }
+    template::render(clients::js::STRUCT_TS, &context)
+}
+
+fn js_account_meta(account: &IdlAccountMeta) -> BTreeMap<String, Value> {
+    let name = to_camel_case(&account.name);
+    let role = match (account.writable, account.signer) {
+        (true, true) => "AccountRole.WRITABLE_SIGNER",
+        (true, false) => "AccountRole.WRITABLE",
+        (false, true) => "AccountRole.READONLY_SIGNER",
+        (false, false) => "AccountRole.READONLY",
+    };
+    // Shank passes the program id in place of an omitted optional account.
+    let (decl, meta) = if account.optional {
+        (
+            format!("{}?: Address", name),
+            format!(
+                "accounts.{name} ? {{ address: accounts.{name}, role: {role} }} : {{ address: PROGRAM_ADDRESS, role: AccountRole.READONLY }}"
+            ),
+        )
+    } else {
+        (
+            format!("{}: Address", name),
+            format!("{{ address: accounts.{}, role: {} }}", name, role),
+        )
+    };
+    item([
+        ("doc", account.docs.join(" ")),
+        ("decl", decl),
+        ("meta", meta),
+    ])
+}
+
+fn pubkey_seeds(seeds: &[PdaSeed]) -> impl Iterator<Item = &str> {
+    seeds.iter().filter_map(|seed| match seed {
+        PdaSeed::Pubkey(name) => Some(name.as_str()),
+        PdaSeed::Constant(_) => None,
+    })
+}
+
+/// The docs of an instruction builder, naming the variant when the source
+/// has none.
+fn instruction_docs(name: &str, docs: &[String]) -> Vec<String> {
+    match docs.is_empty() {
+        true => vec![format!("Builds `ProgramInstruction::{}`.", name)],
+        false => docs.to_vec(),
+    }
+}
+
+fn account_doc(account: &IdlAccount) -> String {
+    match account.docs.is_empty() {
+        true => format!("A `{}` account.", account.ty.name),
+        false => account.docs.join(" "),
+    }
+}
+
+fn has_pubkey_field<'a>(mut structs: impl Iterator<Item = &'a IdlStruct>) -> bool {
+    structs.any(|ty| ty.fields.iter().any(|field| field.ty == FieldType::Pubkey))
+}
+
+fn doc_lines(docs: &[String]) -> Vec<BTreeMap<String, Value>> {
+    docs.iter()
+        .map(|line| item([("text", line.clone())]))
+        .collect()
+}
+
+fn item<const N: usize>(fields: [(&str, String); N]) -> BTreeMap<String, Value> {
+    fields
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), Value::from(value)))
+        .collect()
+}
@@ -0,0 +1,313 @@
+//! Project configuration stored in `Chio.toml`.
+//!
+//! `chio init` writes the file and every other command reads it, so a
+//! project carries its own cluster, keypairs and commands instead of relying
+//! on the global `solana config`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::build::BuildOptions;
+use crate::deploy::Cluster;
+use crate::name::crate_name;
+use crate::TestFramework;
+
+pub const CONFIG_FILE: &str = "Chio.toml";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChioConfig {
+    /// The program of a single-crate project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program: Option<ProgramConfig>,
+    /// The programs of a workspace project, under `programs/<name>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceConfig>,
+    #[serde(default)]
+    pub provider: ProviderConfig,
+    #[serde(default)]
+    pub scripts: ScriptsConfig,
+    /// Options `chio build` passes to `cargo build-sbf` unless overridden.
+    #[serde(default, skip_serializing_if = "BuildOptions::is_empty")]
+    pub build: BuildOptions,
+    /// Deployed program ids, keyed by cluster and then by program name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub programs: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProgramConfig {
+    pub name: String,
+    pub test_framework: TestFramework,
+    /// Program keypair, relative to the project root.
+    pub keypair: PathBuf,
+}
+
+impl ProgramConfig {
+    pub fn new(name: &str, test_framework: TestFramework) -> Self {
+        Self {
+            name: name.to_string(),
+            test_framework,
+            keypair: Path::new("target")
+                .join("deploy")
+                .join(format!("{}-keypair.json", crate_name(name))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    #[serde(default)]
+    pub members: Vec<ProgramConfig>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    /// Cluster `chio deploy` targets when `--cluster` is not passed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<Cluster>,
+    /// Fee payer keypair `chio deploy` uses when `--keypair` is not passed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<PathBuf>,
+}
+
+/// Commands run by `chio build`, `chio test` and `chio bench`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScriptsConfig {
+    #[serde(default = "default_build_script")]
+    pub build: String,
+    #[serde(default = "default_test_script")]
+    pub test: String,
+    #[serde(default = "default_bench_script")]
+    pub bench: String,
+}
+
+impl Default for ScriptsConfig {
+    fn default() -> Self {
+        Self {
+            build: default_build_script(),
+            test: default_test_script(),
+            bench: default_bench_script(),
+        }
+    }
+}
+
+fn default_build_script() -> String {
+    "cargo build-sbf".to_string()
+}
+
+fn default_test_script() -> String {
+    "cargo test".to_string()
+}
+
+fn default_bench_script() -> String {
+    format!("cargo bench --bench {}", crate::bench::BENCH_NAME)
+}
+
+impl ChioConfig {
+    /// The configuration `chio init` writes for a new program.
+    pub fn new(name: &str, test_framework: TestFramework, program_id: &str) -> Self {
+        let mut config = Self::empty();
+        config.program = Some(ProgramConfig::new(name, test_framework));
+        config.record_program_id(&Cluster::Localnet, name, program_id);
+        config
+    }
+
+    /// The configuration `chio init --workspace` writes, before any program
+    /// is added.
+    pub fn new_workspace() -> Self {
+        let mut config = Self::empty();
+        config.workspace = Some(WorkspaceConfig::default());
+        config
+    }
+
+    fn empty() -> Self {
+        Self {
+            program: None,
+            workspace: None,
+            provider: ProviderConfig {
+                cluster: Some(Cluster::Localnet),
+                wallet: None,
+            },
+            scripts: ScriptsConfig::default(),
+            build: BuildOptions::default(),
+            programs: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_workspace(&self) -> bool {
+        self.workspace.is_some()
+    }
+
+    /// Every program of the project: the single program, or the workspace
+    /// members in the order they were added.
+    pub fn program_configs(&self) -> Vec<&ProgramConfig> {
+        let members = self.workspace.iter().flat_map(|ws| ws.members.iter());
+        self.program.iter().chain(members).collect()
+    }
+
+    /// Looks up a program by name, treating hyphens and underscores alike.
+    pub fn program_config(&self, name: &str) -> Option<&ProgramConfig> {
+        let name = crate_name(name);
+        self.program_configs()
+            .into_iter()
+            .find(|program| crate_name(&program.name) == name)
+    }
+
+    /// Like [`ChioConfig::program_config`], failing with the list of
+    /// programs when there is no such program.
+    pub fn member(&self, name: &str) -> Result<&ProgramConfig> {
+        self.program_config(name).with_context(|| {
+            let names: Vec<&str> = self
+                .program_configs()
+                .iter()
+                .map(|program| program.name.as_str())
+                .collect();
+            format!(
+                "No program '{}' in this workspace. Programs: {}",
+                name,
+                names.join(", ")
+            )
+        })
+    }
+
+    /// Registers a new workspace member and its localnet program id.
+    pub fn add_member(&mut self, program: ProgramConfig, program_id: &str) -> Result<()> {
+        if self.program_config(&program.name).is_some() {
+            anyhow::bail!(
+                "Program '{}' already exists in {}",
+                program.name,
+                CONFIG_FILE
+            );
+        }
+        let workspace = self
+            .workspace
+            .as_mut()
+            .with_context(|| format!("{} does not describe a workspace", CONFIG_FILE))?;
+
+        self.programs
+            .entry(Cluster::Localnet.to_string())
+            .or_default()
+            .insert(program.name.clone(), program_id.to_string());
+        workspace.members.push(program);
+        Ok(())
+    }
+
+    /// Reads `Chio.toml` from `root`, returning `None` for projects created
+    /// before it existed.
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config =
+            toml::from_str(&contents).with_context(|| format!("Invalid {}", path.display()))?;
+        Ok(Some(config))
+    }
+
+    /// Writes the config to `Chio.toml` in `root`. An existing file is
+    /// edited in place, so only the keys that changed are touched and its
+    /// comments and layout survive.
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = root.join(CONFIG_FILE);
+        let mut contents = self.to_toml()?;
+        if path.exists() {
+            let existing = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            contents = update_toml(&existing, &contents)
+                .with_context(|| format!("Invalid {}", path.display()))?;
+        }
+        fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Records the id `name` was deployed with on `cluster`.
+    pub fn record_program_id(&mut self, cluster: &Cluster, name: &str, program_id: &str) {
+        self.programs
+            .entry(cluster.to_string())
+            .or_default()
+            .insert(name.to_string(), program_id.to_string());
+    }
+}
+
+/// Rewrites the TOML document `existing` to hold the values of `updated`,
+/// keeping the comments and formatting of everything left unchanged.
+fn update_toml(existing: &str, updated: &str) -> Result<String> {
+    let mut document: DocumentMut = existing.parse()?;
+    let updated: DocumentMut = updated.parse()?;
+    update_item(document.as_item_mut(), updated.as_item());
+    Ok(document.to_string())
+}
+
+fn update_item(existing: &mut Item, updated: &Item) {
+    if let (Some(existing), Some(updated)) = (existing.as_table_like_mut(), updated.as_table_like())
+    {
+        return update_table(existing, updated);
+    }
+
+    // Arrays of tables such as `[[workspace.members]]` are updated entry by
+    // entry, so comments inside the entries that stay are kept.
+    if let (Some(existing), Some(updated)) = (
+        existing.as_array_of_tables_mut(),
+        updated.as_array_of_tables(),
+    ) {
+        while existing.len() > updated.len() {
+            existing.remove(existing.len() - 1);
+        }
+        for (index, table) in updated.iter().enumerate() {
+            match existing.get_mut(index) {
+                Some(current) => update_table(current, table),
+                None => existing.push(table.clone()),
+            }
+        }
+        return;
+    }
+
+    if let (Item::Value(current), Item::Value(value)) = (&mut *existing, updated) {
+        if current.to_string().trim() != value.to_string().trim() {
+            let decor = current.decor().clone();
+            *current = value.clone();
+            *current.decor_mut() = decor;
+        }
+        return;
+    }
+    *existing = updated.clone();
+}
+
+fn update_table(existing: &mut dyn TableLike, updated: &dyn TableLike) {
+    let removed: Vec<String> = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !updated.contains_key(key))
+        .collect();
+    for key in removed {
+        existing.remove(&key);
+    }
+    for (key, item) in updated.iter() {
+        match existing.get_mut(key) {
+            Some(current) => update_item(current, item),
+            None => {
+                existing.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+/// Splits a script into a program and its arguments. Arguments are separated
+/// by whitespace; quoting is not supported.
+pub fn script_command(script: &str) -> Result<(String, Vec<String>)> {
+    let mut words = script.split_whitespace().map(str::to_string);
+    let program = words
+        .next()
+        .with_context(|| format!("Empty script in {}", CONFIG_FILE))?;
+    Ok((program, words.collect()))
+}
@@ -1,9 +1,14 @@
 pub mod templates {
+    use crate::template::{self, TemplateContext};
+    use crate::TestFramework;
+
+    /// Renders a built-in template with a context it is known to need.
+    fn render(source: &str, context: &TemplateContext) -> String {
+        template::render(source, context).expect("built-in templates render")
+    }
 
     //lib.rs
-    pub fn lib_rs(address: &str) -> String {
-        format!(
-            r#"#![no_std]
+    pub const LIB_RS: &str = r#"#![no_std]
 
 #[cfg(not(feature = "no-entrypoint"))]
 mod entrypoint;
@@ -15,9 +20,10 @@ pub mod errors;
 pub mod instructions;
 pub mod states;
 
-pinocchio_pubkey::declare_id!("{}");"#,
-            address
-        )
+pinocchio_pubkey::declare_id!("{{ program_id }}");"#;
+
+    pub fn lib_rs(address: &str) -> String {
+        render(LIB_RS, &TemplateContext::default().with_program_id(address))
     }
 
     // entrypoint.rs template
@@ -103,10 +109,8 @@ tests/
 .env"#
     }
 
-    pub fn cargo_toml_mollusk(project_name: &str) -> String {
-        format!(
-            r#"[package]
-name = "{}"
+    pub const CARGO_TOML: &str = r#"[package]
+name = "{{ project_name }}"
 version = "0.1.0"
 edition = "2021"
 
@@ -115,45 +119,47 @@ crate-type = ["cdylib", "rlib"]
 
 [dependencies]
 pinocchio = "0.9.2"
+pinocchio-log = "0.5.1"
+pinocchio-pubkey = "0.3.0"
+pinocchio-system = "0.3.0"
+shank = "0.4.5"
 
 [dev-dependencies]
 solana-sdk = "3.0.0"
+{% if test_framework == "mollusk" %}
 mollusk-svm = "0.7.0"
 mollusk-svm-bencher = "0.7.0"
+{% else %}
+litesvm = "0.8.1"
+litesvm-token = "0.8.1"
+{% endif %}
+{% if features.workspace %}
+test-helpers = { path = "../../test-helpers" }
+{% endif %}
+{% if test_framework == "mollusk" %}
+
+[[bench]]
+name = "compute_units"
+harness = false
+{% endif %}
 
 [features]
 no-entrypoint = []
 std = []
 test-default = ["no-entrypoint", "std"]
-"#,
-            project_name
+"#;
+
+    pub fn cargo_toml_mollusk(project_name: &str) -> String {
+        render(
+            CARGO_TOML,
+            &TemplateContext::new(project_name).with_test_framework(TestFramework::Mollusk),
         )
     }
 
     pub fn cargo_toml_litesvm(project_name: &str) -> String {
-        format!(
-            r#"[package]
-name = "{}"
-version = "0.1.0"
-edition = "2021"
-
-[lib]
-crate-type = ["cdylib", "rlib"]
-
-[dependencies]
-pinocchio = "0.9.2"
-
-[dev-dependencies]
-solana-sdk = "3.0.0"
-litesvm = "0.8.1"
-litesvm-token = "0.8.1"
-
-[features]
-no-entrypoint = []
-std = []
-test-default = ["no-entrypoint", "std"]
-"#,
-            project_name
+        render(
+            CARGO_TOML,
+            &TemplateContext::new(project_name).with_test_framework(TestFramework::Litesvm),
         )
     }
 
@@ -175,6 +181,29 @@ impl From<MyProgramError> for ProgramError {
 "#
     }
 
+    // message helpers maintained by `chio add error`; `variants` holds the
+    // `name` and the quoted `message` of every variant
+    pub const ERRORS_HELPERS_RS: &str = r#"impl MyProgramError {
+    pub fn message(&self) -> &'static str {
+        match self {
+{% for variant in variants %}
+            Self::{{ variant.name }} => {{ variant.message }},
+{% endfor %}
+        }
+    }
+
+    pub fn log(&self) {
+        pinocchio::msg!(self.message());
+    }
+}
+
+impl core::fmt::Display for MyProgramError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+"#;
+
     pub mod instructions {
         pub fn initialize() -> &'static str {
             r#"use pinocchio::{
@@ -255,6 +284,34 @@ pub fn initialize(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
 }"#
         }
 
+        // handler emitted by `chio add instruction`
+        pub const HANDLER_RS: &str = r#"use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::states::utils::{load_ix_data, DataLen};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct {{ struct_name }} {}
+
+impl DataLen for {{ struct_name }} {
+    const LEN: usize = core::mem::size_of::<{{ struct_name }}>();
+}
+
+pub fn {{ name }}(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [signer_acc, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !signer_acc.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let _ix_data = unsafe { load_ix_data::<{{ struct_name }}>(data)? };
+
+    Ok(())
+}
+"#;
+
         pub fn instructions_mod_rs() -> &'static str {
             r#"use pinocchio::program_error::ProgramError;
 
@@ -263,7 +320,13 @@ pub mod initialize;
 pub use initialize::*;
 
 #[repr(u8)]
+#[derive(shank::ShankInstruction)]
 pub enum ProgramInstruction {
+    /// Creates the MyState account of the payer
+    #[account(0, writable, signer, name = "payer", desc = "Pays for the state account and owns it")]
+    #[account(1, writable, name = "state", desc = "MyState PDA of the payer")]
+    #[account(2, name = "rent", desc = "Rent sysvar")]
+    #[account(3, name = "system_program", desc = "System program")]
     InitializeState,
 }
 
@@ -301,7 +364,7 @@ use pinocchio::{
 use crate::{errors::MyProgramError, instructions::Initialize};
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, shank::ShankAccount)]
 pub struct MyState {
     pub owner: Pubkey,
 }
@@ -332,6 +395,56 @@ impl MyState {
 }"#
         }
 
+        // account state emitted by `chio add state`
+        pub const ACCOUNT_STATE_RS: &str = r#"use super::utils::{load_acc_mut_unchecked, load_acc_unchecked, DataLen};
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::{self, Pubkey},
+};
+
+use crate::errors::MyProgramError;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, shank::ShankAccount)]
+pub struct {{ struct_name }} {
+{% for field in fields %}
+    pub {{ field.name }}: {{ field.ty }},
+{% endfor %}
+}
+
+impl DataLen for {{ struct_name }} {
+    const LEN: usize = core::mem::size_of::<{{ struct_name }}>();
+}
+
+impl {{ struct_name }} {
+    pub const SEED: &'static str = "{{ seed }}";
+
+    pub fn validate_pda(bump: u8, pda: &Pubkey, owner: &Pubkey) -> Result<(), ProgramError> {
+        let seed_with_bump = &[Self::SEED.as_bytes(), owner, &[bump]];
+        let derived = pubkey::create_program_address(seed_with_bump, &crate::ID)?;
+        if derived != *pda {
+            return Err(MyProgramError::PdaMismatch.into());
+        }
+        Ok(())
+    }
+
+    pub fn load(acc: &AccountInfo) -> Result<&Self, ProgramError> {
+        if !acc.is_owned_by(&crate::ID) {
+            return Err(MyProgramError::InvalidOwner.into());
+        }
+        unsafe { load_acc_unchecked::<Self>(acc.borrow_data_unchecked()) }
+    }
+
+    pub fn load_mut(acc: &AccountInfo) -> Result<&mut Self, ProgramError> {
+        if !acc.is_owned_by(&crate::ID) {
+            return Err(MyProgramError::InvalidOwner.into());
+        }
+        unsafe { load_acc_mut_unchecked::<Self>(acc.borrow_mut_data_unchecked()) }
+    }
+}
+"#;
+
         pub fn utils_rs() -> &'static str {
             r#"use pinocchio::program_error::ProgramError;
 
@@ -375,9 +488,119 @@ pub unsafe fn to_mut_bytes<T: DataLen>(data: &mut T) -> &mut [u8] {
         }
     }
 
+    pub mod benches {
+        // compute unit harness run by `chio bench`; `chio add instruction`
+        // adds a case function before `fn main` and a line to `cases`
+        pub const COMPUTE_UNITS_RS: &str = r#"//! Compute unit benchmarks for every instruction, run with `chio bench`.
+//!
+//! Results are written to benches/compute_units.md. Commit it to make the
+//! numbers the baseline the next `chio bench` compares with.
+
+use mollusk_svm::{program, Mollusk};
+use mollusk_svm_bencher::MolluskComputeUnitBencher;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+
+use {{ crate_name }}::instructions::Initialize;
+use {{ crate_name }}::states::{to_bytes, MyState};
+
+pub const PROGRAM: Pubkey = pubkey!("{{ program_id }}");
+
+pub const RENT: Pubkey = pubkey!("SysvarRent111111111111111111111111111111111");
+
+pub const PAYER: Pubkey = pubkey!("{{ payer }}");
+
+/// An instruction and the accounts it runs with.
+type Case = (Instruction, Vec<(Pubkey, Account)>);
+
+fn initialize(mollusk: &Mollusk) -> Case {
+    let (system_program, system_account) = program::keyed_account_for_system_program();
+    let (mystate_pda, bump) =
+        Pubkey::find_program_address(&[MyState::SEED.as_bytes(), &PAYER.to_bytes()], &PROGRAM);
+
+    let payer_account = Account::new(1 * LAMPORTS_PER_SOL, 0, &system_program);
+    let mystate_account = Account::new(0, 0, &system_program);
+    let rent = Rent::default();
+    let rent_size = core::mem::size_of::<Rent>();
+    let mut rent_account = Account::new(mollusk.sysvars.rent.minimum_balance(rent_size), rent_size, &RENT);
+    rent_account.data =
+        unsafe { core::slice::from_raw_parts(&rent as *const Rent as *const u8, rent_size) }.to_vec();
+
+    let ix_data = Initialize {
+        owner: *PAYER.as_array(),
+        bump,
+    };
+    // Ix discriminator = 0
+    let mut data = vec![0];
+    data.extend_from_slice(unsafe { to_bytes(&ix_data) });
+
+    let accounts = vec![
+        AccountMeta::new(PAYER, true),
+        AccountMeta::new(mystate_pda, false),
+        AccountMeta::new_readonly(RENT, false),
+        AccountMeta::new_readonly(system_program, false),
+    ];
+    (
+        Instruction::new_with_bytes(PROGRAM, &data, accounts),
+        vec![
+            (PAYER, payer_account),
+            (mystate_pda, mystate_account),
+            (RENT, rent_account),
+            (system_program, system_account),
+        ],
+    )
+}
+
+fn main() {
+{% if features.workspace %}
+    let mollusk = Mollusk::new(&PROGRAM, &test_helpers::program_path("{{ crate_name }}"));
+{% else %}
+    let mollusk = Mollusk::new(&PROGRAM, "target/deploy/{{ crate_name }}");
+{% endif %}
+
+    // One line per instruction: its name in the report and its case.
+    let cases = [
+        ("initialize", initialize(&mollusk)),
+    ];
+
+    let mut bencher = MolluskComputeUnitBencher::new(mollusk)
+        .must_pass(true)
+        .out_dir("benches");
+    for (name, (instruction, accounts)) in &cases {
+        bencher = bencher.bench((*name, instruction, accounts.as_slice()));
+    }
+    bencher.execute();
+}
+"#;
+
+        // case function emitted by `chio add instruction`
+        pub const INSTRUCTION_CASE_RS: &str = r#"fn {{ name }}(_mollusk: &Mollusk) -> Case {
+    let (system_program, _system_account) = program::keyed_account_for_system_program();
+    let signer = Pubkey::new_unique();
+    let signer_account = Account::new(1 * LAMPORTS_PER_SOL, 0, &system_program);
+
+    let ix_data = {{ crate_name }}::instructions::{{ struct_name }} {};
+    // Ix discriminator = {{ discriminator }}
+    let mut data = vec![{{ discriminator }}];
+    data.extend_from_slice(unsafe { to_bytes(&ix_data) });
+
+    let accounts = vec![AccountMeta::new(signer, true)];
+    (
+        Instruction::new_with_bytes(PROGRAM, &data, accounts),
+        vec![(signer, signer_account)],
+    )
+}
+"#;
+    }
+
     pub mod unit_tests {
-        pub fn unit_test_rs(address: &str, program_address: &str, project_name: &str) -> String {
-            let template = r#"use mollusk_svm::result::{Check, ProgramResult};
+        use crate::template::TemplateContext;
+
+        pub const UNIT_TEST_RS: &str = r#"use mollusk_svm::result::{Check, ProgramResult};
 use mollusk_svm::{program, Mollusk};
 use solana_sdk::account::Account;
 use solana_sdk::instruction::{AccountMeta, Instruction};
@@ -387,18 +610,22 @@ use solana_sdk::pubkey::Pubkey;
 extern crate alloc;
 use alloc::vec;
 
-use {project_name}::instructions::Initialize;
-use {project_name}::states::{to_bytes, MyState};
+use {{ crate_name }}::instructions::Initialize;
+use {{ crate_name }}::states::{to_bytes, MyState};
 use solana_sdk::rent::Rent;
 
-pub const PROGRAM: Pubkey = pubkey!("{program_address}");
+pub const PROGRAM: Pubkey = pubkey!("{{ program_id }}");
 
 pub const RENT: Pubkey = pubkey!("SysvarRent111111111111111111111111111111111");
 
-pub const PAYER: Pubkey = pubkey!("{address}");
+pub const PAYER: Pubkey = pubkey!("{{ payer }}");
 
 pub fn mollusk() -> Mollusk {
-    let mollusk = Mollusk::new(&PROGRAM, "target/deploy/{project_name}");
+{% if features.workspace %}
+    let mollusk = Mollusk::new(&PROGRAM, &test_helpers::program_path("{{ crate_name }}"));
+{% else %}
+    let mollusk = Mollusk::new(&PROGRAM, "target/deploy/{{ crate_name }}");
+{% endif %}
     mollusk
 }
 
@@ -466,18 +693,129 @@ fn test_initialize_mystate() {
 }
         "#;
 
-            template
-                .replace("{address}", address)
-                .replace("{program_address}", program_address)
-                .replace("{project_name}", project_name)
+        pub fn unit_test_rs(address: &str, program_address: &str, project_name: &str) -> String {
+            super::render(
+                UNIT_TEST_RS,
+                &TemplateContext::new(project_name)
+                    .with_program_id(program_address)
+                    .with_payer(address),
+            )
         }
 
-        pub fn litesvm_initialize_rs(project_name: &str) -> String {
-            let template = r#"// use this to run the tests -
+        // Mollusk test emitted by `chio add instruction`
+        pub const MOLLUSK_INSTRUCTION_TEST_RS: &str = r#"use mollusk_svm::result::{Check, ProgramResult};
+use mollusk_svm::{program, Mollusk};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+extern crate alloc;
+use alloc::vec;
+
+use {{ crate_name }}::instructions::{{ struct_name }};
+use {{ crate_name }}::states::to_bytes;
+
+pub const PROGRAM: Pubkey = pubkey!("{{ program_id }}");
+
+#[test]
+fn test_{{ name }}() {
+{% if features.workspace %}
+    let mollusk = Mollusk::new(&PROGRAM, &test_helpers::program_path("{{ crate_name }}"));
+{% else %}
+    let mollusk = Mollusk::new(&PROGRAM, "target/deploy/{{ crate_name }}");
+{% endif %}
+
+    let (system_program, _system_account) = program::keyed_account_for_system_program();
+
+    let signer = Pubkey::new_unique();
+    let signer_account = Account::new(1 * LAMPORTS_PER_SOL, 0, &system_program);
+
+    let ix_accounts = vec![AccountMeta::new(signer, true)];
+
+    let ix_data = {{ struct_name }} {};
+
+    // Ix discriminator = {{ discriminator }}
+    let mut ser_ix_data = vec![{{ discriminator }}];
+    ser_ix_data.extend_from_slice(unsafe { to_bytes(&ix_data) });
+
+    let instruction = Instruction::new_with_bytes(PROGRAM, &ser_ix_data, ix_accounts);
+
+    let tx_accounts = &vec![(signer, signer_account.clone())];
+
+    let res =
+        mollusk.process_and_validate_instruction(&instruction, tx_accounts, &[Check::success()]);
+
+    assert!(res.program_result == ProgramResult::Success);
+}
+"#;
+
+        // LiteSVM test emitted by `chio add instruction`
+        pub const LITESVM_INSTRUCTION_TEST_RS: &str = r#"// use this to run the tests -
+// cargo test --features std  -- --no-capture
+
+{% if not features.workspace %}
+use std::path::PathBuf;
+
+{% endif %}
+use litesvm::LiteSVM;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use {{ crate_name }}::instructions::{{ struct_name }};
+use {{ crate_name }}::states;
+
+pub fn program_id() -> Pubkey {
+    Pubkey::new_from_array({{ crate_name }}::ID)
+}
+
+#[test]
+pub fn test_{{ name }}() {
+    let mut svm = LiteSVM::new();
+
+{% if features.workspace %}
+    let so_path = test_helpers::deploy_dir().join("{{ crate_name }}.so");
+{% else %}
+    let so_path = PathBuf::from("target/deploy").join("{{ crate_name }}.so");
+{% endif %}
+    let program_data = std::fs::read(so_path).expect("Failed to read program .so file");
+    svm.add_program(program_id(), &program_data).expect("add_program failed");
+
+    let payer = Keypair::new();
+    svm.airdrop(&payer.pubkey(), 10 * LAMPORTS_PER_SOL).expect("airdrop failed");
+
+    // Discriminator {{ discriminator }} for {{ struct_name }}
+    let ix = {{ struct_name }} {};
+    let mut ix_data = vec![{{ discriminator }}u8];
+    ix_data.extend_from_slice(unsafe { states::utils::to_bytes(&ix) });
+
+    let accounts = vec![AccountMeta::new(payer.pubkey(), true)];
+    let ix = Instruction { program_id: program_id(), accounts, data: ix_data };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        svm.latest_blockhash(),
+    );
+
+    svm.send_transaction(tx).expect("transaction failed");
+}
+"#;
+
+        pub const LITESVM_INITIALIZE_RS: &str = r#"// use this to run the tests -
 // cargo test --features std  -- --no-capture
 
+{% if not features.workspace %}
 use std::path::PathBuf;
 
+{% endif %}
 use litesvm::{types::TransactionResult, LiteSVM};
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
@@ -489,19 +827,23 @@ use solana_sdk::{
     transaction::Transaction,
 };
 
-use {project_name}::instructions::Initialize;
-use {project_name}::states::{self, MyState};
-use {project_name}::states::utils::DataLen;
+use {{ crate_name }}::instructions::Initialize;
+use {{ crate_name }}::states::{self, MyState};
+use {{ crate_name }}::states::utils::DataLen;
 
 pub fn program_id() -> Pubkey {
     // Convert Pinocchio program ID to solana-sdk Pubkey
-    Pubkey::new_from_array({project_name}::ID)
+    Pubkey::new_from_array({{ crate_name }}::ID)
 }
 
 pub fn setup() -> (LiteSVM, Keypair) {
     let mut svm = LiteSVM::new();
 
-    let so_path = PathBuf::from("target/deploy").join("{project_name}.so");
+{% if features.workspace %}
+    let so_path = test_helpers::deploy_dir().join("{{ crate_name }}.so");
+{% else %}
+    let so_path = PathBuf::from("target/deploy").join("{{ crate_name }}.so");
+{% endif %}
 
     let program_data = std::fs::read(so_path).expect("Failed to read program .so file");
     svm.add_program(program_id(), &program_data).expect("add_program failed");
@@ -573,7 +915,504 @@ pub fn test_initialize() {
     assert_eq!(state_account.data.len(), MyState::LEN, "state size mismatch");
 }
 "#;
-            template.replace("{project_name}", project_name)
+
+        pub fn litesvm_initialize_rs(project_name: &str) -> String {
+            super::render(LITESVM_INITIALIZE_RS, &TemplateContext::new(project_name))
+        }
+    }
+    pub mod workspace {
+        pub fn cargo_toml() -> &'static str {
+            r#"[workspace]
+members = ["test-helpers"]
+resolver = "2"
+"#
+        }
+
+        pub fn readme_md() -> &'static str {
+            r#"# Chio Pinocchio Workspace
+
+Several Solana programs built with the Chio CLI tool.
+
+## Project Structure
+
+```
+programs/                  # One Pinocchio program per directory
+test-helpers/              # Helpers shared by every program's tests
+target/deploy/             # Built programs and their keypairs
+Chio.toml                  # Workspace members, cluster and scripts
+```
+
+## Commands
+
+```bash
+# Add a program
+ chio add program <name>
+
+# Build, test or deploy every program
+ chio build
+ chio test
+ chio deploy
+
+# Or only one of them
+ chio build --program <name>
+```
+"#
+        }
+
+        pub fn test_helpers_cargo_toml() -> &'static str {
+            r#"[package]
+name = "test-helpers"
+version = "0.1.0"
+edition = "2021"
+publish = false
+
+[dependencies]
+"#
+        }
+
+        pub fn test_helpers_lib_rs() -> &'static str {
+            r#"//! Helpers shared by the tests of every program in the workspace.
+
+use std::path::PathBuf;
+
+/// Directory `cargo build-sbf` writes the workspace's programs to.
+pub fn deploy_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("target")
+        .join("deploy")
+}
+
+/// Path of a built program without the `.so` extension, as `Mollusk::new`
+/// expects it.
+pub fn program_path(name: &str) -> String {
+    deploy_dir().join(name).to_string_lossy().into_owned()
+}
+"#
+        }
+    }
+
+    // clients emitted by `chio client generate` from the program's IDL
+    pub mod clients {
+        pub mod rust {
+            // its own [workspace] keeps the client out of the program's build,
+            // including `cargo build-sbf` in workspaces
+            pub const CARGO_TOML: &str = r#"[package]
+name = "{{ crate_name }}_client"
+version = "{{ version }}"
+edition = "2021"
+description = "Rust client for the {{ crate_name }} program, generated by chio"
+
+[dependencies]
+solana-sdk = "3.0.0"
+
+[workspace]
+"#;
+
+            pub const GITIGNORE: &str = "/target\n";
+
+            pub const LIB_RS: &str = r#"//! Rust client for the `{{ crate_name }}` program.
+//!
+//! Generated by `chio client generate` from the program source. Regenerate it
+//! after changing instructions, states or errors instead of editing it.
+
+pub mod accounts;
+pub mod errors;
+pub mod instructions;
+
+use solana_sdk::pubkey::Pubkey;
+
+/// Address of the program, from its `declare_id!`.
+pub const ID: Pubkey = solana_sdk::pubkey!("{{ program_id }}");
+
+/// Data whose length does not match the struct it is decoded into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidLength {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl core::fmt::Display for InvalidLength {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "expected {} bytes, found {}", self.expected, self.actual)
+    }
+}
+
+impl std::error::Error for InvalidLength {}
+"#;
+
+            // a `#[repr(C)]` struct of the program; `fields` hold the byte
+            // range and the encode and decode expressions of every field
+            pub const STRUCT_RS: &str = r#"/// {{ doc }}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct {{ name }} {
+{% for field in fields %}
+    pub {{ field.name }}: {{ field.ty }},
+{% endfor %}
+}
+
+impl {{ name }} {
+    /// Size of the data, `#[repr(C)]` padding included.
+    pub const LEN: usize = {{ len }};
+{% if seed %}
+
+    /// Prefix of the PDA seeds.
+    pub const SEED: &'static str = {{ seed }};
+
+    /// Finds the address and bump of the PDA of these seeds.
+    pub fn find_pda({{ seed_params }}) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[{{ seed_exprs }}], &crate::ID)
+    }
+{% endif %}
+
+    /// Encodes the struct with the layout the program reads.
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        #[allow(unused_mut)]
+        let mut data = [0u8; Self::LEN];
+{% for field in fields %}
+        data[{{ field.start }}..{{ field.end }}].copy_from_slice({{ field.encode }});
+{% endfor %}
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, crate::InvalidLength> {
+        if data.len() != Self::LEN {
+            return Err(crate::InvalidLength {
+                expected: Self::LEN,
+                actual: data.len(),
+            });
+        }
+        Ok(Self {
+{% for field in fields %}
+            {{ field.name }}: {{ field.decode }},
+{% endfor %}
+        })
+    }
+}
+"#;
+
+            pub const INSTRUCTIONS_RS: &str = r#"//! Builders for every `ProgramInstruction` variant.
+
+{% if features.account_metas %}
+use solana_sdk::instruction::{AccountMeta, Instruction};
+{% else %}
+use solana_sdk::instruction::Instruction;
+{% endif %}
+{% if features.pubkey %}
+use solana_sdk::pubkey::Pubkey;
+{% endif %}
+{% for ty in types %}
+
+{{ ty.code }}
+{% endfor %}
+{% for ix in instructions %}
+
+/// Discriminator of `ProgramInstruction::{{ ix.name }}`.
+pub const {{ ix.const_name }}_DISCRIMINATOR: u8 = {{ ix.discriminator }};
+
+/// Accounts of `ProgramInstruction::{{ ix.name }}`, in the order the program
+/// reads them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct {{ ix.name }}Accounts {
+{% for account in ix.accounts %}
+{% if account.doc %}
+    /// {{ account.doc }}
+{% endif %}
+    pub {{ account.name }}: {{ account.ty }},
+{% endfor %}
+}
+
+{% for line in ix.docs %}
+/// {{ line.text }}
+{% endfor %}
+pub fn {{ ix.fn_name }}({{ ix.params }}) -> Instruction {
+{% if ix.args %}
+    let mut data = vec![{{ ix.const_name }}_DISCRIMINATOR];
+    data.extend_from_slice(&args.to_bytes());
+{% else %}
+    let data = vec![{{ ix.const_name }}_DISCRIMINATOR];
+{% endif %}
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+{% for account in ix.accounts %}
+            {{ account.meta }},
+{% endfor %}
+        ],
+        data,
+    }
+}
+{% endfor %}
+"#;
+
+            pub const ACCOUNTS_RS: &str = r#"//! Decoders for the state accounts of the program. Accounts carry no
+//! discriminator, so decoding only checks the length of the data.
+{% if features.pubkey %}
+
+use solana_sdk::pubkey::Pubkey;
+{% endif %}
+{% for account in accounts %}
+
+{{ account.code }}
+{% endfor %}
+"#;
+
+            pub const ERRORS_RS: &str = r#"//! The program's `MyProgramError`, returned as `ProgramError::Custom(code)`.
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MyProgramError {
+{% for error in errors %}
+    /// {{ error.doc }}
+    {{ error.name }} = {{ error.code }},
+{% endfor %}
+}
+
+impl MyProgramError {
+    /// The error behind `ProgramError::Custom(code)`, if the program
+    /// defines one.
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+{% for error in errors %}
+            {{ error.code }} => Some(Self::{{ error.name }}),
+{% endfor %}
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+{% for error in errors %}
+            Self::{{ error.name }} => {{ error.message }},
+{% endfor %}
+        }
+    }
+}
+
+impl core::fmt::Display for MyProgramError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} (error {})", self.message(), self.code())
+    }
+}
+
+impl std::error::Error for MyProgramError {}
+"#;
+        }
+
+        pub mod js {
+            pub const PACKAGE_JSON: &str = r#"{
+  "name": "{{ package_name }}",
+  "version": "{{ version }}",
+  "description": "TypeScript client for the {{ crate_name }} program, generated by chio",
+  "type": "module",
+  "main": "dist/index.js",
+  "types": "dist/index.d.ts",
+  "files": ["dist"],
+  "scripts": {
+    "build": "tsc"
+  },
+  "peerDependencies": {
+    "@solana/kit": "^3.0.0"
+  },
+  "devDependencies": {
+    "@solana/kit": "^3.0.0",
+    "typescript": "^5.4.0"
+  }
+}
+"#;
+
+            pub const TSCONFIG_JSON: &str = r#"{
+  "compilerOptions": {
+    "target": "ES2020",
+    "module": "NodeNext",
+    "moduleResolution": "NodeNext",
+    "declaration": true,
+    "outDir": "dist",
+    "rootDir": "src",
+    "strict": true,
+    "skipLibCheck": true
+  },
+  "include": ["src"]
+}
+"#;
+
+            pub const GITIGNORE: &str = "/node_modules\n/dist\n";
+
+            pub const INDEX_TS: &str = r#"// TypeScript client for the `{{ crate_name }}` program.
+//
+// Generated by `chio client generate` from the program source. Regenerate it
+// after changing instructions, states or errors instead of editing it.
+
+export * from "./program.js";
+export * from "./instructions.js";
+export * from "./accounts.js";
+export * from "./errors.js";
+"#;
+
+            pub const PROGRAM_TS: &str = r#"import {
+  address,
+  getAddressDecoder,
+  getAddressEncoder,
+  type Address,
+  type AccountRole,
+} from "@solana/kit";
+
+/** Address of the program, from its `declare_id!`. */
+export const PROGRAM_ADDRESS: Address = address("{{ program_id }}");
+
+/** An instruction for the program, accepted wherever @solana/kit takes one. */
+export type ProgramInstruction = {
+  programAddress: Address;
+  accounts: { address: Address; role: AccountRole }[];
+  data: Uint8Array;
+};
+
+export const addressEncoder = getAddressEncoder();
+export const addressDecoder = getAddressDecoder();
+
+/** Throws unless `data` holds exactly `size` bytes of a `name`. */
+export function checkSize(name: string, data: Uint8Array, size: number): void {
+  if (data.length !== size) {
+    throw new Error(`Expected ${size} bytes for ${name}, found ${data.length}`);
+  }
+}
+"#;
+
+            // a `#[repr(C)]` struct of the program; `fields` hold the offset
+            // and the encode and decode statements of every field
+            pub const STRUCT_TS: &str = r#"/** {{ doc }} */
+export type {{ name }} = {
+{% for field in fields %}
+  {{ field.name }}: {{ field.ty }};
+{% endfor %}
+};
+
+/** Size of an encoded `{{ name }}`, `#[repr(C)]` padding included. */
+export const {{ const_name }}_SIZE = {{ len }};
+{% if seed %}
+
+/** Prefix of the PDA seeds of `{{ name }}`. */
+export const {{ const_name }}_SEED = {{ seed }};
+
+/** Finds the address and bump of the `{{ name }}` PDA of these seeds. */
+export function find{{ name }}Pda({{ seed_params }}): Promise<ProgramDerivedAddress> {
+  return getProgramDerivedAddress({
+    programAddress: PROGRAM_ADDRESS,
+    seeds: [{{ seed_exprs }}],
+  });
+}
+{% endif %}
+
+/** Encodes `{{ name }}` with the layout the program reads. */
+export function encode{{ name }}(value: {{ name }}): Uint8Array {
+  const data = new Uint8Array({{ const_name }}_SIZE);
+  const view = new DataView(data.buffer);
+{% for field in fields %}
+  {{ field.encode }};
+{% endfor %}
+  return data;
+}
+
+export function decode{{ name }}(data: Uint8Array): {{ name }} {
+  checkSize("{{ name }}", data, {{ const_name }}_SIZE);
+  const view = new DataView(data.buffer, data.byteOffset, data.byteLength);
+  return {
+{% for field in fields %}
+    {{ field.name }}: {{ field.decode }},
+{% endfor %}
+  };
+}
+"#;
+
+            pub const INSTRUCTIONS_TS: &str = r#"// Encoders for every `ProgramInstruction` variant.
+
+import { AccountRole, type Address } from "@solana/kit";
+import {
+  PROGRAM_ADDRESS,
+  addressDecoder,
+  addressEncoder,
+  checkSize,
+  type ProgramInstruction,
+} from "./program.js";
+{% for ty in types %}
+
+{{ ty.code }}
+{% endfor %}
+{% for ix in instructions %}
+
+/** Discriminator of `ProgramInstruction::{{ ix.name }}`. */
+export const {{ ix.const_name }}_DISCRIMINATOR = {{ ix.discriminator }};
+
+/** Accounts of `ProgramInstruction::{{ ix.name }}`, in the order the program reads them. */
+export type {{ ix.name }}Accounts = {
+{% for account in ix.accounts %}
+{% if account.doc %}
+  /** {{ account.doc }} */
+{% endif %}
+  {{ account.decl }};
+{% endfor %}
+};
+
+{% for line in ix.docs %}
+/** {{ line.text }} */
+{% endfor %}
+export function get{{ ix.name }}Instruction({{ ix.params }}): ProgramInstruction {
+  return {
+    programAddress: PROGRAM_ADDRESS,
+    accounts: [
+{% for account in ix.accounts %}
+      {{ account.meta }},
+{% endfor %}
+    ],
+    data: new Uint8Array([{{ ix.data }}]),
+  };
+}
+{% endfor %}
+"#;
+
+            pub const ACCOUNTS_TS: &str = r#"// Decoders for the state accounts of the program. Accounts carry no
+// discriminator, so decoding only checks the length of the data.
+
+import {
+  getProgramDerivedAddress,
+  type Address,
+  type ProgramDerivedAddress,
+} from "@solana/kit";
+import { PROGRAM_ADDRESS, addressDecoder, addressEncoder, checkSize } from "./program.js";
+{% for account in accounts %}
+
+{{ account.code }}
+{% endfor %}
+"#;
+
+            pub const ERRORS_TS: &str = r#"// The program's `MyProgramError`, returned as `ProgramError::Custom(code)`.
+
+export enum MyProgramError {
+{% for error in errors %}
+  /** {{ error.doc }} */
+  {{ error.name }} = {{ error.code }},
+{% endfor %}
+}
+
+const MESSAGES: Record<number, string> = {
+{% for error in errors %}
+  {{ error.code }}: {{ error.message }},
+{% endfor %}
+};
+
+/** The error behind `ProgramError::Custom(code)`, if the program defines one. */
+export function getMyProgramError(code: number): MyProgramError | undefined {
+  return code in MESSAGES ? (code as MyProgramError) : undefined;
+}
+
+export function getMyProgramErrorMessage(code: number): string | undefined {
+  return MESSAGES[code];
+}
+"#;
         }
     }
 }
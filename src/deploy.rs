@@ -0,0 +1,362 @@
+//! Pre-flight checks and arguments for `chio deploy`.
+
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::add::Project;
+use crate::config::ChioConfig;
+use crate::keypair::Keypair;
+use crate::name::crate_name;
+use crate::runner::{self, CommandRunner, ProcessCommand};
+
+/// Result of comparing a program binary's deploy address with the id
+/// compiled into it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProgramIdCheck {
+    /// Keypair the binary is deployed with.
+    pub keypair_path: PathBuf,
+    /// Address of that keypair, i.e. where the program will live.
+    pub address: String,
+    /// Address passed to `declare_id!` in `src/lib.rs`.
+    pub declared: String,
+}
+
+impl ProgramIdCheck {
+    pub fn is_match(&self) -> bool {
+        self.address == self.declared
+    }
+}
+
+/// Picks the program binary to deploy from `deploy_dir`.
+///
+/// The binary is `<name>.so`, where `name` is `requested` if given and the
+/// package name otherwise, with hyphens mapped to underscores as cargo does.
+/// When it is missing, the error lists every binary found instead of falling
+/// back to one of them.
+pub fn select_program(
+    deploy_dir: &Path,
+    package: &str,
+    requested: Option<&str>,
+) -> Result<PathBuf> {
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(deploy_dir)
+        .with_context(|| format!("Failed to read {}", deploy_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("so") {
+            candidates.push(path);
+        }
+    }
+    candidates.sort();
+
+    if candidates.is_empty() {
+        anyhow::bail!(
+            "No .so file found in {}. Please run 'chio build' first.",
+            deploy_dir.display()
+        );
+    }
+
+    let wanted = crate_name(requested.unwrap_or(package));
+    if let Some(path) = candidates
+        .iter()
+        .find(|path| path.file_stem().and_then(|stem| stem.to_str()) == Some(wanted.as_str()))
+    {
+        return Ok(path.clone());
+    }
+
+    let names: Vec<String> = candidates
+        .iter()
+        .filter_map(|path| path.file_stem()?.to_str().map(str::to_string))
+        .collect();
+    anyhow::bail!(
+        "No {}.so found in {}. Available programs: {}. Pass --program <name> to choose one, or run 'chio build' if it is stale.",
+        wanted,
+        deploy_dir.display(),
+        names.join(", ")
+    )
+}
+
+/// Returns the keypair `cargo build-sbf` writes next to `so_path`, e.g.
+/// `target/deploy/my_program-keypair.json` for `target/deploy/my_program.so`.
+pub fn keypair_for_so(so_path: &Path) -> Result<PathBuf> {
+    let stem = so_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .with_context(|| format!("Invalid program path {}", so_path.display()))?;
+
+    Ok(so_path.with_file_name(format!("{}-keypair.json", stem)))
+}
+
+/// Pairs `so_path` with its keypair, or with `program_keypair` when given,
+/// and compares that keypair's address with the `declare_id!` of `project`.
+pub fn check_program_id(
+    project: &Project,
+    so_path: &Path,
+    program_keypair: Option<&Path>,
+) -> Result<ProgramIdCheck> {
+    let keypair_path = match program_keypair {
+        Some(path) => path.to_path_buf(),
+        None => keypair_for_so(so_path)?,
+    };
+    if !keypair_path.exists() {
+        anyhow::bail!(
+            "No keypair found for {} (expected {}). Run 'chio build' to generate one.",
+            so_path.display(),
+            keypair_path.display()
+        );
+    }
+
+    let address = Keypair::read(&keypair_path)?.address();
+    let declared = project.program_id()?;
+
+    Ok(ProgramIdCheck {
+        keypair_path,
+        address,
+        declared,
+    })
+}
+
+/// Cluster a program is deployed to.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Cluster {
+    Localnet,
+    Devnet,
+    Testnet,
+    Mainnet,
+    Custom(String),
+}
+
+impl Cluster {
+    /// Returns the JSON RPC URL passed to `solana --url`.
+    pub fn url(&self) -> &str {
+        match self {
+            Self::Localnet => "http://127.0.0.1:8899",
+            Self::Devnet => "https://api.devnet.solana.com",
+            Self::Testnet => "https://api.testnet.solana.com",
+            Self::Mainnet => "https://api.mainnet-beta.solana.com",
+            Self::Custom(url) => url,
+        }
+    }
+
+    /// Whether deploying here costs real funds, judging by the name alone:
+    /// custom URLs count when they contain "mainnet". [`targets_mainnet`]
+    /// also asks the RPC for its genesis hash.
+    pub fn is_mainnet(&self) -> bool {
+        match self {
+            Self::Mainnet => true,
+            Self::Custom(url) => url.contains("mainnet"),
+            _ => false,
+        }
+    }
+
+    /// The named cluster behind `url`, or a custom one.
+    pub fn from_url(url: &str) -> Self {
+        let trimmed = url.trim().trim_end_matches('/');
+        let named = [Self::Localnet, Self::Devnet, Self::Testnet, Self::Mainnet]
+            .into_iter()
+            .find(|cluster| cluster.url() == trimmed);
+        match named {
+            Some(cluster) => cluster,
+            None if trimmed == "http://localhost:8899" => Self::Localnet,
+            None => Self::Custom(trimmed.to_string()),
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "localnet" | "localhost" | "l" => Ok(Self::Localnet),
+            "devnet" | "d" => Ok(Self::Devnet),
+            "testnet" | "t" => Ok(Self::Testnet),
+            "mainnet" | "mainnet-beta" | "m" => Ok(Self::Mainnet),
+            url if url.starts_with("http://")
+                || url.starts_with("https://")
+                || url.starts_with("ws://")
+                || url.starts_with("wss://") =>
+            {
+                Ok(Self::Custom(url.to_string()))
+            }
+            other => Err(format!(
+                "unknown cluster '{}', expected localnet, devnet, testnet, mainnet or an RPC URL",
+                other
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for Cluster {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Cluster> for String {
+    fn from(cluster: Cluster) -> Self {
+        cluster.to_string()
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Localnet => f.write_str("localnet"),
+            Self::Devnet => f.write_str("devnet"),
+            Self::Testnet => f.write_str("testnet"),
+            Self::Mainnet => f.write_str("mainnet"),
+            Self::Custom(url) => f.write_str(url),
+        }
+    }
+}
+
+/// Options forwarded to `solana program deploy`. Anything left unset falls
+/// back to the active `solana config`; the cluster is read from it up front,
+/// see [`resolve_cluster`].
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct DeployOptions {
+    /// Cluster to deploy to: localnet, devnet, testnet, mainnet or an RPC URL
+    #[arg(long, short = 'u')]
+    pub cluster: Option<Cluster>,
+    /// Fee payer keypair
+    #[arg(long, short = 'k')]
+    pub keypair: Option<PathBuf>,
+    /// Program keypair, defaults to the one next to the .so in target/deploy
+    #[arg(long)]
+    pub program_keypair: Option<PathBuf>,
+    /// Upgrade authority keypair
+    #[arg(long)]
+    pub upgrade_authority: Option<PathBuf>,
+    /// Make the program immutable
+    #[arg(long = "final")]
+    pub is_final: bool,
+    /// Maximum program size in bytes, reserving room for upgrades
+    #[arg(long)]
+    pub max_len: Option<usize>,
+    /// Skip the mainnet confirmation prompt
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+impl DeployOptions {
+    /// Fills the options not given on the command line from `Chio.toml`.
+    /// The configured program keypair only applies when deploying the
+    /// configured program.
+    pub fn or_config(&self, config: &ChioConfig, program: &str) -> Self {
+        let mut options = self.clone();
+        if options.cluster.is_none() {
+            options.cluster = config.provider.cluster.clone();
+        }
+        if options.keypair.is_none() {
+            options.keypair = config.provider.wallet.clone();
+        }
+        if options.program_keypair.is_none() {
+            options.program_keypair = config
+                .program_config(program)
+                .map(|program| program.keypair.clone());
+        }
+        options
+    }
+}
+
+/// Genesis hash of mainnet-beta, which identifies it behind any RPC URL.
+pub const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+/// The cluster a deploy with `options` goes to: the one given or configured,
+/// or else the RPC URL of the active `solana config`.
+pub fn resolve_cluster(runner: &dyn CommandRunner, options: &DeployOptions) -> Result<Cluster> {
+    if let Some(cluster) = &options.cluster {
+        return Ok(cluster.clone());
+    }
+
+    let output = runner.output(&ProcessCommand::new("solana", ["config", "get"]));
+    let url = output
+        .ok()
+        .filter(|output| output.is_success())
+        .and_then(|output| {
+            output.stdout.lines().find_map(|line| {
+                line.strip_prefix("RPC URL:")
+                    .map(|url| url.trim().to_string())
+            })
+        });
+    match url {
+        Some(url) if !url.is_empty() => Ok(Cluster::from_url(&url)),
+        _ => anyhow::bail!(
+            "No cluster to deploy to. Pass --cluster, set `cluster` under [provider] in Chio.toml, \
+            or run 'solana config set --url <cluster>'."
+        ),
+    }
+}
+
+/// Whether deploying to `cluster` costs real funds. A custom URL is checked
+/// with `solana genesis-hash`, and counts as mainnet when its hash cannot be
+/// read, so an unknown RPC still asks for confirmation.
+pub fn targets_mainnet(runner: &dyn CommandRunner, cluster: &Cluster) -> bool {
+    match cluster {
+        Cluster::Custom(url) if !cluster.is_mainnet() => {
+            let command = ProcessCommand::new("solana", ["genesis-hash", "--url", url.as_str()]);
+            match runner.output(&command) {
+                Ok(output) if output.is_success() => output.stdout.trim() == MAINNET_GENESIS_HASH,
+                _ => true,
+            }
+        }
+        _ => cluster.is_mainnet(),
+    }
+}
+
+/// Builds the arguments of `solana` that deploy `so_path` with
+/// `program_keypair`.
+pub fn deploy_args(
+    so_path: &Path,
+    program_keypair: &Path,
+    options: &DeployOptions,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "program".into(),
+        "deploy".into(),
+        so_path.into(),
+        "--program-id".into(),
+        program_keypair.into(),
+    ];
+
+    if let Some(cluster) = &options.cluster {
+        args.push("--url".into());
+        args.push(cluster.url().into());
+    }
+    if let Some(keypair) = &options.keypair {
+        args.push("--keypair".into());
+        args.push(keypair.into());
+    }
+    if let Some(authority) = &options.upgrade_authority {
+        args.push("--upgrade-authority".into());
+        args.push(authority.into());
+    }
+    if options.is_final {
+        args.push("--final".into());
+    }
+    if let Some(max_len) = options.max_len {
+        args.push("--max-len".into());
+        args.push(max_len.to_string().into());
+    }
+
+    args
+}
+
+/// Runs `solana program deploy` for `so_path`, failing when it does.
+pub fn run_deploy(
+    runner: &dyn CommandRunner,
+    so_path: &Path,
+    program_keypair: &Path,
+    options: &DeployOptions,
+) -> Result<()> {
+    let command = ProcessCommand::new("solana", deploy_args(so_path, program_keypair, options));
+    runner::run_checked(runner, &command, "Deploy")
+}
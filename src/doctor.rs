@@ -0,0 +1,437 @@
+//! Toolchain and project diagnostics for `chio doctor`.
+//!
+//! Each check reports what it found and, when something is wrong, how to fix
+//! it. Errors are problems that stop `chio build` or `chio deploy` from
+//! working; warnings are worth fixing but do not block anything.
+
+use anyhow::Result;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ChioConfig, CONFIG_FILE};
+use crate::content::templates;
+use crate::keypair::Keypair;
+use crate::runner::{CommandRunner, ProcessCommand};
+use crate::template::{self, TemplateContext};
+use crate::TestFramework;
+
+/// Oldest Solana CLI, and `cargo build-sbf`, the generated projects are
+/// expected to build with.
+pub const MIN_SOLANA_VERSION: Version = Version(2, 1, 0);
+
+/// A `major.minor.patch` version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub u64, pub u64, pub u64);
+
+impl Version {
+    /// Finds the first `x.y` or `x.y.z` version in `text`, such as the
+    /// output of `solana --version` or a Cargo requirement like `^0.9`.
+    pub fn find(text: &str) -> Option<Self> {
+        text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter_map(|word| {
+                let mut parts = word.trim_matches('.').split('.');
+                let major = parts.next()?.parse().ok()?;
+                let minor = parts.next()?.parse().ok()?;
+                let patch = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+                Some(Self(major, minor, patch))
+            })
+            .next()
+    }
+
+    /// Whether a crate at `self` is semver compatible with `other`.
+    pub fn is_compatible(&self, other: &Version) -> bool {
+        match (self.0, other.0) {
+            (0, 0) => self.1 == other.1,
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub severity: Severity,
+    /// What was found, such as a version or a path.
+    pub detail: String,
+    /// How to fix a warning or error.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            severity: Severity::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            fix: Some(fix.into()),
+            ..Self::ok(name, detail)
+        }
+    }
+
+    fn error(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            ..Self::warning(name, detail, fix)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.severity == severity)
+            .count()
+    }
+
+    pub fn check(&self, name: &str) -> Option<&Check> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+}
+
+/// Checks the toolchain, the active Solana config and, when `root` holds a
+/// chio project, the versions its programs pin. `home` is where the Solana
+/// platform tools are cached, under `.cache/solana`.
+pub fn diagnose(runner: &dyn CommandRunner, root: &Path, home: Option<&Path>) -> Result<Report> {
+    let mut checks = vec![
+        tool(runner, "cargo", &["--version"], Severity::Error, None),
+        tool(
+            runner,
+            "solana",
+            &["--version"],
+            Severity::Error,
+            Some(MIN_SOLANA_VERSION),
+        ),
+        tool(
+            runner,
+            "solana-keygen",
+            &["--version"],
+            Severity::Warning,
+            None,
+        ),
+        tool(runner, "git", &["--version"], Severity::Warning, None),
+    ];
+    checks.extend(build_sbf(runner, home));
+
+    // A broken Chio.toml is reported like any other problem, and the checks
+    // that do not need it still run.
+    let config = match ChioConfig::load(root) {
+        Ok(config) => config,
+        Err(e) => {
+            checks.push(Check::error(
+                CONFIG_FILE,
+                format!("{:#}", e),
+                format!("Fix the error in {}", CONFIG_FILE),
+            ));
+            None
+        }
+    };
+    checks.push(wallet(runner, root, config.as_ref()));
+    if let Some(config) = &config {
+        checks.extend(dependencies(root, config));
+    }
+
+    Ok(Report { checks })
+}
+
+const INSTALL_SOLANA: &str = "Install the Solana CLI: https://solana.com/docs/intro/installation";
+
+/// Runs `program args` and checks that it exists and, with `min`, that the
+/// version it prints is recent enough.
+fn tool(
+    runner: &dyn CommandRunner,
+    program: &str,
+    args: &[&str],
+    missing: Severity,
+    min: Option<Version>,
+) -> Check {
+    let install = match program {
+        "cargo" => "Install Rust: https://rustup.rs".to_string(),
+        "git" => "Install git to have 'chio init' create the repository".to_string(),
+        _ => INSTALL_SOLANA.to_string(),
+    };
+
+    let output = match runner.output(&ProcessCommand::new(program, args)) {
+        Ok(output) if output.is_success() => output,
+        _ => {
+            return Check {
+                severity: missing,
+                ..Check::error(program, "not found", install)
+            }
+        }
+    };
+
+    let first_line = output.stdout.lines().next().unwrap_or_default().trim();
+    version_check(program, first_line, min)
+}
+
+/// Checks the version `tool` printed on its first line against `min`.
+fn version_check(tool: &str, first_line: &str, min: Option<Version>) -> Check {
+    match (min, Version::find(first_line)) {
+        (Some(min), Some(version)) if version < min => Check::error(
+            tool,
+            format!("{}, older than {}", first_line, min),
+            format!("Update with 'agave-install update' or {}", INSTALL_SOLANA),
+        ),
+        _ => Check::ok(tool, first_line),
+    }
+}
+
+/// Checks `cargo build-sbf` and the platform tools it builds with.
+fn build_sbf(runner: &dyn CommandRunner, home: Option<&Path>) -> Vec<Check> {
+    const NAME: &str = "cargo build-sbf";
+    let output = match runner.output(&ProcessCommand::new("cargo", ["build-sbf", "--version"])) {
+        Ok(output) if output.is_success() => output,
+        _ => return vec![Check::error(NAME, "not found", INSTALL_SOLANA)],
+    };
+
+    let mut lines = output.stdout.lines().map(str::trim);
+    let first_line = lines.next().unwrap_or_default();
+    let build_sbf = version_check(NAME, first_line, Some(MIN_SOLANA_VERSION));
+
+    let platform_tools = lines
+        .find(|line| line.starts_with("platform-tools"))
+        .and_then(|line| line.split_whitespace().nth(1));
+    let platform_tools = match (platform_tools, home) {
+        (None, _) => Check::warning(
+            "platform-tools",
+            "version unknown",
+            "Run 'cargo build-sbf --version' to see which platform tools it uses",
+        ),
+        (Some(version), Some(home)) => {
+            let dir = home
+                .join(".cache")
+                .join("solana")
+                .join(version)
+                .join("platform-tools");
+            if dir.is_dir() {
+                Check::ok(
+                    "platform-tools",
+                    format!("{} in {}", version, dir.display()),
+                )
+            } else {
+                Check::warning(
+                    "platform-tools",
+                    format!("{} not installed", version),
+                    "Run 'cargo build-sbf --force-tools-install', or let the first build download them",
+                )
+            }
+        }
+        (Some(version), None) => Check::ok("platform-tools", version),
+    };
+
+    vec![build_sbf, platform_tools]
+}
+
+/// Checks the fee payer keypair: the project's wallet, or the one in the
+/// active `solana config`.
+fn wallet(runner: &dyn CommandRunner, root: &Path, config: Option<&ChioConfig>) -> Check {
+    const NAME: &str = "wallet";
+    let configured = config.and_then(|config| config.provider.wallet.clone());
+
+    let (path, source) = match configured {
+        Some(path) => (root.join(path), "Chio.toml"),
+        None => {
+            let output = runner.output(&ProcessCommand::new("solana", ["config", "get"]));
+            let keypair_path =
+                output
+                    .ok()
+                    .filter(|output| output.is_success())
+                    .and_then(|output| {
+                        output.stdout.lines().find_map(|line| {
+                            line.strip_prefix("Keypair Path:")
+                                .map(|path| PathBuf::from(path.trim()))
+                        })
+                    });
+            match keypair_path {
+                Some(path) => (path, "solana config"),
+                None => {
+                    return Check::warning(
+                        NAME,
+                        "no active solana config",
+                        "Run 'solana config set --url devnet' and 'solana-keygen new'",
+                    )
+                }
+            }
+        }
+    };
+
+    if !path.exists() {
+        return Check::warning(
+            NAME,
+            format!("{} from {} does not exist", path.display(), source),
+            format!("Create it with 'solana-keygen new -o {}'", path.display()),
+        );
+    }
+    match Keypair::read(&path) {
+        Ok(keypair) => Check::ok(
+            NAME,
+            format!(
+                "{} ({}, from {})",
+                keypair.address(),
+                path.display(),
+                source
+            ),
+        ),
+        Err(e) => Check::error(
+            NAME,
+            format!("{:#}", e),
+            format!("Point {} at a valid keypair file", source),
+        ),
+    }
+}
+
+/// Compares the pinocchio, mollusk and litesvm versions each program pins
+/// with the ones chio generates code for.
+fn dependencies(root: &Path, config: &ChioConfig) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    for program in config.program_configs() {
+        let manifest_path = if config.is_workspace() {
+            Path::new("programs").join(&program.name).join("Cargo.toml")
+        } else {
+            PathBuf::from("Cargo.toml")
+        };
+        let manifest = match fs::read_to_string(root.join(&manifest_path)) {
+            Ok(manifest) => manifest,
+            Err(_) => {
+                checks.push(Check::error(
+                    &program.name,
+                    format!("{} not found", manifest_path.display()),
+                    format!(
+                        "Remove '{}' from Chio.toml or restore its crate",
+                        program.name
+                    ),
+                ));
+                continue;
+            }
+        };
+        let pinned = match dependency_versions(&manifest) {
+            Ok(pinned) => pinned,
+            Err(e) => {
+                checks.push(Check::error(
+                    &program.name,
+                    format!("Invalid {}: {:#}", manifest_path.display(), e),
+                    format!("Fix the error in {}", manifest_path.display()),
+                ));
+                continue;
+            }
+        };
+        let context =
+            TemplateContext::new(&program.name).with_test_framework(program.test_framework);
+        let expected = match template::render(templates::CARGO_TOML, &context)
+            .and_then(|generated| dependency_versions(&generated))
+        {
+            Ok(expected) => expected,
+            Err(e) => {
+                checks.push(Check::error(
+                    &program.name,
+                    format!("Failed to read the versions chio generates: {:#}", e),
+                    "Reinstall chio, or report the error if it persists",
+                ));
+                continue;
+            }
+        };
+
+        let test_crate = match program.test_framework {
+            TestFramework::Mollusk => "mollusk-svm",
+            TestFramework::Litesvm => "litesvm",
+        };
+        for name in ["pinocchio", test_crate] {
+            let Some(expected) = expected
+                .iter()
+                .find(|(dep, _)| dep == name)
+                .map(|(_, v)| *v)
+            else {
+                continue;
+            };
+            let check_name = format!("{} {}", program.name, name);
+            let check = match pinned.iter().find(|(dep, _)| dep == name) {
+                None => Check::warning(
+                    &check_name,
+                    format!("not in {}", manifest_path.display()),
+                    format!(
+                        "Add {} = \"{}\" to {}",
+                        name,
+                        expected,
+                        manifest_path.display()
+                    ),
+                ),
+                Some((_, version)) if !version.is_compatible(&expected) => Check::warning(
+                    &check_name,
+                    format!("{} pinned, chio generates code for {}", version, expected),
+                    format!(
+                        "Update {} to \"{}\" in {}, or check code added by 'chio add' against {}",
+                        name,
+                        expected,
+                        manifest_path.display(),
+                        version
+                    ),
+                ),
+                Some((_, version)) => Check::ok(&check_name, version.to_string()),
+            };
+            checks.push(check);
+        }
+    }
+
+    checks
+}
+
+/// The versions a manifest requires, from `[dependencies]` and
+/// `[dev-dependencies]`. Path and git dependencies without a version are
+/// skipped.
+fn dependency_versions(manifest: &str) -> Result<Vec<(String, Version)>> {
+    let manifest: toml::Table = toml::from_str(manifest)?;
+    let mut versions = Vec::new();
+
+    for section in ["dependencies", "dev-dependencies"] {
+        let Some(deps) = manifest.get(section).and_then(|deps| deps.as_table()) else {
+            continue;
+        };
+        for (name, spec) in deps {
+            let requirement = match spec {
+                toml::Value::String(version) => Some(version.as_str()),
+                toml::Value::Table(table) => table.get("version").and_then(|v| v.as_str()),
+                _ => None,
+            };
+            if let Some(version) = requirement.and_then(Version::find) {
+                versions.push((name.clone(), version));
+            }
+        }
+    }
+    Ok(versions)
+}
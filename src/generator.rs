@@ -0,0 +1,802 @@
+//! Project scaffolding, usable without the CLI.
+//!
+//! [`ProjectGenerator`] builds the files of a new program or workspace as a
+//! [`FileTree`] that can be inspected in memory, planned with
+//! [`GeneratedProject::plan`] or written to disk:
+//!
+//! ```no_run
+//! use chio::generator::ProjectGenerator;
+//! use chio::TestFramework;
+//!
+//! let project = ProjectGenerator::new("vault")
+//!     .with_test_framework(TestFramework::Litesvm)
+//!     .with_output_dir("/tmp/vault")
+//!     .generate()?;
+//! assert!(project.files.get("src/lib.rs").is_some());
+//! project.write()?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::ChioConfig;
+use crate::content::templates;
+use crate::keypair::{self, Keypair};
+use crate::merge;
+use crate::name::{validate_project_name, ProjectName};
+use crate::runner::{CommandRunner, ProcessCommand, SystemRunner};
+use crate::template::TemplateContext;
+use crate::template_set::TemplateSet;
+use crate::TestFramework;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProjectKind {
+    Program,
+    Workspace,
+}
+
+/// Builder for the files of a new project.
+///
+/// A program generated with the `workspace` feature is a member of an
+/// existing workspace: its paths are relative to the workspace root, its
+/// sources go under `programs/<name>` and no `Chio.toml` is generated, as the
+/// workspace already has one.
+pub struct ProjectGenerator {
+    kind: ProjectKind,
+    name: String,
+    test_framework: TestFramework,
+    program_keypair: Option<Keypair>,
+    program_id: Option<String>,
+    payer: Option<String>,
+    output_dir: Option<PathBuf>,
+    features: BTreeMap<String, bool>,
+    template: Option<TemplateSet>,
+    variables: BTreeMap<String, String>,
+    git: bool,
+    conflicts: ConflictMode,
+}
+
+impl ProjectGenerator {
+    /// A single-program project named `name`, tested with Mollusk unless
+    /// [`ProjectGenerator::with_test_framework`] says otherwise.
+    pub fn new(name: &str) -> Self {
+        Self {
+            kind: ProjectKind::Program,
+            name: name.to_string(),
+            test_framework: TestFramework::Mollusk,
+            program_keypair: None,
+            program_id: None,
+            payer: None,
+            output_dir: None,
+            features: BTreeMap::new(),
+            template: None,
+            variables: BTreeMap::new(),
+            git: false,
+            conflicts: ConflictMode::Abort,
+        }
+    }
+
+    /// A workspace named `name` with no programs yet.
+    pub fn workspace(name: &str) -> Self {
+        Self {
+            kind: ProjectKind::Workspace,
+            ..Self::new(name)
+        }
+    }
+
+    pub fn with_test_framework(mut self, test_framework: TestFramework) -> Self {
+        self.test_framework = test_framework;
+        self
+    }
+
+    /// Deploys the program with `keypair`, written to
+    /// `target/deploy/<name>-keypair.json`. A new keypair is generated when
+    /// neither this nor [`ProjectGenerator::with_program_id`] is set.
+    pub fn with_program_keypair(mut self, keypair: Keypair) -> Self {
+        self.program_keypair = Some(keypair);
+        self
+    }
+
+    /// Declares the program with an id whose keypair the caller manages, so
+    /// no keypair file is generated.
+    pub fn with_program_id(mut self, program_id: &str) -> Self {
+        self.program_id = Some(program_id.to_string());
+        self
+    }
+
+    /// Fee payer address used by the generated tests. Defaults to a
+    /// throwaway address.
+    pub fn with_payer(mut self, payer: &str) -> Self {
+        self.payer = Some(payer.to_string());
+        self
+    }
+
+    /// Directory the project is written to. Defaults to the project name.
+    pub fn with_output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(output_dir.into());
+        self
+    }
+
+    pub fn with_feature(mut self, feature: &str, enabled: bool) -> Self {
+        self.features.insert(feature.to_string(), enabled);
+        self
+    }
+
+    /// Renders the program from `template` instead of the built-in templates.
+    pub fn with_template(mut self, template: TemplateSet) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Values for the variables the template declares.
+    pub fn with_variables(mut self, variables: BTreeMap<String, String>) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    /// Plans a git repository with an initial commit, created once the
+    /// project is written.
+    pub fn with_git(mut self, git: bool) -> Self {
+        self.git = git;
+        self
+    }
+
+    /// What writing the project does with files that already exist in the
+    /// output directory. Defaults to [`ConflictMode::Abort`].
+    pub fn with_conflict_mode(mut self, conflicts: ConflictMode) -> Self {
+        self.conflicts = conflicts;
+        self
+    }
+
+    fn is_member(&self) -> bool {
+        self.features.get("workspace").copied().unwrap_or(false)
+    }
+
+    /// Builds the project in memory, without touching the disk or running
+    /// any command.
+    pub fn generate(self) -> Result<GeneratedProject> {
+        let name = validate_project_name(&self.name)?;
+        let root = self
+            .output_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&self.name));
+        let mut files = FileTree::new(root);
+
+        let program_id = match self.kind {
+            ProjectKind::Workspace => {
+                self.workspace_files(&mut files)?;
+                None
+            }
+            ProjectKind::Program => Some(self.program_files(&name, &mut files)?),
+        };
+
+        let commands = if self.git {
+            PlannedCommand::git_init(&self.name)
+        } else {
+            Vec::new()
+        };
+        Ok(GeneratedProject {
+            name: self.name,
+            program_id,
+            files,
+            commands,
+            conflicts: self.conflicts,
+        })
+    }
+
+    fn program_files(&self, name: &ProjectName, files: &mut FileTree) -> Result<String> {
+        let set = match &self.template {
+            Some(set) => set.clone(),
+            None => TemplateSet::builtin(),
+        };
+        set.check_variables(&self.variables)?;
+
+        let keypair = match (&self.program_keypair, &self.program_id) {
+            (Some(keypair), _) => Some(keypair.clone()),
+            (None, Some(_)) => None,
+            (None, None) => Some(Keypair::generate()?),
+        };
+        let program_id = match &keypair {
+            Some(keypair) => keypair.address(),
+            None => self.program_id.clone().unwrap_or_default(),
+        };
+        let payer = match &self.payer {
+            Some(payer) => payer.clone(),
+            None => Keypair::generate()?.address(),
+        };
+
+        let mut context = TemplateContext::new(&self.name)
+            .with_program_id(&program_id)
+            .with_payer(&payer)
+            .with_test_framework(self.test_framework);
+        for (feature, enabled) in &self.features {
+            context = context.with_feature(feature, *enabled);
+        }
+        let context = set.context(context, &self.variables)?;
+
+        let program_dir = if self.is_member() {
+            Path::new("programs").join(&self.name)
+        } else {
+            PathBuf::new()
+        };
+        for (path, contents) in set.render(&context)? {
+            files.add(program_dir.join(path), contents);
+        }
+
+        if let Some(keypair) = keypair {
+            let path = Path::new("target")
+                .join("deploy")
+                .join(format!("{}-keypair.json", name.crate_name()));
+            files.add_private(path, keypair.to_json());
+        }
+        if !self.is_member() {
+            let config = ChioConfig::new(&self.name, self.test_framework, &program_id);
+            files.add(crate::config::CONFIG_FILE, config.to_toml()?);
+        }
+
+        Ok(program_id)
+    }
+
+    fn workspace_files(&self, files: &mut FileTree) -> Result<()> {
+        files.add("Cargo.toml", templates::workspace::cargo_toml());
+        files.add("README.md", templates::workspace::readme_md());
+        files.add(".gitignore", templates::gitignore());
+        files.add_dir("programs");
+        files.add(
+            Path::new("test-helpers").join("Cargo.toml"),
+            templates::workspace::test_helpers_cargo_toml(),
+        );
+        files.add(
+            Path::new("test-helpers").join("src").join("lib.rs"),
+            templates::workspace::test_helpers_lib_rs(),
+        );
+        files.add(
+            crate::config::CONFIG_FILE,
+            ChioConfig::new_workspace().to_toml()?,
+        );
+        Ok(())
+    }
+}
+
+/// The files of a project, relative to the directory they are written to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileTree {
+    pub root: PathBuf,
+    pub files: BTreeMap<PathBuf, String>,
+    /// Files readable only by the owner, such as keypairs.
+    pub private: BTreeSet<PathBuf>,
+    /// Directories created even though no file is generated in them.
+    pub directories: BTreeSet<PathBuf>,
+}
+
+impl FileTree {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn add(&mut self, path: impl Into<PathBuf>, contents: impl Into<String>) {
+        self.files.insert(path.into(), contents.into());
+    }
+
+    pub fn add_private(&mut self, path: impl Into<PathBuf>, contents: impl Into<String>) {
+        let path = path.into();
+        self.private.insert(path.clone());
+        self.add(path, contents);
+    }
+
+    pub fn add_dir(&mut self, path: impl Into<PathBuf>) {
+        self.directories.insert(path.into());
+    }
+
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&str> {
+        self.files.get(path.as_ref()).map(String::as_str)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Writes every file and directory under `root`, creating it if needed
+    /// and overwriting the files already there.
+    pub fn write(&self) -> Result<()> {
+        self.write_resolving(ConflictMode::Force).map(drop)
+    }
+
+    /// The files that already exist under `root` with other contents.
+    pub fn conflicts(&self) -> Vec<&Path> {
+        self.files
+            .iter()
+            .filter(|(path, contents)| {
+                let full_path = self.root.join(path);
+                full_path.exists()
+                    && fs::read_to_string(&full_path)
+                        .map_or(true, |existing| existing != **contents)
+            })
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    /// Writes every file and directory under `root`, resolving the files
+    /// that already exist there with `mode`.
+    ///
+    /// The write is all or nothing: the files are staged in a directory next
+    /// to `root` and only moved into place once all of them are written. If
+    /// anything fails, `root` is left as it was. With [`ConflictMode::Abort`],
+    /// nothing is written if any file conflicts.
+    pub fn write_resolving(&self, mode: ConflictMode) -> Result<WriteReport> {
+        let conflicts = self.conflicts();
+        if mode == ConflictMode::Abort && !conflicts.is_empty() {
+            let list: Vec<String> = conflicts
+                .iter()
+                .map(|path| format!("  {}", path.display()))
+                .collect();
+            anyhow::bail!(
+                "{} already has files that would be overwritten:\n{}\n\
+                Use --force to overwrite them, --skip-existing to keep them, or --merge to merge \
+                TOML files and .gitignore and keep the rest.",
+                self.root.display(),
+                list.join("\n")
+            );
+        }
+
+        // Resolve every file before touching the disk, so a file that cannot
+        // be merged fails the write before anything is changed.
+        let mut report = WriteReport::default();
+        let mut writes: Vec<(&Path, String)> = Vec::new();
+        for (path, contents) in &self.files {
+            let full_path = self.root.join(path);
+            let action = if !full_path.exists() {
+                FileAction::Created
+            } else if !conflicts.contains(&path.as_path()) {
+                FileAction::Unchanged
+            } else {
+                match mode {
+                    ConflictMode::Abort | ConflictMode::Force => FileAction::Overwritten,
+                    ConflictMode::SkipExisting => FileAction::Skipped,
+                    ConflictMode::Merge => {
+                        let existing = fs::read_to_string(&full_path)
+                            .with_context(|| format!("Failed to read {}", full_path.display()))?;
+                        match merge::merge(path, &existing, contents) {
+                            Some(merged) => {
+                                let merged = merged?;
+                                if merged == existing {
+                                    FileAction::Unchanged
+                                } else {
+                                    writes.push((path, merged));
+                                    FileAction::Merged
+                                }
+                            }
+                            None => FileAction::Skipped,
+                        }
+                    }
+                }
+            };
+            if matches!(action, FileAction::Created | FileAction::Overwritten) {
+                writes.push((path, contents.clone()));
+            }
+            report.files.push((path.clone(), action));
+        }
+
+        let staging = Staging::new(&self.root)?;
+        let result = self
+            .stage(&staging.dir, &writes)
+            .and_then(|()| staging.commit(&self.root, &self.directories, &writes));
+        staging.remove();
+        result?;
+        Ok(report)
+    }
+
+    /// Writes `writes` and the directories under `dir`.
+    fn stage(&self, dir: &Path, writes: &[(&Path, String)]) -> Result<()> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        for path in &self.directories {
+            fs::create_dir_all(dir.join(path))?;
+        }
+        for (path, contents) in writes {
+            let staged = dir.join(path);
+            if let Some(parent) = staged.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if self.private.contains(*path) {
+                keypair::write_private(&staged, contents.as_bytes())
+            } else {
+                fs::write(&staged, contents)
+            }
+            .with_context(|| format!("Failed to stage {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// A directory where a project's files are written before they are moved
+/// into place: inside the root when it already exists, so nothing is written
+/// outside the project, and next to it when the root is being created.
+struct Staging {
+    dir: PathBuf,
+    /// Where the files replaced in an existing root are kept until the write
+    /// succeeds.
+    backup: PathBuf,
+}
+
+impl Staging {
+    fn new(root: &Path) -> Result<Self> {
+        let id = std::process::id();
+        if root.is_dir() {
+            let staging = Self {
+                dir: root.join(format!(".chio-staging-{}", id)),
+                backup: root.join(format!(".chio-backup-{}", id)),
+            };
+            staging.remove();
+            return Ok(staging);
+        }
+
+        let parent = match root.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        fs::create_dir_all(&parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+
+        let name = root.file_name().unwrap_or_default().to_string_lossy();
+        let staging = Self {
+            dir: parent.join(format!(".{}.chio-staging-{}", name, id)),
+            backup: parent.join(format!(".{}.chio-backup-{}", name, id)),
+        };
+        staging.remove();
+        Ok(staging)
+    }
+
+    /// Moves the staged files into `root`: the whole directory when `root`
+    /// does not exist yet, and otherwise file by file, undoing every move if
+    /// one of them fails.
+    fn commit(
+        &self,
+        root: &Path,
+        directories: &BTreeSet<PathBuf>,
+        writes: &[(&Path, String)],
+    ) -> Result<()> {
+        if !root.exists() {
+            return fs::rename(&self.dir, root).with_context(|| {
+                format!("Failed to create project directory: {}", root.display())
+            });
+        }
+
+        let mut moves = Moves::default();
+        moves
+            .apply(self, root, directories, writes)
+            .map_err(|error| {
+                moves.undo();
+                let root = match root.to_str() {
+                    Some(".") => "The current directory".to_string(),
+                    _ => root.display().to_string(),
+                };
+                anyhow::anyhow!("{:#}. {} was left as it was.", error, root)
+            })
+    }
+
+    fn remove(&self) {
+        let _ = fs::remove_dir_all(&self.dir);
+        let _ = fs::remove_dir_all(&self.backup);
+    }
+}
+
+/// The changes made to an existing root, in order, so they can be undone.
+#[derive(Default)]
+struct Moves {
+    created_dirs: Vec<PathBuf>,
+    /// Files moved into the root, with the backup of the file they replaced.
+    placed: Vec<(PathBuf, Option<PathBuf>)>,
+}
+
+impl Moves {
+    fn apply(
+        &mut self,
+        staging: &Staging,
+        root: &Path,
+        directories: &BTreeSet<PathBuf>,
+        writes: &[(&Path, String)],
+    ) -> Result<()> {
+        for dir in directories {
+            self.create_dirs(&root.join(dir))?;
+        }
+        for (path, _) in writes {
+            let target = root.join(path);
+            if let Some(parent) = target.parent() {
+                self.create_dirs(parent)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+
+            let backup = if target.exists() {
+                let backup = staging.backup.join(path);
+                if let Some(parent) = backup.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&target, &backup)
+                    .with_context(|| format!("Failed to replace {}", path.display()))?;
+                Some(backup)
+            } else {
+                None
+            };
+            let moved = fs::rename(staging.dir.join(path), &target);
+            self.placed.push((target, backup));
+            moved.with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Creates `dir` and its missing parents, remembering each one.
+    fn create_dirs(&mut self, dir: &Path) -> Result<()> {
+        let missing: Vec<&Path> = dir.ancestors().take_while(|dir| !dir.exists()).collect();
+        for dir in missing.into_iter().rev() {
+            fs::create_dir(dir)?;
+            self.created_dirs.push(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn undo(&self) {
+        for (target, backup) in self.placed.iter().rev() {
+            let _ = fs::remove_file(target);
+            if let Some(backup) = backup {
+                let _ = fs::rename(backup, target);
+            }
+        }
+        for dir in self.created_dirs.iter().rev() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+/// What writing a project does with a file that already exists with other
+/// contents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictMode {
+    /// Fail without writing anything.
+    #[default]
+    Abort,
+    /// Replace the existing file.
+    Force,
+    /// Keep the existing file.
+    SkipExisting,
+    /// Merge TOML files and `.gitignore` into the existing file, see
+    /// [`crate::merge`], and keep the other files.
+    Merge,
+}
+
+/// What happened to a file when a project was written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileAction {
+    Created,
+    /// The file already had the generated contents.
+    Unchanged,
+    Overwritten,
+    Skipped,
+    Merged,
+}
+
+impl fmt::Display for FileAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Created => "created",
+            Self::Unchanged => "unchanged",
+            Self::Overwritten => "overwritten",
+            Self::Skipped => "skipped",
+            Self::Merged => "merged",
+        })
+    }
+}
+
+/// The files of a project, in path order, and what writing did to each.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteReport {
+    pub files: Vec<(PathBuf, FileAction)>,
+}
+
+impl WriteReport {
+    pub fn action(&self, path: impl AsRef<Path>) -> Option<FileAction> {
+        self.files
+            .iter()
+            .find(|(file, _)| file == path.as_ref())
+            .map(|(_, action)| *action)
+    }
+}
+
+/// A generated project, ready to be written.
+#[derive(Clone, Debug)]
+pub struct GeneratedProject {
+    pub name: String,
+    /// The program's id, `None` for a workspace.
+    pub program_id: Option<String>,
+    pub files: FileTree,
+    /// Commands run in the project directory once the files are written.
+    pub commands: Vec<PlannedCommand>,
+    /// What writing does with files that already exist.
+    pub conflicts: ConflictMode,
+}
+
+impl GeneratedProject {
+    /// Writes the files, then runs the planned commands. A failing command
+    /// does not fail the write; its error is returned instead and the
+    /// remaining commands are skipped.
+    pub fn write(&self) -> Result<Option<String>> {
+        self.write_with(&SystemRunner)
+    }
+
+    /// [`GeneratedProject::write`], running the commands with `runner`.
+    pub fn write_with(&self, runner: &dyn CommandRunner) -> Result<Option<String>> {
+        self.write_files()?;
+        self.run_commands(runner)
+    }
+
+    /// Writes the files, resolving existing ones with the project's
+    /// [`ConflictMode`].
+    pub fn write_files(&self) -> Result<WriteReport> {
+        self.files.write_resolving(self.conflicts)
+    }
+
+    /// Runs the planned commands in the project directory, returning the
+    /// error of the first one that fails.
+    pub fn run_commands(&self, runner: &dyn CommandRunner) -> Result<Option<String>> {
+        for command in &self.commands {
+            let output = runner
+                .output(&command.to_process().current_dir(&self.files.root))
+                .with_context(|| format!("Failed to {}", command.description))?;
+
+            if !output.is_success() {
+                return Ok(Some(format!(
+                    "Failed to {}: {}",
+                    command.description, output.stderr
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// What [`GeneratedProject::write`] would do, without file contents.
+    pub fn plan(&self) -> ProjectPlan {
+        let mut directories = self.files.directories.clone();
+        for path in self.files.paths() {
+            let parents = path.ancestors().skip(1);
+            directories.extend(
+                parents
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .map(Path::to_path_buf),
+            );
+        }
+
+        ProjectPlan {
+            root: self.files.root.clone(),
+            directories: directories.into_iter().collect(),
+            files: self
+                .files
+                .files
+                .iter()
+                .map(|(path, contents)| PlannedFile {
+                    path: path.clone(),
+                    bytes: contents.len(),
+                    private: self.files.private.contains(path),
+                })
+                .collect(),
+            commands: self.commands.clone(),
+        }
+    }
+}
+
+/// An external command a project is set up with.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PlannedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// What the command does, as in "initialize git repository".
+    pub description: String,
+}
+
+impl PlannedCommand {
+    fn new(program: &str, args: &[&str], description: &str) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            description: description.to_string(),
+        }
+    }
+
+    pub fn to_process(&self) -> ProcessCommand {
+        ProcessCommand::new(&self.program, &self.args)
+    }
+
+    /// `git init`, `git add .` and the initial commit.
+    fn git_init(project_name: &str) -> Vec<Self> {
+        let commit_message = format!("Initial commit: Setup Pinocchio project '{}'", project_name);
+        vec![
+            Self::new("git", &["init"], "initialize git repository"),
+            Self::new("git", &["add", "."], "add files to git"),
+            Self::new(
+                "git",
+                &["commit", "-m", &commit_message],
+                "make initial commit",
+            ),
+        ]
+    }
+}
+
+impl fmt::Display for PlannedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_process().fmt(f)
+    }
+}
+
+/// The directories, files and commands of a generated project, printed by
+/// `chio init --dry-run`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProjectPlan {
+    pub root: PathBuf,
+    /// Every directory created under `root`, parents first.
+    pub directories: Vec<PathBuf>,
+    pub files: Vec<PlannedFile>,
+    pub commands: Vec<PlannedCommand>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub bytes: usize,
+    /// Written readable only by the owner.
+    pub private: bool,
+}
+
+impl ProjectPlan {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Draws the directories and files as a tree, followed by the commands.
+    pub fn to_tree(&self) -> String {
+        let mut out = format!("{}/\n", self.root.display());
+        self.draw_dir(Path::new(""), "", &mut out);
+
+        if !self.commands.is_empty() {
+            out.push_str("\nCommands:\n");
+            for command in &self.commands {
+                out.push_str(&format!("  $ {}\n", command));
+            }
+        }
+        out
+    }
+
+    fn draw_dir(&self, dir: &Path, prefix: &str, out: &mut String) {
+        let dirs = self
+            .directories
+            .iter()
+            .filter(|path| path.parent() == Some(dir))
+            .map(|path| (path, true));
+        let files = self
+            .files
+            .iter()
+            .filter(|file| file.path.parent() == Some(dir))
+            .map(|file| (&file.path, false));
+        let mut entries: Vec<(&PathBuf, bool)> = dirs.chain(files).collect();
+        entries.sort();
+
+        for (index, (path, is_dir)) in entries.iter().enumerate() {
+            let last = index + 1 == entries.len();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let branch = if last { "└── " } else { "├── " };
+            let slash = if *is_dir { "/" } else { "" };
+            out.push_str(&format!("{}{}{}{}\n", prefix, branch, name, slash));
+
+            if *is_dir {
+                let indent = if last { "    " } else { "│   " };
+                self.draw_dir(path, &format!("{}{}", prefix, indent), out);
+            }
+        }
+    }
+}
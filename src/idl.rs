@@ -0,0 +1,715 @@
+//! Shank IDL generation behind `chio idl build`.
+//!
+//! The IDL is read from the project source rather than from compiled
+//! metadata, so it can be built without the Solana toolchain:
+//!
+//! - instructions are the variants of `ProgramInstruction`, with the account
+//!   metas of their Shank `#[account(...)]` attributes and the discriminators
+//!   of its `TryFrom<&u8>` impl;
+//! - instruction arguments are the struct a handler passes to `load_ix_data`,
+//!   found through the dispatch arm in `entrypoint.rs`;
+//! - accounts are the state structs deriving `ShankAccount`, with the PDA
+//!   seeds of their `SEED` constant and `validate_pda`;
+//! - errors are the variants of `MyProgramError`.
+//!
+//! [`Idl`] is the parsed interface; [`Idl::to_json`] renders it in Shank's
+//! JSON format.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::add::{self, find_block, ErrorVariant, FieldType, Generated, Project, StateField};
+
+/// The interface of a program, as declared in its source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Idl {
+    /// Crate name of the program.
+    pub name: String,
+    pub version: String,
+    /// The address in `declare_id!`.
+    pub address: String,
+    pub instructions: Vec<IdlInstruction>,
+    pub accounts: Vec<IdlAccount>,
+    pub errors: Vec<ErrorVariant>,
+}
+
+/// A `ProgramInstruction` variant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdlInstruction {
+    /// Name of the variant.
+    pub name: String,
+    pub docs: Vec<String>,
+    pub discriminator: u8,
+    /// Accounts in the order the handler expects them.
+    pub accounts: Vec<IdlAccountMeta>,
+    /// The instruction data following the discriminator, `None` when the
+    /// handler reads none.
+    pub args: Option<IdlStruct>,
+}
+
+/// An account an instruction takes, from a Shank `#[account(...)]`
+/// attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdlAccountMeta {
+    pub name: String,
+    pub writable: bool,
+    pub signer: bool,
+    pub optional: bool,
+    pub docs: Vec<String>,
+}
+
+/// A `#[repr(C)]` struct read from the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdlStruct {
+    pub name: String,
+    pub fields: Vec<StateField>,
+}
+
+impl IdlStruct {
+    /// The offset of every field and the size of the struct under
+    /// `#[repr(C)]`, padding included, as the program reads it.
+    pub fn layout(&self) -> (Vec<usize>, usize) {
+        let mut offsets = Vec::with_capacity(self.fields.len());
+        let mut offset = 0usize;
+        let mut align = 1;
+        for field in &self.fields {
+            offset = offset.next_multiple_of(field.ty.align());
+            offsets.push(offset);
+            offset += field.ty.size();
+            align = align.max(field.ty.align());
+        }
+        (offsets, offset.next_multiple_of(align))
+    }
+}
+
+/// A state struct deriving `ShankAccount`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdlAccount {
+    pub ty: IdlStruct,
+    pub docs: Vec<String>,
+    /// PDA seeds, empty when the struct has no `SEED` constant.
+    pub seeds: Vec<PdaSeed>,
+}
+
+/// A seed of the PDA a state account lives at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PdaSeed {
+    /// The `SEED` constant.
+    Constant(String),
+    /// A public key passed to `validate_pda`, such as `owner`.
+    Pubkey(String),
+}
+
+/// Where `chio idl build` writes the IDL of `project`, relative to its root.
+pub fn idl_path(project: &Project) -> PathBuf {
+    Path::new("idl").join(format!("{}.json", project.crate_name))
+}
+
+impl Idl {
+    /// Reads the interface of `project` from its source.
+    pub fn parse(project: &Project) -> Result<Self> {
+        let src = project.root.join("src");
+        let manifest = read(&project.root.join("Cargo.toml"))?;
+        let mod_rs = read(&src.join("instructions").join("mod.rs"))?;
+        let entrypoint_rs = read(&src.join("entrypoint.rs"))?;
+
+        let handlers = module_sources(&src.join("instructions"), &mod_rs)?;
+        let instructions = parse_instructions(&mod_rs)?
+            .into_iter()
+            .map(|mut instruction| {
+                instruction.args = instruction_args(&entrypoint_rs, &handlers, &instruction.name)?;
+                Ok(instruction)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let states_dir = src.join("states");
+        let states = module_sources(&states_dir, &read(&states_dir.join("mod.rs"))?)?;
+        let mut accounts = Vec::new();
+        for (_, state_rs) in &states {
+            accounts.extend(parse_accounts(state_rs)?);
+        }
+
+        Ok(Self {
+            name: project.crate_name.clone(),
+            version: package_version(&manifest).unwrap_or_else(|| "0.1.0".to_string()),
+            address: project.program_id()?,
+            instructions,
+            accounts,
+            errors: add::parse_errors(&read(&src.join("errors.rs"))?)?,
+        })
+    }
+
+    /// Renders the IDL in Shank's JSON format. Instruction arguments are a
+    /// single `defined` argument whose struct is listed under `types`, and
+    /// names are camelCase as Shank emits them.
+    pub fn to_json(&self) -> Result<String> {
+        let instructions = self
+            .instructions
+            .iter()
+            .map(|instruction| json::Instruction {
+                name: &instruction.name,
+                docs: &instruction.docs,
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|account| json::AccountMeta {
+                        name: to_camel_case(&account.name),
+                        is_mut: account.writable,
+                        is_signer: account.signer,
+                        is_optional: account.optional,
+                        docs: &account.docs,
+                    })
+                    .collect(),
+                args: instruction
+                    .args
+                    .iter()
+                    .map(|args| json::Field {
+                        name: to_camel_case(&add::to_snake_case(&args.name)),
+                        ty: serde_json::json!({ "defined": args.name }),
+                    })
+                    .collect(),
+                discriminant: json::Discriminant {
+                    ty: "u8",
+                    value: instruction.discriminator,
+                },
+            })
+            .collect();
+
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|account| json::Account {
+                name: &account.ty.name,
+                docs: &account.docs,
+                ty: json::TypeDef::from(&account.ty),
+                seeds: account.seeds.iter().map(json::Seed::from).collect(),
+            })
+            .collect();
+
+        let types = self
+            .instructions
+            .iter()
+            .filter_map(|instruction| instruction.args.as_ref())
+            .map(|args| json::NamedType {
+                name: &args.name,
+                ty: json::TypeDef::from(args),
+            })
+            .collect();
+
+        let idl = json::Idl {
+            version: &self.version,
+            name: &self.name,
+            instructions,
+            accounts,
+            types,
+            errors: &self.errors,
+            metadata: json::Metadata {
+                origin: "shank",
+                address: &self.address,
+            },
+        };
+        Ok(serde_json::to_string_pretty(&idl)? + "\n")
+    }
+}
+
+/// Builds the IDL of `project` and writes it to [`idl_path`].
+pub fn build(project: &Project) -> Result<Generated> {
+    let json = Idl::parse(project)?.to_json()?;
+    let path = idl_path(project);
+    let existed = project.root.join(&path).exists();
+
+    fs::create_dir_all(project.root.join("idl"))?;
+    fs::write(project.root.join(&path), json)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(if existed {
+        Generated {
+            created: Vec::new(),
+            updated: vec![path],
+        }
+    } else {
+        Generated {
+            created: vec![path],
+            updated: Vec::new(),
+        }
+    })
+}
+
+/// Reads the `ProgramInstruction` variants of `instructions/mod.rs`, with
+/// their docs, account metas and discriminators. Arguments are left empty.
+pub fn parse_instructions(mod_rs: &str) -> Result<Vec<IdlInstruction>> {
+    let (open, close) = find_block(mod_rs, "enum ProgramInstruction")
+        .context("Could not find `enum ProgramInstruction` in instructions/mod.rs")?;
+    let discriminators = add::instruction_variants(mod_rs);
+
+    let mut instructions = Vec::new();
+    let mut docs = Vec::new();
+    let mut accounts = Vec::new();
+    for item in items(&mod_rs[open + 1..close]) {
+        if let Some(doc) = item.strip_prefix("///") {
+            docs.push(doc.trim().to_string());
+        } else if let Some(attr) = item.strip_prefix("#[account(") {
+            let attr = attr.strip_suffix(")]").unwrap_or(attr);
+            accounts.push(parse_account_meta(attr)?);
+        } else if !item.starts_with("#[") && !item.starts_with("//") {
+            let name = item
+                .trim_end_matches(',')
+                .split('=')
+                .next()
+                .unwrap_or("")
+                .trim();
+            if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                anyhow::bail!(
+                    "ProgramInstruction variants must be fieldless, found '{}'",
+                    item
+                );
+            }
+            let discriminator = discriminators
+                .iter()
+                .find(|(_, variant)| variant == name)
+                .map(|(disc, _)| *disc)
+                .with_context(|| {
+                    format!(
+                        "ProgramInstruction::{} has no arm in `impl TryFrom<&u8> for ProgramInstruction`",
+                        name
+                    )
+                })?;
+
+            accounts.sort_by_key(|(index, _)| *index);
+            instructions.push(IdlInstruction {
+                name: name.to_string(),
+                docs: std::mem::take(&mut docs),
+                discriminator,
+                accounts: accounts.drain(..).map(|(_, meta)| meta).collect(),
+                args: None,
+            });
+        }
+    }
+    Ok(instructions)
+}
+
+/// Parses the arguments of a Shank `#[account(...)]` attribute, e.g.
+/// `0, writable, signer, name = "payer", desc = "Pays for the state"`.
+fn parse_account_meta(attr: &str) -> Result<(usize, IdlAccountMeta)> {
+    let mut index = None;
+    let mut name = None;
+    let mut meta = IdlAccountMeta {
+        name: String::new(),
+        writable: false,
+        signer: false,
+        optional: false,
+        docs: Vec::new(),
+    };
+    for part in split_args(attr) {
+        match part.split_once('=') {
+            Some((key, value)) => {
+                let value = unquote(value.trim());
+                match key.trim() {
+                    "name" => name = Some(value),
+                    "desc" | "docs" => meta.docs.push(value),
+                    other => anyhow::bail!("Unknown key '{}' in #[account({})]", other, attr),
+                }
+            }
+            None => match part.as_str() {
+                "writable" | "mut" | "w" => meta.writable = true,
+                "signer" | "sign" | "sig" | "s" => meta.signer = true,
+                "optional" | "option" | "opt" => meta.optional = true,
+                "optional_signer" => {
+                    meta.signer = true;
+                    meta.optional = true;
+                }
+                other => {
+                    index = Some(other.parse::<usize>().with_context(|| {
+                        format!("Unknown flag '{}' in #[account({})]", other, attr)
+                    })?)
+                }
+            },
+        }
+    }
+
+    meta.name = name.with_context(|| format!("#[account({})] has no name", attr))?;
+    Ok((index.unwrap_or(usize::MAX), meta))
+}
+
+/// The struct the handler of `variant` loads its instruction data into.
+fn instruction_args(
+    entrypoint_rs: &str,
+    handlers: &[(String, String)],
+    variant: &str,
+) -> Result<Option<IdlStruct>> {
+    let arm = format!("ProgramInstruction::{} =>", variant);
+    let Some(start) = entrypoint_rs.find(&arm).map(|i| i + arm.len()) else {
+        return Ok(None);
+    };
+    let arm_src = &entrypoint_rs[start..];
+    let arm_src = &arm_src[..arm_src
+        .find("ProgramInstruction::")
+        .unwrap_or(arm_src.len())];
+    let Some(handler) = arm_src.split("instructions::").nth(1).map(ident) else {
+        return Ok(None);
+    };
+
+    let handler_fn = format!("pub fn {}(", handler);
+    let Some((_, handler_rs)) = handlers.iter().find(|(_, src)| src.contains(&handler_fn)) else {
+        return Ok(None);
+    };
+    let body = &handler_rs[handler_rs.find(&handler_fn).unwrap_or(0)..];
+    let Some(args) = body.split("load_ix_data::<").nth(1).map(ident) else {
+        return Ok(None);
+    };
+
+    let args = handlers
+        .iter()
+        .find_map(|(_, src)| parse_struct(src, args).transpose())
+        .transpose()?
+        .with_context(|| format!("Could not find `pub struct {}` in src/instructions", args))?;
+    Ok(Some(args).filter(|args| !args.fields.is_empty()))
+}
+
+/// Reads the state structs deriving `ShankAccount` in `src`.
+pub fn parse_accounts(src: &str) -> Result<Vec<IdlAccount>> {
+    let mut accounts = Vec::new();
+    let mut docs = Vec::new();
+    let mut is_account = false;
+    for line in src.lines().map(str::trim) {
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim().to_string());
+        } else if line.starts_with("#[") {
+            is_account |= line.starts_with("#[derive(") && line.contains("ShankAccount");
+        } else if let Some(rest) = line.strip_prefix("pub struct ").filter(|_| is_account) {
+            let name = ident(rest);
+            let ty = parse_struct(src, name)?
+                .with_context(|| format!("Could not read the fields of {}", name))?;
+            accounts.push(IdlAccount {
+                seeds: pda_seeds(src, name),
+                ty,
+                docs: std::mem::take(&mut docs),
+            });
+            is_account = false;
+        } else {
+            docs.clear();
+            is_account = false;
+        }
+    }
+    Ok(accounts)
+}
+
+/// The `SEED` constant of `name` followed by the public keys its
+/// `validate_pda` derives the address from.
+fn pda_seeds(src: &str, name: &str) -> Vec<PdaSeed> {
+    let Some((open, close)) = find_block(src, &format!("impl {} ", name)) else {
+        return Vec::new();
+    };
+    let body = &src[open..close];
+
+    let Some(seed) = body.lines().find_map(|line| {
+        let line = line.trim();
+        let rest = line.split("const SEED:").nth(1)?;
+        Some(unquote(
+            rest.split_once('=')?.1.trim().trim_end_matches(';'),
+        ))
+    }) else {
+        return Vec::new();
+    };
+
+    let mut seeds = vec![PdaSeed::Constant(seed)];
+    if let Some(params) = body
+        .split("fn validate_pda(")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+    {
+        seeds.extend(params.split(',').filter_map(|param| {
+            let (name, ty) = param.split_once(':')?;
+            let name = name.trim();
+            (ty.trim() == "&Pubkey" && name != "pda").then(|| PdaSeed::Pubkey(name.to_string()))
+        }));
+    }
+    seeds
+}
+
+/// Reads the fields of `pub struct <name>` in `src`, or `None` when `src`
+/// does not declare it.
+pub fn parse_struct(src: &str, name: &str) -> Result<Option<IdlStruct>> {
+    let header = format!("pub struct {}", name);
+    let Some(start) = src.match_indices(&header).map(|(i, _)| i).find(|i| {
+        src[i + header.len()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c == ' ' || c == '{')
+    }) else {
+        return Ok(None);
+    };
+    let (open, close) = find_block(&src[start..], &header)
+        .with_context(|| format!("`pub struct {}` has no fields block", name))?;
+
+    let fields = items(&src[start + open + 1..start + close])
+        .filter(|item| !item.starts_with("//") && !item.starts_with("#["))
+        .flat_map(|item| {
+            item.split(',')
+                .filter(|field| !field.trim().is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .map(|field| {
+            let (field_name, ty) = field
+                .trim()
+                .trim_start_matches("pub ")
+                .split_once(':')
+                .with_context(|| {
+                    format!("Could not read the field '{}' of {}", field.trim(), name)
+                })?;
+            let ty = FieldType::parse(ty)
+                .with_context(|| format!("Unsupported type for {}.{}", name, field_name.trim()))?;
+            Ok(StateField {
+                name: field_name.trim().to_string(),
+                ty,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(IdlStruct {
+        name: name.to_string(),
+        fields,
+    }))
+}
+
+/// The modules declared with `pub mod` in `mod_rs` and their sources in
+/// `dir`, in declaration order.
+fn module_sources(dir: &Path, mod_rs: &str) -> Result<Vec<(String, String)>> {
+    mod_rs
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("pub mod "))
+        .map(|module| module.trim_end_matches(';').trim())
+        .filter(|module| dir.join(format!("{}.rs", module)).exists())
+        .map(|module| {
+            Ok((
+                module.to_string(),
+                read(&dir.join(format!("{}.rs", module)))?,
+            ))
+        })
+        .collect()
+}
+
+/// Splits a block body into its trimmed, non-empty lines, joining
+/// attributes that span several lines.
+fn items(body: &str) -> impl Iterator<Item = String> + '_ {
+    let mut pending = String::new();
+    body.lines().filter_map(move |line| {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        pending.push_str(line);
+        let opened = pending.matches('[').count();
+        if pending.starts_with("#[") && opened > pending.matches(']').count() {
+            pending.push(' ');
+            return None;
+        }
+        Some(std::mem::take(&mut pending))
+    })
+}
+
+/// Splits attribute arguments on the commas outside string literals.
+fn split_args(args: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in args.chars() {
+        match c {
+            '"' if !escaped => in_string = !in_string,
+            ',' if !in_string => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        escaped = c == '\\' && !escaped;
+        current.push(c);
+    }
+    parts.push(current.trim().to_string());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+        .replace("\\\"", "\"")
+}
+
+/// The identifier `src` starts with.
+fn ident(src: &str) -> &str {
+    let src = src.trim_start();
+    let end = src
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(src.len());
+    &src[..end]
+}
+
+/// Converts a snake_case name into camelCase.
+pub fn to_camel_case(name: &str) -> String {
+    let pascal = add::to_pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Reads the `version` key of the `[package]` table of a `Cargo.toml`.
+fn package_version(manifest: &str) -> Option<String> {
+    let manifest: toml::Table = manifest.parse().ok()?;
+    Some(
+        manifest
+            .get("package")?
+            .get("version")?
+            .as_str()?
+            .to_string(),
+    )
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// The shape of a Shank IDL.
+mod json {
+    use super::*;
+
+    #[derive(Serialize)]
+    pub struct Idl<'a> {
+        pub version: &'a str,
+        pub name: &'a str,
+        pub instructions: Vec<Instruction<'a>>,
+        pub accounts: Vec<Account<'a>>,
+        pub types: Vec<NamedType<'a>>,
+        pub errors: &'a [ErrorVariant],
+        pub metadata: Metadata<'a>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Instruction<'a> {
+        pub name: &'a str,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        pub docs: &'a [String],
+        pub accounts: Vec<AccountMeta<'a>>,
+        pub args: Vec<Field>,
+        pub discriminant: Discriminant,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AccountMeta<'a> {
+        pub name: String,
+        pub is_mut: bool,
+        pub is_signer: bool,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        pub is_optional: bool,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        pub docs: &'a [String],
+    }
+
+    #[derive(Serialize)]
+    pub struct Discriminant {
+        #[serde(rename = "type")]
+        pub ty: &'static str,
+        pub value: u8,
+    }
+
+    #[derive(Serialize)]
+    pub struct Field {
+        pub name: String,
+        #[serde(rename = "type")]
+        pub ty: serde_json::Value,
+    }
+
+    #[derive(Serialize)]
+    pub struct Account<'a> {
+        pub name: &'a str,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        pub docs: &'a [String],
+        #[serde(rename = "type")]
+        pub ty: TypeDef,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub seeds: Vec<Seed<'a>>,
+    }
+
+    #[derive(Serialize)]
+    pub struct NamedType<'a> {
+        pub name: &'a str,
+        #[serde(rename = "type")]
+        pub ty: TypeDef,
+    }
+
+    #[derive(Serialize)]
+    pub struct TypeDef {
+        pub kind: &'static str,
+        pub fields: Vec<Field>,
+    }
+
+    impl From<&IdlStruct> for TypeDef {
+        fn from(ty: &IdlStruct) -> Self {
+            Self {
+                kind: "struct",
+                fields: ty
+                    .fields
+                    .iter()
+                    .map(|field| Field {
+                        name: to_camel_case(&field.name),
+                        ty: field_type(field.ty),
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    #[serde(tag = "kind", rename_all = "camelCase")]
+    pub enum Seed<'a> {
+        Constant {
+            #[serde(rename = "type")]
+            ty: &'static str,
+            value: &'a str,
+        },
+        Variable {
+            name: String,
+            #[serde(rename = "type")]
+            ty: &'static str,
+        },
+    }
+
+    impl<'a> From<&'a PdaSeed> for Seed<'a> {
+        fn from(seed: &'a PdaSeed) -> Self {
+            match seed {
+                PdaSeed::Constant(value) => Self::Constant {
+                    ty: "string",
+                    value,
+                },
+                PdaSeed::Pubkey(name) => Self::Variable {
+                    name: to_camel_case(name),
+                    ty: "publicKey",
+                },
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    pub struct Metadata<'a> {
+        pub origin: &'static str,
+        pub address: &'a str,
+    }
+
+    fn field_type(ty: FieldType) -> serde_json::Value {
+        match ty {
+            FieldType::Pubkey => "publicKey".into(),
+            FieldType::Bytes(len) => serde_json::json!({ "array": ["u8", len] }),
+            other => other.rust_type().into(),
+        }
+    }
+}
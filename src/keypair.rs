@@ -0,0 +1,109 @@
+//! Program keypairs, generated and stored without the Solana CLI.
+//!
+//! Keypairs are written in the format `solana-keygen` uses: a JSON array of
+//! the 64 bytes made of the 32-byte ed25519 secret key followed by its
+//! public key.
+
+use anyhow::{Context, Result};
+use ed25519_dalek::SigningKey;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::runner::{CommandRunner, ProcessCommand};
+
+/// An ed25519 keypair, such as the one whose address a program deploys to.
+#[derive(Clone)]
+pub struct Keypair {
+    signing_key: SigningKey,
+}
+
+impl Keypair {
+    /// Generates a keypair from the operating system's random source.
+    pub fn generate() -> Result<Self> {
+        let mut secret = [0u8; 32];
+        getrandom::getrandom(&mut secret)
+            .map_err(|e| anyhow::anyhow!("Failed to gather randomness for keypair: {}", e))?;
+
+        Ok(Self {
+            signing_key: SigningKey::from_bytes(&secret),
+        })
+    }
+
+    /// Builds a keypair from its 64-byte secret-then-public encoding,
+    /// checking that the public half matches the secret.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes: &[u8; 64] = bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Keypair must be 64 bytes, got {}", bytes.len()))?;
+        let signing_key = SigningKey::from_keypair_bytes(bytes)
+            .map_err(|_| anyhow::anyhow!("Keypair public key does not match its secret key"))?;
+
+        Ok(Self { signing_key })
+    }
+
+    /// Reads a keypair file written by `solana-keygen` or [`Keypair::write`].
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read keypair {}", path.display()))?;
+        let bytes: Vec<u8> = serde_json::from_str(&json)
+            .with_context(|| format!("Keypair {} is not a JSON byte array", path.display()))?;
+
+        Self::from_bytes(&bytes).with_context(|| format!("Invalid keypair {}", path.display()))
+    }
+
+    /// Writes the keypair as a JSON byte array to a new file, readable only
+    /// by the owner on Unix. An existing file is never overwritten.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_private(path, self.to_json().as_bytes())
+            .with_context(|| format!("Failed to write keypair {}", path.display()))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.signing_key.to_keypair_bytes()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.to_bytes().to_vec()).expect("byte arrays always serialize")
+    }
+
+    pub fn pubkey(&self) -> [u8; 32] {
+        self.signing_key.verifying_key().to_bytes()
+    }
+
+    /// Returns the base58 address, as printed by `solana address`.
+    pub fn address(&self) -> String {
+        bs58::encode(self.pubkey()).into_string()
+    }
+}
+
+/// Returns the address of the active Solana wallet: the one `solana address`
+/// reports, or the default keypair when the Solana CLI is not installed.
+pub fn wallet_address(runner: &dyn CommandRunner) -> Option<String> {
+    let output = runner.output(&ProcessCommand::new("solana", ["address"]));
+    if let Ok(output) = output {
+        if output.is_success() {
+            return Some(output.stdout.trim().to_string());
+        }
+    }
+
+    let default_keypair =
+        std::env::var_os("HOME").map(|home| Path::new(&home).join(".config/solana/id.json"))?;
+    Keypair::read(&default_keypair)
+        .ok()
+        .map(|wallet| wallet.address())
+}
+
+/// Creates `path` with `contents`, failing when it already exists. On Unix
+/// the file is created with mode 0600, so the secret is never readable by
+/// anyone else, not even before the write finishes.
+pub fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
@@ -0,0 +1,168 @@
+//! Keeps the program id baked into the source in step with the deploy
+//! keypair.
+//!
+//! `chio init` writes the keypair address into `declare_id!` in `src/lib.rs`
+//! and into the `PROGRAM` constant of the generated tests. Swapping the
+//! keypair afterwards leaves those behind, which is what `chio keys sync`
+//! repairs.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::add::Project;
+use crate::keypair::Keypair;
+
+/// Source directories searched for program ids.
+const SOURCE_DIRS: &[&str] = &["src", "tests", "benches"];
+
+/// A program id literal found in the project source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramIdLiteral {
+    /// File path relative to the project root.
+    pub path: PathBuf,
+    /// One-based line number.
+    pub line: usize,
+    pub address: String,
+}
+
+/// Returns the path of the deploy keypair of `project`, relative to
+/// [`Project::base_dir`].
+pub fn keypair_path(project: &Project) -> PathBuf {
+    Path::new("target")
+        .join("deploy")
+        .join(format!("{}-keypair.json", project.crate_name))
+}
+
+/// Reads the address of the deploy keypair of `project`.
+pub fn keypair_address(project: &Project) -> Result<String> {
+    let path = keypair_path(project);
+    let keypair = Keypair::read(&project.base_dir().join(&path)).with_context(|| {
+        format!(
+            "No deploy keypair for '{}'. Expected it at {}",
+            project.crate_name,
+            path.display()
+        )
+    })?;
+    Ok(keypair.address())
+}
+
+/// Finds the program id literals in `src`: the argument of every
+/// `declare_id!` and of every `pubkey!` assigned to a `PROGRAM` constant.
+/// Returns the one-based line and the address of each.
+pub fn program_id_literals(src: &str) -> Vec<(usize, String)> {
+    src.lines()
+        .enumerate()
+        .filter_map(|(i, line)| Some((i + 1, program_id_in_line(line)?.1.to_string())))
+        .collect()
+}
+
+/// Rewrites every program id literal in `src` to `address`.
+pub fn replace_program_ids(src: &str, address: &str) -> String {
+    src.split_inclusive('\n')
+        .map(|line| match program_id_in_line(line) {
+            Some((start, current)) => format!(
+                "{}{}{}",
+                &line[..start],
+                address,
+                &line[start + current.len()..]
+            ),
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+/// Collects the program id literals of every Rust file under the project's
+/// source directories, in path order.
+pub fn find_program_ids(project: &Project) -> Result<Vec<ProgramIdLiteral>> {
+    let mut literals = Vec::new();
+    for path in source_files(project)? {
+        let src = fs::read_to_string(project.root.join(&path))
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        for (line, address) in program_id_literals(&src) {
+            literals.push(ProgramIdLiteral {
+                path: path.clone(),
+                line,
+                address,
+            });
+        }
+    }
+    Ok(literals)
+}
+
+/// Returns the literals that differ from `address`. Unless `check` is set,
+/// the files containing them are rewritten to use `address`.
+pub fn sync_program_ids(
+    project: &Project,
+    address: &str,
+    check: bool,
+) -> Result<Vec<ProgramIdLiteral>> {
+    let stale: Vec<ProgramIdLiteral> = find_program_ids(project)?
+        .into_iter()
+        .filter(|literal| literal.address != address)
+        .collect();
+
+    if !check {
+        let mut paths: Vec<&PathBuf> = stale.iter().map(|literal| &literal.path).collect();
+        paths.dedup();
+        for path in paths {
+            let full_path = project.root.join(path);
+            let src = fs::read_to_string(&full_path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            fs::write(&full_path, replace_program_ids(&src, address))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(stale)
+}
+
+/// Returns the byte offset and text of the program id literal on `line`.
+fn program_id_in_line(line: &str) -> Option<(usize, &str)> {
+    let marker = if line.contains("declare_id!(\"") {
+        "declare_id!(\""
+    } else if is_program_const(line) && line.contains("pubkey!(\"") {
+        "pubkey!(\""
+    } else {
+        return None;
+    };
+
+    let start = line.find(marker)? + marker.len();
+    let len = line[start..].find('"')?;
+    Some((start, &line[start..start + len]))
+}
+
+/// Whether `line` declares the constant `PROGRAM` itself, not another
+/// `PROGRAM_*` constant such as `PROGRAM_AUTHORITY`.
+fn is_program_const(line: &str) -> bool {
+    line.split_once("const ").is_some_and(|(_, rest)| {
+        rest.trim_start()
+            .strip_prefix("PROGRAM")
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    })
+}
+
+fn source_files(project: &Project) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending: Vec<PathBuf> = SOURCE_DIRS.iter().map(PathBuf::from).collect();
+
+    while let Some(dir) = pending.pop() {
+        let full_dir = project.root.join(&dir);
+        if !full_dir.is_dir() {
+            continue;
+        }
+        for entry in
+            fs::read_dir(&full_dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let path = dir.join(entry?.file_name());
+            if project.root.join(&path).is_dir() {
+                pending.push(path);
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some("rs") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
@@ -1,32 +1,38 @@
 //! # Chio - Solana Pinocchio Project Scaffolder
 //!
 //! A CLI tool for quickly setting up Solana Pinocchio programs.
-//! This library exposes core functionality for testing purposes.
+//! This library exposes core functionality for testing purposes, and
+//! [`generator::ProjectGenerator`] lets other tools scaffold projects
+//! without going through the CLI.
 
+pub mod add;
+pub mod bench;
+pub mod build;
+pub mod client;
+pub mod config;
 pub mod content;
+pub mod deploy;
+pub mod doctor;
+pub mod generator;
+pub mod idl;
+pub mod keypair;
+pub mod keys;
+pub mod merge;
+pub mod name;
+pub mod runner;
+pub mod scripts;
+pub mod size;
+pub mod template;
+pub mod template_set;
 
-/// Validates that a project name only contains alphanumeric characters and underscores.
-///
-/// Valid names:
-/// - `my_project`
-/// - `MyProject`
-/// - `project_123`
-/// - `_project`
-///
-/// Invalid names:
-/// - `my-project` (contains hyphen)
-/// - `my project` (contains space)
-/// - `` (empty string)
-///
-/// # Arguments
-/// * `name` - The project name to validate
-///
-/// # Returns
-/// `true` if the name is valid, `false` otherwise
-pub fn is_valid_project_name(name: &str) -> bool {
-    if name.is_empty() {
-        return false;
-    }
+pub use name::{is_valid_project_name, validate_project_name};
 
-    name.chars().all(|c| c.is_alphanumeric() || c == '_')
+/// Test harness a generated program is scaffolded with.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Debug, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Mollusk,
+    Litesvm,
 }
@@ -1,22 +1,36 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Parser, Subcommand};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
-use std::process::Command;
-
-use chio::content::templates;
-use chio::is_valid_project_name;
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug, ValueEnum)]
-enum TestFramework {
-    Mollusk,
-    Litesvm,
-}
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chio::add::{self, Generated, Project, StateField};
+use chio::bench::{self, ProgramBench};
+use chio::build::{self, BuildOptions};
+use chio::client::{self, Language};
+use chio::config::{self, ChioConfig, ProgramConfig};
+use chio::deploy::{self, DeployOptions};
+use chio::doctor::{self, Report, Severity};
+use chio::generator::{ConflictMode, GeneratedProject, ProjectGenerator};
+use chio::idl;
+use chio::keypair::{self, Keypair};
+use chio::keys;
+use chio::name::crate_name;
+use chio::runner::{CommandRunner, SystemRunner};
+use chio::scripts::{self, Script};
+use chio::size::{self, ProgramSize};
+use chio::template_set::{self, TemplateSet};
+use chio::{validate_project_name, TestFramework};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
+    /// Workspace program to operate on, all of them when omitted. For deploy
+    /// outside a workspace, the program in target/deploy to deploy.
+    #[arg(long, global = true)]
+    program: Option<String>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -24,93 +38,429 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     Init {
+        /// Name of the project and its directory, or '.' for the current directory
         project_name: String,
         #[arg(long, value_enum, default_value_t = TestFramework::Mollusk)]
         test_framework: TestFramework,
+        /// Create a workspace for several programs, added with 'chio add program'
+        #[arg(long, conflicts_with_all = ["test_framework", "template"])]
+        workspace: bool,
+        /// Scaffold from a template directory or git repository instead of the built-in templates
+        #[arg(long, value_name = "PATH|GIT_URL")]
+        template: Option<String>,
+        /// Set a variable declared by the template (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE", requires = "template")]
+        vars: Vec<String>,
+        /// Print the files, directories and commands init would create, without creating them
+        #[arg(long)]
+        dry_run: bool,
+        /// How --dry-run prints the plan
+        #[arg(long, value_enum, default_value_t = PlanFormat::Tree, requires = "dry_run")]
+        format: PlanFormat,
+        /// Overwrite files that already exist in the project directory
+        #[arg(long, conflicts_with_all = ["skip_existing", "merge"])]
+        force: bool,
+        /// Keep files that already exist in the project directory
+        #[arg(long, conflicts_with = "merge")]
+        skip_existing: bool,
+        /// Merge Cargo.toml, Chio.toml and .gitignore into existing ones, keeping other existing files
+        #[arg(long)]
+        merge: bool,
+    },
+    /// Build the program with cargo build-sbf
+    Build {
+        /// Record these options in Chio.toml as the project's build defaults
+        #[arg(long)]
+        save: bool,
+        #[command(flatten)]
+        options: BuildOptions,
     },
-    Build,
     Test,
-    Deploy,
+    /// Measure the compute units of every instruction against the committed baseline
+    Bench,
+    /// Deploy the built program
+    Deploy {
+        /// Deploy even if the program keypair does not match declare_id!
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        options: DeployOptions,
+    },
+    /// Generate code inside an existing project
+    Add {
+        #[command(subcommand)]
+        target: AddTarget,
+    },
+    /// Inspect and sync the program id with the deploy keypair
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+    /// Generate the program's Shank IDL from its source
+    Idl {
+        #[command(subcommand)]
+        command: IdlCommand,
+    },
+    /// Generate clients for the program from its source
+    Client {
+        #[command(subcommand)]
+        command: ClientCommand,
+    },
+    /// Check the toolchain, Solana config and project dependencies
+    Doctor,
+    /// Work with project templates
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommand,
+    },
     #[command(name = "--help")]
     Help,
 }
 
+#[derive(Subcommand)]
+enum AddTarget {
+    /// Add a program to a workspace under programs/<name>
+    Program {
+        name: String,
+        #[arg(long, value_enum, default_value_t = TestFramework::Mollusk)]
+        test_framework: TestFramework,
+    },
+    /// Add an instruction handler, its discriminator and a test
+    Instruction { name: String },
+    /// Add a zero-copy account state struct
+    State {
+        name: String,
+        /// Field as NAME:TYPE, e.g. owner:Pubkey (repeatable)
+        #[arg(long = "field", value_name = "NAME:TYPE")]
+        fields: Vec<String>,
+        /// PDA seed prefix, defaults to the snake_case state name
+        #[arg(long)]
+        seed: Option<String>,
+    },
+    /// Add a MyProgramError variant and regenerate idl/errors.table.json
+    Error {
+        name: String,
+        /// Stable error code, defaults to the highest code in use plus one
+        #[arg(long)]
+        code: Option<u32>,
+        /// Message shown in logs and the error table
+        #[arg(long)]
+        message: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// Show the deploy keypair address and every program id in the source
+    List,
+    /// Rewrite declare_id! and PROGRAM constants to the deploy keypair address
+    Sync {
+        /// Only report mismatches, exiting non-zero if there are any
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum IdlCommand {
+    /// Write idl/<crate_name>.json from the instructions, states and errors in src/
+    Build,
+}
+
+#[derive(Subcommand)]
+enum ClientCommand {
+    /// Write clients with instruction builders, account decoders, PDA finders and errors to clients/<lang>
+    Generate {
+        /// Language to generate a client in (repeatable), every language when omitted
+        #[arg(long = "lang", value_enum)]
+        languages: Vec<Language>,
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum PlanFormat {
+    Tree,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum TemplateCommand {
+    /// Write the built-in templates and their template.toml to a directory
+    Export { dir: PathBuf },
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let runner = SystemRunner;
 
     match &cli.command {
         Commands::Init {
             project_name,
             test_framework,
+            workspace,
+            template,
+            vars,
+            dry_run,
+            format,
+            force,
+            skip_existing,
+            merge,
         } => {
-            init_project(project_name, *test_framework)?;
+            let dry_run = dry_run.then_some(*format);
+            let conflicts = match (force, skip_existing, merge) {
+                (true, _, _) => ConflictMode::Force,
+                (_, true, _) => ConflictMode::SkipExisting,
+                (_, _, true) => ConflictMode::Merge,
+                _ => ConflictMode::Abort,
+            };
+            let target = InitTarget::resolve(project_name)?;
+            if *workspace {
+                init_workspace(&runner, &target, conflicts, dry_run)?;
+            } else {
+                let set = match template {
+                    Some(source) if dry_run.is_some() && template_set::is_git_url(source) => {
+                        anyhow::bail!(
+                            "--dry-run does not clone templates. Clone {} and pass the directory to --template.",
+                            source
+                        )
+                    }
+                    Some(source) => load_template_set(&runner, source)?,
+                    None => TemplateSet::builtin(),
+                };
+                let vars = vars
+                    .iter()
+                    .map(|spec| template_set::parse_variable(spec))
+                    .collect::<Result<BTreeMap<_, _>>>()?;
+                init_project(
+                    &runner,
+                    &target,
+                    *test_framework,
+                    set,
+                    vars,
+                    conflicts,
+                    dry_run,
+                )?;
+            }
         }
-        Commands::Build => {
+        Commands::Build { save, options } => {
+            let root = Path::new(".");
+            if *save {
+                build::save_defaults(root, options)?;
+                println!("📝 Saved build options to {}", config::CONFIG_FILE);
+            }
             println!("Building program");
-            let status = Command::new("cargo")
-                .arg("build-sbf")
-                .spawn()?
-                .wait()
-                .with_context(|| "Failed to build project")?;
-
-            if !status.success() {
-                anyhow::bail!("Build failed with exit code: {:?}", status.code());
-            } else {
-                println!("Build completed successfully!");
+            build::run_build(&runner, root, cli.program.as_deref(), options)?;
+            println!("Build completed successfully!");
+
+            let options = build::resolve_options(root, options)?;
+            let sizes = size::program_sizes(root, cli.program.as_deref(), &options)?;
+            print_sizes(&sizes);
+            if let Some(max_size) = options.max_size {
+                size::check_max_size(&sizes, max_size)?;
+                println!(
+                    "✅ Within the size budget of {}",
+                    size::format_bytes(max_size)
+                );
             }
         }
         Commands::Test => {
             println!("Testing program");
-            let status = Command::new("cargo")
-                .arg("test")
-                .spawn()?
-                .wait()
-                .with_context(|| "Failed to test project")?;
-
-            if !status.success() {
-                anyhow::bail!("Test failed with exit code: {:?}", status.code());
-            } else {
-                println!("Tested successfully!");
-            }
+            scripts::run_script(
+                &runner,
+                Path::new("."),
+                Script::Test,
+                cli.program.as_deref(),
+            )?;
+            println!("Tested successfully!");
         }
-        Commands::Deploy => {
+        Commands::Bench => {
+            println!("⏱️  Benchmarking compute units");
+            let benches = bench::run_bench(&runner, Path::new("."), cli.program.as_deref())?;
+            print_benches(&benches);
+        }
+        Commands::Deploy { force, options } => {
             println!("Deploying program");
 
-            let target_deploy_dir = Path::new("target/deploy");
+            let root = Path::new(".");
+            let deploy_dir = build::deploy_dir(root)?;
+            let target_deploy_dir = deploy_dir.as_path();
             if !target_deploy_dir.exists() {
-                anyhow::bail!("target/deploy directory not found. Please run 'chio build' first.");
+                anyhow::bail!(
+                    "{} directory not found. Please run 'chio build' first.",
+                    target_deploy_dir.display()
+                );
             }
 
-            let mut so_file = None;
-            for entry in fs::read_dir(target_deploy_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.extension().and_then(|s| s.to_str()) == Some("so") {
-                    so_file = Some(path);
-                    break;
+            let targets: Vec<(Project, PathBuf)> = match ChioConfig::load(root)? {
+                Some(config) if config.is_workspace() => {
+                    workspace_projects(&config, cli.program.as_deref())?
+                        .into_iter()
+                        .map(|project| {
+                            let so_path = deploy::select_program(
+                                target_deploy_dir,
+                                &project.crate_name,
+                                Some(&project.crate_name),
+                            )?;
+                            Ok((project, so_path))
+                        })
+                        .collect::<Result<_>>()?
+                }
+                _ => {
+                    let project = Project::open(root)?;
+                    let so_path = deploy::select_program(
+                        target_deploy_dir,
+                        &project.crate_name,
+                        cli.program.as_deref(),
+                    )?;
+                    vec![(project, so_path)]
                 }
+            };
+
+            for (project, so_path) in &targets {
+                deploy_program(&runner, project, so_path, options, *force)?;
+            }
+        }
+        Commands::Add { target } => {
+            if let AddTarget::Program {
+                name,
+                test_framework,
+            } = target
+            {
+                add_program(&runner, name, *test_framework)?;
+                return Ok(());
             }
 
-            let so_path = so_file.ok_or_else(|| {
-                anyhow::anyhow!(
-                    "No .so file found in target/deploy. Please run 'chio build' first."
-                )
-            })?;
-
-            let status = Command::new("solana")
-                .arg("program")
-                .arg("deploy")
-                .arg(&so_path)
-                .spawn()?
-                .wait()
-                .with_context(|| "Failed to deploy program")?;
-
-            if !status.success() {
-                anyhow::bail!("Deploy failed with exit code: {:?}", status.code());
-            } else {
-                println!("Program deployed successfully!");
+            let project = open_project(cli.program.as_deref())?;
+            match target {
+                AddTarget::Program { .. } => unreachable!("handled above"),
+                AddTarget::Instruction { name } => {
+                    let generated = add::add_instruction(&project, name)?;
+                    print_generated(&project, &generated);
+                    println!("✅ Instruction '{}' added", name);
+                }
+                AddTarget::State { name, fields, seed } => {
+                    let fields = fields
+                        .iter()
+                        .map(|spec| StateField::parse(spec))
+                        .collect::<Result<Vec<_>>>()?;
+                    let generated = add::add_state(&project, name, &fields, seed.as_deref())?;
+                    print_generated(&project, &generated);
+                    println!("✅ State '{}' added", name);
+                }
+                AddTarget::Error {
+                    name,
+                    code,
+                    message,
+                } => {
+                    let generated = add::add_error(&project, name, *code, message.as_deref())?;
+                    print_generated(&project, &generated);
+                    println!("✅ Error '{}' added", name);
+                }
             }
         }
+        Commands::Keys { command } => {
+            let mut stale_count = 0;
+            for project in open_projects(cli.program.as_deref())? {
+                let address = keys::keypair_address(&project)?;
+                match command {
+                    KeysCommand::List => {
+                        println!(
+                            "{}: {} ({})",
+                            project.crate_name,
+                            address,
+                            keys::keypair_path(&project).display()
+                        );
+                        for literal in keys::find_program_ids(&project)? {
+                            let status = if literal.address == address {
+                                "✅"
+                            } else {
+                                "❌"
+                            };
+                            println!(
+                                "  {} {}:{} {}",
+                                status,
+                                project_path(&project, &literal.path).display(),
+                                literal.line,
+                                literal.address
+                            );
+                        }
+                    }
+                    KeysCommand::Sync { check } => {
+                        let stale = keys::sync_program_ids(&project, &address, *check)?;
+                        for literal in &stale {
+                            println!(
+                                "  {}:{} {} -> {}",
+                                project_path(&project, &literal.path).display(),
+                                literal.line,
+                                literal.address,
+                                address
+                            );
+                        }
+                        if stale.is_empty() {
+                            println!("✅ Program id {} is in sync", address);
+                        } else if !*check {
+                            println!("✅ Synced {} program id(s) to {}", stale.len(), address);
+                        }
+                        stale_count += stale.len();
+                    }
+                }
+            }
+
+            if matches!(command, KeysCommand::Sync { check: true }) && stale_count > 0 {
+                anyhow::bail!(
+                    "{} program id(s) do not match the deploy keypair. Run 'chio keys sync' to fix them.",
+                    stale_count
+                );
+            }
+        }
+        Commands::Idl { command } => match command {
+            IdlCommand::Build => {
+                for project in open_projects(cli.program.as_deref())? {
+                    let generated = idl::build(&project)?;
+                    print_generated(&project, &generated);
+                    println!("✅ IDL for {} built", project.crate_name);
+                }
+            }
+        },
+        Commands::Client { command } => match command {
+            ClientCommand::Generate { languages } => {
+                let languages = match languages.is_empty() {
+                    true => vec![Language::Rust, Language::Js],
+                    false => languages.clone(),
+                };
+                for project in open_projects(cli.program.as_deref())? {
+                    for language in &languages {
+                        let generated = client::generate(&project, *language)?;
+                        print_generated(&project, &generated);
+                    }
+                    println!("✅ Clients for {} generated", project.crate_name);
+                }
+            }
+        },
+        Commands::Doctor => {
+            let home = std::env::var_os("HOME").map(PathBuf::from);
+            let report = doctor::diagnose(&runner, Path::new("."), home.as_deref())?;
+            print_report(&report);
+            if report.errors() > 0 {
+                anyhow::bail!(
+                    "{} blocking problem(s) found. Fix them before building or deploying.",
+                    report.errors()
+                );
+            }
+        }
+        Commands::Template { command } => match command {
+            TemplateCommand::Export { dir } => {
+                if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+                    anyhow::bail!("{} is not empty", dir.display());
+                }
+                TemplateSet::builtin().export(dir)?;
+                println!(
+                    "✅ Built-in templates written to {}. Scaffold from them with 'chio init <name> --template {}'",
+                    dir.display(),
+                    dir.display()
+                );
+            }
+        },
         Commands::Help => {
             display_help_banner()?;
         }
@@ -119,6 +469,279 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Opens the selected workspace program, or every program when none is
+/// selected.
+fn workspace_projects(config: &ChioConfig, program: Option<&str>) -> Result<Vec<Project>> {
+    let members = match program {
+        Some(name) => vec![config.member(name)?],
+        None => config.program_configs(),
+    };
+    if members.is_empty() {
+        anyhow::bail!(
+            "This workspace has no programs yet. Add one with 'chio add program <name>'."
+        );
+    }
+
+    members
+        .iter()
+        .map(|member| Project::open_member(Path::new("."), &member.name))
+        .collect()
+}
+
+/// Opens the projects a command applies to: the workspace programs selected
+/// by `program`, or the project in the current directory.
+fn open_projects(program: Option<&str>) -> Result<Vec<Project>> {
+    let root = Path::new(".");
+    match ChioConfig::load(root)? {
+        Some(config) if config.is_workspace() => workspace_projects(&config, program),
+        _ if program.is_some() => anyhow::bail!(
+            "--program selects a workspace program, but this project is not a workspace"
+        ),
+        _ => Ok(vec![Project::open(root)?]),
+    }
+}
+
+/// Like [`open_projects`], for commands that work on a single program.
+fn open_project(program: Option<&str>) -> Result<Project> {
+    let mut projects = open_projects(program)?;
+    if projects.len() > 1 {
+        let names: Vec<&str> = projects.iter().map(|p| p.crate_name.as_str()).collect();
+        anyhow::bail!(
+            "This workspace has several programs ({}). Pass --program <name> to choose one.",
+            names.join(", ")
+        );
+    }
+    Ok(projects.remove(0))
+}
+
+/// Path of `path` inside `project`, as shown to the user.
+fn project_path(project: &Project, path: &Path) -> PathBuf {
+    let path = project.root.join(path);
+    match path.strip_prefix(".") {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path,
+    }
+}
+
+fn deploy_program(
+    runner: &dyn CommandRunner,
+    project: &Project,
+    so_path: &Path,
+    options: &DeployOptions,
+    force: bool,
+) -> Result<()> {
+    println!("Selected {}", so_path.display());
+
+    let program_name = so_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let mut options = match &project.config {
+        Some(config) => options.or_config(config, &program_name),
+        None => options.clone(),
+    };
+
+    let check = deploy::check_program_id(project, so_path, options.program_keypair.as_deref())?;
+    if !check.is_match() {
+        let mismatch = format!(
+            "{} deploys to {} but {} declares {}. \
+            The deployed program would fail every owner check.",
+            check.keypair_path.display(),
+            check.address,
+            project_path(project, &Path::new("src").join("lib.rs")).display(),
+            check.declared
+        );
+        if !force {
+            anyhow::bail!(
+                "{}\nRun 'chio keys sync' and rebuild, or pass --force to deploy anyway.",
+                mismatch
+            );
+        }
+        println!("Warning: {}", mismatch);
+    }
+
+    // Resolved before deploying, so a mainnet RPC from `solana config` is
+    // confirmed too, and passed on so the deploy cannot go anywhere else.
+    let cluster = deploy::resolve_cluster(runner, &options)?;
+    if !options.yes && deploy::targets_mainnet(runner, &cluster) {
+        confirm_mainnet_deploy(&check.address)?;
+    }
+    println!("Deploying to {}", cluster);
+    options.cluster = Some(cluster);
+
+    deploy::run_deploy(runner, so_path, &check.keypair_path, &options)?;
+    println!("Program deployed successfully!");
+
+    // Reloaded so that deploying several workspace programs keeps every id.
+    if let (Some(mut config), Some(cluster)) =
+        (ChioConfig::load(project.base_dir())?, &options.cluster)
+    {
+        // Recorded under the name in Chio.toml, which may use hyphens where
+        // the .so name has underscores.
+        let name = config
+            .program_config(&program_name)
+            .map_or(program_name, |program| program.name.clone());
+        config.record_program_id(cluster, &name, &check.address);
+        config.save(project.base_dir())?;
+    }
+    Ok(())
+}
+
+fn confirm_mainnet_deploy(address: &str) -> Result<()> {
+    print!(
+        "You are about to deploy {} to mainnet, which spends real SOL. Type 'yes' to continue: ",
+        address
+    );
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim() != "yes" {
+        anyhow::bail!("Mainnet deploy cancelled");
+    }
+    Ok(())
+}
+
+fn print_generated(project: &Project, generated: &Generated) {
+    for path in &generated.created {
+        println!("  created  {}", project_path(project, path).display());
+    }
+    for path in &generated.updated {
+        println!("  updated  {}", project_path(project, path).display());
+    }
+}
+
+fn print_benches(benches: &[ProgramBench]) {
+    let mut regressions = 0;
+    for program in benches {
+        println!();
+        if program.has_baseline {
+            println!(
+                "📊 {} ({} compared with HEAD)",
+                program.program,
+                program.report.display()
+            );
+        } else {
+            println!(
+                "📊 {} ({}, no committed baseline)",
+                program.program,
+                program.report.display()
+            );
+        }
+
+        let width = program
+            .comparisons
+            .iter()
+            .map(|comparison| comparison.name.len())
+            .max()
+            .unwrap_or_default();
+        for comparison in &program.comparisons {
+            let icon = if comparison.is_regression() {
+                regressions += 1;
+                "⚠️ "
+            } else {
+                "  "
+            };
+            println!(
+                "{} {:width$}  {}",
+                icon,
+                comparison.name,
+                comparison,
+                width = width
+            );
+        }
+        if !program.has_baseline {
+            println!(
+                "   Commit {} to compare later runs with it.",
+                program.report.display()
+            );
+        }
+    }
+
+    println!();
+    match regressions {
+        0 => println!("No instruction uses more compute units than the baseline."),
+        n => println!(
+            "{} instruction(s) use more compute units than the baseline.",
+            n
+        ),
+    }
+}
+
+fn print_sizes(programs: &[ProgramSize]) {
+    for program in programs {
+        println!();
+        println!("📦 {} ({})", program.program, program.path.display());
+        println!("   Size:              {}", size::format_bytes(program.size));
+        println!(
+            "   Deploy deposit:    {} (rent exempt program data and program accounts)",
+            size::format_sol(program.deploy_deposit())
+        );
+
+        println!("   Sections:");
+        let width = program
+            .sections
+            .iter()
+            .map(|section| section.name.len())
+            .max()
+            .unwrap_or_default();
+        for section in &program.sections {
+            println!(
+                "     {:width$}  {:>10}",
+                section.name,
+                section.size,
+                width = width
+            );
+        }
+
+        if program.symbols.is_empty() {
+            continue;
+        }
+        match &program.symbols_from {
+            Some(path) => println!("   Largest symbols (from {}):", path.display()),
+            None => println!("   Largest symbols:"),
+        }
+        for symbol in &program.symbols {
+            println!("     {:>10}  {}", symbol.size, symbol.name);
+        }
+    }
+}
+
+fn print_report(report: &Report) {
+    println!("🩺 Checking your Solana toolchain\n");
+    let width = report
+        .checks
+        .iter()
+        .map(|check| check.name.len())
+        .max()
+        .unwrap_or_default();
+
+    for check in &report.checks {
+        let icon = match check.severity {
+            Severity::Ok => "✅",
+            Severity::Warning => "⚠️ ",
+            Severity::Error => "❌",
+        };
+        println!(
+            "{} {:width$}  {}",
+            icon,
+            check.name,
+            check.detail,
+            width = width
+        );
+        if let Some(fix) = &check.fix {
+            println!("   {:width$}  → {}", "", fix, width = width);
+        }
+    }
+
+    println!();
+    match (report.errors(), report.warnings()) {
+        (0, 0) => println!("Everything looks good!"),
+        (errors, warnings) => println!("{} error(s), {} warning(s)", errors, warnings),
+    }
+}
+
 fn display_help_banner() -> Result<()> {
     // banner
     println!(
@@ -135,320 +758,290 @@ fn display_help_banner() -> Result<()> {
 
     println!("\n🏗️ AVAILABLE COMMANDS:");
     println!("   chio init <project_name> - Initialize a new Pinocchio project");
+    println!("   chio init --workspace <name> - Initialize a workspace for several programs");
+    println!("   chio init . [--force|--skip-existing|--merge] - Initialize the current directory");
+    println!("   chio add program <name>  - Add a program to the workspace");
+    println!("   chio init <name> --template <path|git url> - Scaffold from a custom template");
+    println!("   chio init <name> --dry-run [--format json] - Show what init would create");
+    println!("   chio template export <dir> - Write the built-in templates as a starting point");
     println!("   chio build               - Build the project");
+    println!("   chio build --save [opts] - Build with cargo build-sbf options and keep them in Chio.toml");
+    println!("   chio build --max-size N  - Fail the build when a program .so is over N bytes");
     println!("   chio test                - Run project tests");
+    println!("   chio bench               - Compare compute units with the committed baseline");
     println!("   chio deploy              - Deploy the project");
+    println!("   chio add instruction <name> - Add an instruction to the project");
+    println!("   chio add state <Name> --field <name:Type> - Add an account state");
+    println!("   chio add error <Variant> - Add a program error code");
+    println!("   chio idl build           - Generate the Shank IDL in idl/<name>.json");
+    println!("   chio client generate [--lang rust|js] - Generate clients in clients/rust and clients/js");
+    println!("   chio doctor              - Check the toolchain and project dependencies");
+    println!("   chio keys list           - Show the program id and where it is used");
+    println!("   chio keys sync [--check] - Sync declare_id! with the deploy keypair");
+    println!("   --program <name>         - Limit a command to one workspace program");
 
     Ok(())
 }
 
-fn init_project(project_name: &str, test_framework: TestFramework) -> Result<()> {
-    // Validate project name - only allow alphanumeric characters and underscores
-    if !is_valid_project_name(project_name) {
-        anyhow::bail!(
-            "Invalid project name '{}'. Project names can only contain letters, numbers, and underscores (_). \
-            Hyphens (-) and other special characters are not allowed.",
-            project_name
-        );
-    }
+/// The project `chio init` creates and the directory it goes in.
+struct InitTarget {
+    name: String,
+    dir: PathBuf,
+}
 
-    println!(
-        r#"
-      *     *
-  ___| |__ (_) ___
- / __| '_ \| |/ _ \
-| (__| | | | | (_) |
- \___|_| |_|_|\___/
+impl InitTarget {
+    /// `.` initializes the current directory, naming the project after it.
+    fn resolve(project_name: &str) -> Result<Self> {
+        if project_name != "." {
+            return Ok(Self {
+                name: project_name.to_string(),
+                dir: PathBuf::from(project_name),
+            });
+        }
 
- "#
-    );
-    println!("🧑🏻‍🍳 Initializing your pinocchio project: {}", project_name);
-    println!(""); // Create the project directory
-    let project_dir = Path::new(project_name);
-    fs::create_dir_all(project_dir)
-        .with_context(|| format!("Failed to create project directory: {}", project_name))?;
-
-    // init new cargo project inside
-    let output = Command::new("cargo")
-        .arg("init")
-        .arg("--lib")
-        .arg("--name")
-        .arg(project_name)
-        .current_dir(project_dir)
-        .output()
-        .with_context(|| "Failed to run 'cargo init'")?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to initialize Cargo project: {}", error);
+        let current_dir = std::env::current_dir()?;
+        let name = current_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("The current directory has no name to give the project")?;
+        validate_project_name(name).with_context(|| {
+            format!(
+                "'chio init .' names the project after the current directory, '{}'",
+                name
+            )
+        })?;
+        Ok(Self {
+            name: name.to_string(),
+            dir: PathBuf::from("."),
+        })
     }
 
-    let deploy_dir = project_dir.join("target").join("deploy");
-    fs::create_dir_all(&deploy_dir)?;
-
-    // generate keypair
-    let keypair_path = format!("./target/deploy/{}-keypair.json", project_name);
-    let keygen_output = Command::new("solana-keygen")
-        .arg("new")
-        .arg("-o")
-        .arg(&keypair_path)
-        .arg("--no-bip39-passphrase") // skip the passphrase prompt
-        .current_dir(project_dir)
-        .output()
-        .with_context(|| "Failed to generate keypair")?;
-
-    if !keygen_output.status.success() {
-        let error = String::from_utf8_lossy(&keygen_output.stderr);
-        anyhow::bail!("Failed to generate keypair: {}", error);
+    fn is_current_dir(&self) -> bool {
+        self.dir == Path::new(".")
     }
 
-    let address_output = Command::new("solana")
-        .arg("address")
-        .arg("-k")
-        .arg(&keypair_path)
-        .current_dir(project_dir)
-        .output()
-        .with_context(|| "Failed to read keypair address")?;
-
-    let program_address: String;
-    if address_output.status.success() {
-        program_address = String::from_utf8_lossy(&address_output.stdout)
-            .trim()
-            .to_string();
-        println!("Generated program address: {}", program_address);
-    } else {
-        let error = String::from_utf8_lossy(&address_output.stderr);
-        anyhow::bail!("Failed to get program address from keypair: {}", error);
+    /// Git is only set up in directories that are not already a repository.
+    fn needs_git(&self) -> bool {
+        !self.dir.join(".git").exists()
     }
 
-    let user_address_output = Command::new("solana")
-        .arg("address")
-        .current_dir(project_dir)
-        .output()
-        .with_context(|| "Failed to get user address")?;
-
-    let user_address: String;
-    if user_address_output.status.success() {
-        user_address = String::from_utf8_lossy(&user_address_output.stdout)
-            .trim()
-            .to_string();
-    } else {
-        let error = String::from_utf8_lossy(&user_address_output.stderr);
-        println!("Failed to get user Solana address: {}", error);
-        user_address = String::new();
+    fn print_next_steps(&self, steps: &[&str]) {
+        println!("\n📋 Next steps:");
+        if !self.is_current_dir() {
+            println!("$ cd {}", self.dir.display());
+        }
+        for step in steps {
+            println!("$ {}", step);
+        }
+        println!();
     }
+}
 
-    create_project_structure(
-        project_dir,
-        user_address,
-        program_address.clone(),
-        test_framework,
-    )?;
-    update_cargo_toml(project_dir, project_name, test_framework)?;
+fn init_project(
+    runner: &dyn CommandRunner,
+    target: &InitTarget,
+    test_framework: TestFramework,
+    set: TemplateSet,
+    vars: BTreeMap<String, String>,
+    conflicts: ConflictMode,
+    dry_run: Option<PlanFormat>,
+) -> Result<()> {
+    let mut generator = ProjectGenerator::new(&target.name)
+        .with_test_framework(test_framework)
+        .with_template(set)
+        .with_variables(vars)
+        .with_output_dir(&target.dir)
+        .with_conflict_mode(conflicts)
+        .with_git(target.needs_git());
+
+    // A program keypair already in the directory is kept, so the program id
+    // matches the one it may have been deployed with.
+    let keypair_path = target
+        .dir
+        .join("target")
+        .join("deploy")
+        .join(format!("{}-keypair.json", crate_name(&target.name)));
+    let existing_keypair = if keypair_path.exists() {
+        let keypair = Keypair::read(&keypair_path)?;
+        generator = generator.with_program_id(&keypair.address());
+        true
+    } else {
+        false
+    };
 
-    init_git_repo(project_dir, project_name)?;
+    if let Some(format) = dry_run {
+        // The payer is left out: looking up the wallet runs the Solana CLI,
+        // and the plan does not show file contents.
+        return print_plan(&generator.generate()?, format);
+    }
+
+    validate_project_name(&target.name)?;
+    print_init_banner(&target.name);
+
+    let project = generator.with_payer(&wallet_address(runner)).generate()?;
+    if let Some(program_id) = &project.program_id {
+        if existing_keypair {
+            println!(
+                "Using program address {} from {}",
+                program_id,
+                keypair_path.display()
+            );
+        } else {
+            println!("Generated program address: {}", program_id);
+        }
+    }
+    write_project(runner, &project)?;
 
-    println!("");
+    println!();
     println!(
         "✅ Pinocchio Project '{}' initialized successfully!",
-        project_name
+        target.name
     );
-    println!("\n📋 Next steps:");
-    println!("$ cd {}", project_name);
-    println!("$ chio build");
-    println!("$ chio test");
-    println!("$ chio deploy");
-    println!("");
+    target.print_next_steps(&["chio build", "chio test", "chio deploy"]);
 
     Ok(())
 }
 
-fn init_git_repo(project_dir: &Path, project_name: &str) -> Result<()> {
-    let git_init_output = Command::new("git")
-        .arg("init")
-        .current_dir(project_dir)
-        .output()
-        .with_context(|| "Failed to initialize git repository")?;
-
-    if !git_init_output.status.success() {
-        let error = String::from_utf8_lossy(&git_init_output.stderr);
-        println!("Warning: Failed to initialize git repository: {}", error);
-        return Ok(());
+fn print_plan(project: &GeneratedProject, format: PlanFormat) -> Result<()> {
+    let plan = project.plan();
+    match format {
+        PlanFormat::Tree => print!("{}", plan.to_tree()),
+        PlanFormat::Json => println!("{}", plan.to_json()?),
     }
+    Ok(())
+}
 
-    let git_add_output = Command::new("git")
-        .arg("add")
-        .arg(".")
-        .current_dir(project_dir)
-        .output()
-        .with_context(|| "Failed to add files to git")?;
-
-    if !git_add_output.status.success() {
-        let error = String::from_utf8_lossy(&git_add_output.stderr);
-        println!("Warning: Failed to add files to git: {}", error);
-        return Ok(());
+/// Writes a generated project, listing what happened to each file and
+/// reporting a failed git setup as a warning.
+fn write_project(runner: &dyn CommandRunner, project: &GeneratedProject) -> Result<()> {
+    let report = project.write_files()?;
+    for (path, action) in &report.files {
+        println!("  {:<11} {}", action, path.display());
+    }
+    if project.commands.is_empty() {
+        println!("Existing git repository found, skipping git init");
     }
 
-    let commit_message = format!("Initial commit: Setup Pinocchio project '{}'", project_name);
-    let git_commit_output = Command::new("git")
-        .arg("commit")
-        .arg("-m")
-        .arg(&commit_message)
-        .current_dir(project_dir)
-        .output()
-        .with_context(|| "Failed to make initial commit")?;
-
-    if !git_commit_output.status.success() {
-        let error = String::from_utf8_lossy(&git_commit_output.stderr);
-        println!("Warning: Failed to make initial commit: {}", error);
+    if let Some(error) = project.run_commands(runner)? {
+        println!("Warning: {}", error);
         // Check if it's because of missing git config
         if error.contains("user.email") || error.contains("user.name") {
             println!("Hint: Set your git config with:");
             println!("  git config --global user.email \"you@example.com\"");
             println!("  git config --global user.name \"Your Name\"");
         }
-        return Ok(());
     }
     Ok(())
 }
 
-fn create_project_structure(
-    project_dir: &Path,
-    user_address: String,
-    program_address: String,
-    test_framework: TestFramework,
-) -> Result<()> {
-    fs::write(project_dir.join("README.md"), templates::readme_md())?;
-    fs::write(project_dir.join(".gitignore"), templates::gitignore())?;
-
-    let src_dir = project_dir.join("src");
-    fs::create_dir_all(&src_dir)?;
-
-    match test_framework {
-        TestFramework::Mollusk => {
-            fs::write(
-                src_dir.join("lib.rs"),
-                templates::lib_rs(program_address.as_str()),
-            )?;
-
-            let test_dir = project_dir.join("tests");
-            fs::create_dir_all(&test_dir)?;
-
-            let project_name = project_dir
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("project");
-
-            fs::write(
-                test_dir.join("tests.rs"),
-                templates::unit_tests::unit_test_rs(&user_address, &program_address, project_name),
-            )?;
-        }
-        TestFramework::Litesvm => {
-            fs::write(
-                src_dir.join("lib.rs"),
-                templates::lib_rs(program_address.as_str()),
-            )?;
-
-            let test_dir = project_dir.join("tests");
-            fs::create_dir_all(&test_dir)?;
-
-            let project_name_str = project_dir
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("project");
-
-            fs::write(
-                test_dir.join("initialize.rs"),
-                templates::unit_tests::litesvm_initialize_rs(project_name_str),
-            )?;
-        }
+/// Loads the template set `chio init --template` points at, cloning it first
+/// when it is a git repository.
+fn load_template_set(runner: &dyn CommandRunner, source: &str) -> Result<TemplateSet> {
+    if !template_set::is_git_url(source) {
+        return TemplateSet::load(Path::new(source));
     }
 
-    fs::write(src_dir.join("entrypoint.rs"), templates::entrypoint_rs())?;
-
-    fs::write(src_dir.join("errors.rs"), templates::errors_rs())?;
-
-    let instructions_dir = src_dir.join("instructions");
-    fs::create_dir_all(&instructions_dir)?;
-
-    fs::write(
-        instructions_dir.join("mod.rs"),
-        templates::instructions::instructions_mod_rs(),
-    )?;
-    fs::write(
-        instructions_dir.join("initialize.rs"),
-        templates::instructions::initialize(),
-    )?;
-
-    let states_dir = src_dir.join("states");
-    fs::create_dir_all(&states_dir)?;
+    println!("Cloning template {}", source);
+    TemplateSet::load_git(runner, source)
+}
 
-    fs::write(
-        states_dir.join("mod.rs"),
-        templates::states::states_mod_rs(),
-    )?;
-    fs::write(states_dir.join("utils.rs"), templates::states::utils_rs())?;
+fn init_workspace(
+    runner: &dyn CommandRunner,
+    target: &InitTarget,
+    conflicts: ConflictMode,
+    dry_run: Option<PlanFormat>,
+) -> Result<()> {
+    let workspace = ProjectGenerator::workspace(&target.name)
+        .with_output_dir(&target.dir)
+        .with_conflict_mode(conflicts)
+        .with_git(target.needs_git())
+        .generate()?;
+    if let Some(format) = dry_run {
+        return print_plan(&workspace, format);
+    }
 
-    fs::write(states_dir.join("state.rs"), templates::states::state_rs())?;
+    print_init_banner(&target.name);
+    write_project(runner, &workspace)?;
 
-    // tests already handled per framework above
+    println!();
+    println!(
+        "✅ Pinocchio Workspace '{}' initialized successfully!",
+        target.name
+    );
+    target.print_next_steps(&["chio add program <name>", "chio build"]);
 
     Ok(())
 }
 
-fn update_cargo_toml(
-    project_dir: &Path,
-    project_name: &str,
+/// Adds a program to the workspace in the current directory.
+fn add_program(
+    runner: &dyn CommandRunner,
+    name: &str,
     test_framework: TestFramework,
 ) -> Result<()> {
-    let dev_deps = match test_framework {
-        TestFramework::Mollusk => {
-            r#"
-[dev-dependencies]
-solana-sdk = "3.0.0"
-mollusk-svm = "0.7.0"
-mollusk-svm-bencher = "0.7.0"
-"#
-        }
-        TestFramework::Litesvm => {
-            r#"
-[dev-dependencies]
-solana-sdk = "3.0.0"
-litesvm = "0.8.1"
-litesvm-token = "0.8.1"
-"#
-        }
+    let root = Path::new(".");
+    let mut config = match ChioConfig::load(root)? {
+        Some(config) if config.is_workspace() => config,
+        _ => anyhow::bail!(
+            "'chio add program' only works in a workspace. Create one with 'chio init --workspace <name>'."
+        ),
     };
+    validate_project_name(name)?;
 
-    let cargo_toml = format!(
-        r#"[package]
-name = "{}"
-version = "0.1.0"
-edition = "2021"
-
-[lib]
-crate-type = ["cdylib", "rlib"]
-
-[dependencies]
-pinocchio = "0.9.2"
-pinocchio-log = "0.5.1"
-pinocchio-pubkey = "0.3.0"
-pinocchio-system = "0.3.0"
-shank = "0.4.5"
-
-{dev_deps}
-
-[features]
-no-entrypoint = []
-std = []
-test-default = ["no-entrypoint", "std"]
-"#,
-        project_name,
-        dev_deps = dev_deps
-    );
+    let program_dir = Path::new("programs").join(name);
+    if program_dir.exists() {
+        anyhow::bail!("{} already exists", program_dir.display());
+    }
+    let manifest_path = root.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest = add::patch_workspace_members(&manifest, &format!("programs/{}", name))?;
+
+    let program = ProjectGenerator::new(name)
+        .with_test_framework(test_framework)
+        .with_payer(&wallet_address(runner))
+        .with_output_dir(root)
+        .with_feature("workspace", true)
+        .generate()?;
+    let program_address = program.program_id.clone().unwrap_or_default();
+    println!("Generated program address: {}", program_address);
+
+    program.files.write()?;
+    fs::write(&manifest_path, manifest)?;
+
+    config.add_member(ProgramConfig::new(name, test_framework), &program_address)?;
+    config.save(root)?;
+
+    println!("  created  {}", program_dir.display());
+    println!("  updated  Cargo.toml");
+    println!("  updated  {}", config::CONFIG_FILE);
+    println!("✅ Program '{}' added", name);
+    Ok(())
+}
 
-    fs::write(project_dir.join("Cargo.toml"), cargo_toml)?;
+/// Prints the banner shown when a project is initialized.
+fn print_init_banner(project_name: &str) {
+    println!(
+        r#"
+      *     *
+  ___| |__ (_) ___
+ / __| '_ \| |/ _ \
+| (__| | | | | (_) |
+ \___|_| |_|_|\___/
 
-    Ok(())
+ "#
+    );
+    println!("🧑🏻‍🍳 Initializing your pinocchio project: {}", project_name);
+    println!();
+}
+
+/// Returns the address of the active Solana wallet, used as the payer in the
+/// generated tests, or a throwaway address when there is no wallet.
+fn wallet_address(runner: &dyn CommandRunner) -> String {
+    keypair::wallet_address(runner).unwrap_or_else(|| {
+        println!("No Solana wallet found, using a throwaway payer address in tests");
+        Keypair::generate()
+            .map(|keypair| keypair.address())
+            .unwrap_or_default()
+    })
 }
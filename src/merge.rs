@@ -0,0 +1,96 @@
+//! Merging generated files into the ones already in a directory, for
+//! `chio init --merge`.
+//!
+//! Only files with a structure chio understands are merged: TOML files such
+//! as `Cargo.toml` and `Chio.toml`, and line lists such as `.gitignore`. What
+//! is already in the existing file always wins; the generated file only adds
+//! what is missing.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use toml_edit::{DocumentMut, Item};
+
+/// Whether [`merge`] knows how to merge the file at `path`.
+pub fn can_merge(path: &Path) -> bool {
+    let file_name = path.file_name().and_then(|name| name.to_str());
+    matches!(file_name, Some(".gitignore"))
+        || path.extension().and_then(|ext| ext.to_str()) == Some("toml")
+}
+
+/// Merges `generated` into `existing`, or returns `None` when the file at
+/// `path` is not one that can be merged.
+pub fn merge(path: &Path, existing: &str, generated: &str) -> Option<Result<String>> {
+    if !can_merge(path) {
+        return None;
+    }
+    if path.extension().and_then(|ext| ext.to_str()) == Some("toml") {
+        Some(
+            merge_toml(existing, generated)
+                .with_context(|| format!("Failed to merge {}", path.display())),
+        )
+    } else {
+        Some(Ok(merge_lines(existing, generated)))
+    }
+}
+
+/// Adds the tables, keys and array values of `generated` missing from
+/// `existing`, keeping its values, comments and formatting.
+pub fn merge_toml(existing: &str, generated: &str) -> Result<String> {
+    let mut document: DocumentMut = existing.parse()?;
+    let generated: DocumentMut = generated.parse()?;
+    merge_item(document.as_item_mut(), generated.as_item());
+    Ok(document.to_string())
+}
+
+fn merge_item(existing: &mut Item, generated: &Item) {
+    if let (Some(existing), Some(generated)) =
+        (existing.as_table_like_mut(), generated.as_table_like())
+    {
+        for (key, item) in generated.iter() {
+            match existing.get_mut(key) {
+                None => {
+                    existing.insert(key, item.clone());
+                }
+                Some(current) => merge_item(current, item),
+            }
+        }
+        return;
+    }
+
+    // Arrays such as `members` get the generated values they lack.
+    if let (Some(existing), Some(generated)) = (
+        existing.as_array_mut(),
+        generated.as_value().and_then(|value| value.as_array()),
+    ) {
+        for value in generated.iter() {
+            let present = existing
+                .iter()
+                .any(|current| current.to_string().trim() == value.to_string().trim());
+            if !present {
+                existing.push_formatted(value.clone().decorated("", ""));
+            }
+        }
+    }
+}
+
+/// Appends the lines of `generated` missing from `existing`.
+pub fn merge_lines(existing: &str, generated: &str) -> String {
+    let present: Vec<&str> = existing.lines().map(str::trim).collect();
+    let missing: Vec<&str> = generated
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !present.contains(&line.trim()))
+        .collect();
+    if missing.is_empty() {
+        return existing.to_string();
+    }
+
+    let mut merged = existing.to_string();
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    for line in missing {
+        merged.push_str(line);
+        merged.push('\n');
+    }
+    merged
+}
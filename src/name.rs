@@ -0,0 +1,187 @@
+//! Project names and the Rust crate identifiers derived from them.
+//!
+//! A project name is used as-is for the directory and the Cargo package,
+//! while generated code refers to the crate by its identifier, with hyphens
+//! mapped to underscores the way Cargo does: `my-program` is imported as
+//! `my_program` and builds `target/deploy/my_program.so`.
+
+use std::fmt;
+
+/// Words that cannot be used as a crate, module or function identifier: the
+/// Rust keywords, including the reserved ones, and `_`.
+pub const KEYWORDS: &[&str] = &[
+    "_", "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Crates a generated program already depends on, or that Rust provides,
+/// which a package of the same name would shadow.
+const RESERVED_CRATES: &[&str] = &[
+    "alloc",
+    "core",
+    "proc_macro",
+    "std",
+    "test",
+    "pinocchio",
+    "pinocchio_log",
+    "pinocchio_pubkey",
+    "pinocchio_system",
+    "shank",
+    "solana_sdk",
+    "mollusk_svm",
+    "mollusk_svm_bencher",
+    "litesvm",
+    "litesvm_token",
+    "test_helpers",
+];
+
+/// Why a project name was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvalidProjectName {
+    Empty,
+    /// The name has a character other than an ASCII letter, digit, `_` or `-`.
+    InvalidCharacter {
+        name: String,
+        character: char,
+    },
+    /// The name starts with a digit or a hyphen.
+    InvalidStart {
+        name: String,
+    },
+    /// The crate identifier is a Rust keyword.
+    Keyword {
+        name: String,
+        ident: String,
+    },
+    /// The crate identifier is the name of a crate the program uses.
+    ReservedCrate {
+        name: String,
+        ident: String,
+    },
+}
+
+impl fmt::Display for InvalidProjectName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Project name cannot be empty"),
+            Self::InvalidCharacter { name, character } => write!(
+                f,
+                "Invalid project name '{}': '{}' is not allowed. \
+                Use ASCII letters, digits, underscores (_) and hyphens (-).",
+                name, character
+            ),
+            Self::InvalidStart { name } => write!(
+                f,
+                "Invalid project name '{}': it must start with a letter or an underscore, \
+                as the crate is imported by name in Rust code.",
+                name
+            ),
+            Self::Keyword { name, ident } => write!(
+                f,
+                "Invalid project name '{}': '{}' is a Rust keyword and cannot name a crate.",
+                name, ident
+            ),
+            Self::ReservedCrate { name, ident } => write!(
+                f,
+                "Invalid project name '{}': '{}' would shadow the crate of the same name \
+                that generated programs use.",
+                name, ident
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidProjectName {}
+
+/// A validated project name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectName {
+    package: String,
+    crate_name: String,
+}
+
+impl ProjectName {
+    pub fn parse(name: &str) -> Result<Self, InvalidProjectName> {
+        let first = name.chars().next().ok_or(InvalidProjectName::Empty)?;
+        if let Some(character) = name
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '-'))
+        {
+            return Err(InvalidProjectName::InvalidCharacter {
+                name: name.to_string(),
+                character,
+            });
+        }
+        if !(first.is_ascii_alphabetic() || first == '_') {
+            return Err(InvalidProjectName::InvalidStart {
+                name: name.to_string(),
+            });
+        }
+
+        let crate_name = crate_name(name);
+        if KEYWORDS.contains(&crate_name.as_str()) {
+            return Err(InvalidProjectName::Keyword {
+                name: name.to_string(),
+                ident: crate_name,
+            });
+        }
+        if RESERVED_CRATES.contains(&crate_name.as_str()) {
+            return Err(InvalidProjectName::ReservedCrate {
+                name: name.to_string(),
+                ident: crate_name,
+            });
+        }
+
+        Ok(Self {
+            package: name.to_string(),
+            crate_name,
+        })
+    }
+
+    /// The name as given, used for the directory and the Cargo package.
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// The identifier Rust code imports the crate by.
+    pub fn crate_name(&self) -> &str {
+        &self.crate_name
+    }
+}
+
+impl fmt::Display for ProjectName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.package)
+    }
+}
+
+/// Maps a package name to its crate identifier.
+pub fn crate_name(package: &str) -> String {
+    package.replace('-', "_")
+}
+
+/// Validates that a name can be used for a project: its directory, its Cargo
+/// package and, with hyphens mapped to underscores, its crate identifier.
+///
+/// Valid names:
+/// - `my_project`
+/// - `my-project` (imported as `my_project`)
+/// - `MyProject`
+/// - `_project`
+///
+/// Invalid names:
+/// - `my project`, `my.project`, `café` (only ASCII letters, digits, `_` and `-`)
+/// - `123abc`, `-project` (must start with a letter or `_`)
+/// - `fn`, `self` (Rust keywords)
+/// - `` (empty string)
+pub fn is_valid_project_name(name: &str) -> bool {
+    ProjectName::parse(name).is_ok()
+}
+
+/// Like [`is_valid_project_name`], explaining why a name is rejected.
+pub fn validate_project_name(name: &str) -> Result<ProjectName, InvalidProjectName> {
+    ProjectName::parse(name)
+}
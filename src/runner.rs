@@ -0,0 +1,258 @@
+//! External processes, run behind the [`CommandRunner`] trait.
+//!
+//! Everything chio runs — `git`, `solana`, `cargo build-sbf` — goes through a
+//! runner, so the flows around those commands can be tested with a
+//! [`RecordingRunner`] instead of the real toolchain.
+
+use anyhow::Result;
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// An external command: the program, its arguments and where it runs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessCommand {
+    pub program: String,
+    pub args: Vec<OsString>,
+    /// Working directory, the current one when `None`.
+    pub dir: Option<PathBuf>,
+}
+
+impl ProcessCommand {
+    pub fn new<I, S>(program: &str, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        Self {
+            program: program.to_string(),
+            args: args.into_iter().map(Into::into).collect(),
+            dir: None,
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn current_dir(mut self, dir: &Path) -> Self {
+        self.dir = Some(dir.to_path_buf());
+        self
+    }
+}
+
+impl fmt::Display for ProcessCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            let arg = arg.to_string_lossy();
+            if arg.contains(char::is_whitespace) {
+                write!(f, " {:?}", arg)?;
+            } else {
+                write!(f, " {}", arg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a command exited, with its output when it was captured.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Exit code, `None` when the command was killed by a signal.
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    /// A successful run printing `stdout`.
+    pub fn success(stdout: &str) -> Self {
+        Self {
+            code: Some(0),
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        }
+    }
+
+    /// A run exiting with `code` after printing `stderr`.
+    pub fn failure(code: i32, stderr: &str) -> Self {
+        Self {
+            code: Some(code),
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+impl From<Output> for CommandOutput {
+    fn from(output: Output) -> Self {
+        Self {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+}
+
+pub trait CommandRunner {
+    /// Runs `command` with its output captured.
+    fn output(&self, command: &ProcessCommand) -> Result<CommandOutput>;
+
+    /// Runs `command` attached to the terminal, for builds, tests and deploys
+    /// whose progress the user follows. The returned output has no streams.
+    fn status(&self, command: &ProcessCommand) -> Result<CommandOutput>;
+}
+
+/// Runs commands as real processes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemRunner;
+
+impl SystemRunner {
+    fn command(command: &ProcessCommand) -> Command {
+        let mut process = Command::new(&command.program);
+        process.args(&command.args);
+        if let Some(dir) = &command.dir {
+            process.current_dir(dir);
+        }
+        process
+    }
+}
+
+impl CommandRunner for SystemRunner {
+    fn output(&self, command: &ProcessCommand) -> Result<CommandOutput> {
+        let output = Self::command(command)
+            .output()
+            .map_err(|e| spawn_error(&command.program, e))?;
+        Ok(output.into())
+    }
+
+    fn status(&self, command: &ProcessCommand) -> Result<CommandOutput> {
+        let status = Self::command(command)
+            .status()
+            .map_err(|e| spawn_error(&command.program, e))?;
+        Ok(CommandOutput {
+            code: status.code(),
+            ..CommandOutput::default()
+        })
+    }
+}
+
+/// The error for a command that could not be started, pointing at the
+/// missing tool when it is not installed.
+pub fn spawn_error(program: &str, error: std::io::Error) -> anyhow::Error {
+    if error.kind() == ErrorKind::NotFound {
+        anyhow::anyhow!(
+            "'{}' was not found. Install it and make sure it is on your PATH.",
+            program
+        )
+    } else {
+        anyhow::Error::new(error).context(format!("Failed to run '{}'", program))
+    }
+}
+
+/// Records the commands it is asked to run and answers them with canned
+/// outputs instead of starting processes.
+///
+/// Responses are matched against the start of the command line, so
+/// `"git commit"` answers only commits while `"git"` answers every git
+/// command; the longest match wins, and the latest of equally long ones.
+/// Commands without a response succeed with no output.
+#[derive(Debug, Default)]
+pub struct RecordingRunner {
+    responses: Vec<(Vec<String>, Option<CommandOutput>)>,
+    commands: RefCell<Vec<ProcessCommand>>,
+}
+
+impl RecordingRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers commands starting with `prefix` with `output`.
+    pub fn with_output(mut self, prefix: &str, output: CommandOutput) -> Self {
+        self.responses.push((words(prefix), Some(output)));
+        self
+    }
+
+    /// Fails commands starting with `prefix` as if the program were not
+    /// installed.
+    pub fn with_missing(mut self, prefix: &str) -> Self {
+        self.responses.push((words(prefix), None));
+        self
+    }
+
+    /// The commands run so far, in order.
+    pub fn commands(&self) -> Vec<ProcessCommand> {
+        self.commands.borrow().clone()
+    }
+
+    /// The commands run so far as command lines.
+    pub fn command_lines(&self) -> Vec<String> {
+        self.commands().iter().map(ToString::to_string).collect()
+    }
+
+    fn respond(&self, command: &ProcessCommand) -> Result<CommandOutput> {
+        self.commands.borrow_mut().push(command.clone());
+
+        let line: Vec<&OsStr> = std::iter::once(OsStr::new(&command.program))
+            .chain(command.args.iter().map(OsString::as_os_str))
+            .collect();
+        let response = self
+            .responses
+            .iter()
+            .filter(|(prefix, _)| {
+                prefix.len() <= line.len()
+                    && prefix.iter().zip(&line).all(|(a, b)| OsStr::new(a) == *b)
+            })
+            .max_by_key(|(prefix, _)| prefix.len());
+
+        match response {
+            None => Ok(CommandOutput::success("")),
+            Some((_, Some(output))) => Ok(output.clone()),
+            Some((_, None)) => Err(spawn_error(
+                &command.program,
+                std::io::Error::from(ErrorKind::NotFound),
+            )),
+        }
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn output(&self, command: &ProcessCommand) -> Result<CommandOutput> {
+        self.respond(command)
+    }
+
+    fn status(&self, command: &ProcessCommand) -> Result<CommandOutput> {
+        self.respond(command).map(|output| CommandOutput {
+            code: output.code,
+            ..CommandOutput::default()
+        })
+    }
+}
+
+fn words(prefix: &str) -> Vec<String> {
+    prefix.split_whitespace().map(str::to_string).collect()
+}
+
+/// Runs `command` attached to the terminal and fails unless it succeeds,
+/// describing the step as `action` ("Build", "Deploy", ...).
+pub fn run_checked(
+    runner: &dyn CommandRunner,
+    command: &ProcessCommand,
+    action: &str,
+) -> Result<()> {
+    let output = runner.status(command)?;
+    if !output.is_success() {
+        anyhow::bail!("{} failed with exit code: {:?}", action, output.code);
+    }
+    Ok(())
+}
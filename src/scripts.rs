@@ -0,0 +1,71 @@
+//! The build, test and bench scripts `chio build`, `chio test` and
+//! `chio bench` run.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::{self, ChioConfig};
+use crate::runner::{self, CommandRunner, ProcessCommand};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script {
+    Build,
+    Test,
+    Bench,
+}
+
+impl Script {
+    fn action(self) -> &'static str {
+        match self {
+            Script::Build => "Build",
+            Script::Test => "Test",
+            Script::Bench => "Bench",
+        }
+    }
+}
+
+/// The command `script` runs in `root`: the one from `Chio.toml`, or the
+/// default when there is none, limited to the selected workspace `program`.
+pub fn script_process(
+    root: &Path,
+    script: Script,
+    program: Option<&str>,
+) -> Result<ProcessCommand> {
+    let config = ChioConfig::load(root)?;
+    let selection = match (program, &config) {
+        (None, _) => Vec::new(),
+        (Some(name), Some(config)) if config.is_workspace() => {
+            let member = config.member(name)?;
+            let manifest = Path::new("programs").join(&member.name).join("Cargo.toml");
+            vec![
+                "--manifest-path".to_string(),
+                manifest.display().to_string(),
+            ]
+        }
+        (Some(_), _) => anyhow::bail!(
+            "--program selects a workspace program, but this project is not a workspace"
+        ),
+    };
+
+    let scripts = config.map(|config| config.scripts).unwrap_or_default();
+    let script = match script {
+        Script::Build => scripts.build,
+        Script::Test => scripts.test,
+        Script::Bench => scripts.bench,
+    };
+    let (program, mut args) = config::script_command(&script)?;
+    args.extend(selection);
+
+    Ok(ProcessCommand::new(&program, args).current_dir(root))
+}
+
+/// Runs `script` in `root` attached to the terminal, failing when it does.
+pub fn run_script(
+    runner: &dyn CommandRunner,
+    root: &Path,
+    script: Script,
+    program: Option<&str>,
+) -> Result<()> {
+    let command = script_process(root, script, program)?;
+    runner::run_checked(runner, &command, script.action())
+}
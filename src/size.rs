@@ -0,0 +1,353 @@
+//! The program size report `chio build` prints.
+//!
+//! The size of the `.so` decides what a deploy costs: the program data
+//! account holds the whole ELF and has to be rent exempt, and the buffer it
+//! is written through is just as large. After a build, every program in the
+//! deploy directory is read back and broken down by section and symbol.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::add::Project;
+use crate::build::BuildOptions;
+use crate::config::ChioConfig;
+use crate::name::crate_name;
+
+/// How many of the largest symbols the report lists.
+pub const TOP_SYMBOLS: usize = 10;
+
+/// Bytes of upgradeable loader state in front of the ELF in the program data
+/// account.
+pub const PROGRAM_DATA_METADATA: u64 = 45;
+/// Size of the program account, which points at the program data account.
+pub const PROGRAM_ACCOUNT_SIZE: u64 = 36;
+
+const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+const LAMPORTS_PER_BYTE_YEAR: u64 = 3480;
+const EXEMPTION_THRESHOLD_YEARS: u64 = 2;
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNSYM: u32 = 11;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+/// A named part of an ELF and the bytes it takes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeEntry {
+    pub name: String,
+    pub size: u64,
+}
+
+/// The sections and the function and data symbols of an ELF, largest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ElfSizes {
+    pub sections: Vec<SizeEntry>,
+    pub symbols: Vec<SizeEntry>,
+    /// Whether the ELF has no `.symtab`, leaving only exported symbols.
+    pub stripped: bool,
+}
+
+/// The size report of one built program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramSize {
+    pub program: String,
+    /// The `.so`, relative to the project root.
+    pub path: PathBuf,
+    pub size: u64,
+    pub sections: Vec<SizeEntry>,
+    /// The largest symbols.
+    pub symbols: Vec<SizeEntry>,
+    /// The unstripped build the symbols were read from, when the `.so`
+    /// itself is stripped.
+    pub symbols_from: Option<PathBuf>,
+}
+
+impl ProgramSize {
+    /// Lamports that make the program data and program accounts rent
+    /// exempt, i.e. what deploying the program locks up.
+    pub fn deploy_deposit(&self) -> u64 {
+        deploy_deposit(self.size)
+    }
+}
+
+/// The minimum balance of a rent exempt account holding `data_len` bytes.
+pub fn rent_exempt_minimum(data_len: u64) -> u64 {
+    (ACCOUNT_STORAGE_OVERHEAD + data_len) * LAMPORTS_PER_BYTE_YEAR * EXEMPTION_THRESHOLD_YEARS
+}
+
+/// Lamports deploying a program of `program_len` bytes locks up in its
+/// program data and program accounts.
+pub fn deploy_deposit(program_len: u64) -> u64 {
+    rent_exempt_minimum(PROGRAM_DATA_METADATA + program_len)
+        + rent_exempt_minimum(PROGRAM_ACCOUNT_SIZE)
+}
+
+/// Formats lamports as SOL, e.g. `1.234560000 SOL`.
+pub fn format_sol(lamports: u64) -> String {
+    format!(
+        "{}.{:09} SOL",
+        lamports / LAMPORTS_PER_SOL,
+        lamports % LAMPORTS_PER_SOL
+    )
+}
+
+/// Formats a byte count with its KiB, e.g. `24576 bytes (24.0 KiB)`.
+pub fn format_bytes(bytes: u64) -> String {
+    format!("{} bytes ({:.1} KiB)", bytes, bytes as f64 / 1024.0)
+}
+
+/// Reads the section and symbol sizes of a 64-bit little-endian ELF, the
+/// format `cargo build-sbf` produces. Symbols come from `.symtab`, or from
+/// `.dynsym` when the binary is stripped.
+pub fn parse_elf(bytes: &[u8]) -> Result<ElfSizes> {
+    if bytes.get(..4) != Some(b"\x7fELF".as_slice()) {
+        anyhow::bail!("Not an ELF file");
+    }
+    if bytes.get(4) != Some(&2) || bytes.get(5) != Some(&1) {
+        anyhow::bail!("Not a 64-bit little-endian ELF file");
+    }
+
+    let section_offset = read_u64(bytes, 0x28)?;
+    let entry_size = read_u16(bytes, 0x3a)? as u64;
+    let count = read_u16(bytes, 0x3c)? as u64;
+    let names_index = read_u16(bytes, 0x3e)? as u64;
+
+    let headers = (0..count)
+        .map(|index| SectionHeader::read(bytes, section_offset.saturating_add(index * entry_size)))
+        .collect::<Result<Vec<_>>>()?;
+    let names = headers
+        .get(names_index as usize)
+        .context("ELF section name table is missing")?
+        .data(bytes)?;
+
+    let mut sections: Vec<SizeEntry> = headers
+        .iter()
+        .filter(|header| header.size > 0)
+        .map(|header| {
+            Ok(SizeEntry {
+                name: read_str(names, header.name as u64)?,
+                size: header.size,
+            })
+        })
+        .collect::<Result<_>>()?;
+    sections.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+    let symtab = headers.iter().find(|header| header.kind == SHT_SYMTAB);
+    let table = symtab.or_else(|| headers.iter().find(|header| header.kind == SHT_DYNSYM));
+    let mut symbols = match table {
+        Some(table) => read_symbols(bytes, table, &headers)?,
+        None => Vec::new(),
+    };
+    symbols.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    symbols.dedup();
+
+    Ok(ElfSizes {
+        sections,
+        symbols,
+        stripped: symtab.is_none(),
+    })
+}
+
+struct SectionHeader {
+    name: u32,
+    kind: u32,
+    offset: u64,
+    size: u64,
+    link: u32,
+}
+
+impl SectionHeader {
+    fn read(bytes: &[u8], at: u64) -> Result<Self> {
+        Ok(Self {
+            name: read_u32(bytes, at)?,
+            kind: read_u32(bytes, at.saturating_add(4))?,
+            offset: read_u64(bytes, at.saturating_add(24))?,
+            size: read_u64(bytes, at.saturating_add(32))?,
+            link: read_u32(bytes, at.saturating_add(40))?,
+        })
+    }
+
+    fn data<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8]> {
+        slice(bytes, self.offset, self.size)
+    }
+}
+
+fn read_symbols(
+    bytes: &[u8],
+    table: &SectionHeader,
+    headers: &[SectionHeader],
+) -> Result<Vec<SizeEntry>> {
+    let names = headers
+        .get(table.link as usize)
+        .context("ELF symbol name table is missing")?
+        .data(bytes)?;
+    let data = table.data(bytes)?;
+
+    let mut symbols = Vec::new();
+    for entry in data.chunks_exact(24) {
+        let kind = entry[4] & 0xf;
+        let size = read_u64(entry, 16)?;
+        if size == 0 || !(kind == STT_FUNC || kind == STT_OBJECT) {
+            continue;
+        }
+        let name = read_str(names, read_u32(entry, 0)? as u64)?;
+        if !name.is_empty() {
+            symbols.push(SizeEntry {
+                name: demangle(&name),
+                size,
+            });
+        }
+    }
+    Ok(symbols)
+}
+
+fn slice(bytes: &[u8], offset: u64, len: u64) -> Result<&[u8]> {
+    let start = usize::try_from(offset)?;
+    let end = start.checked_add(usize::try_from(len)?);
+    end.and_then(|end| bytes.get(start..end))
+        .context("ELF file is truncated")
+}
+
+fn read_u16(bytes: &[u8], at: u64) -> Result<u16> {
+    Ok(u16::from_le_bytes(slice(bytes, at, 2)?.try_into()?))
+}
+
+fn read_u32(bytes: &[u8], at: u64) -> Result<u32> {
+    Ok(u32::from_le_bytes(slice(bytes, at, 4)?.try_into()?))
+}
+
+fn read_u64(bytes: &[u8], at: u64) -> Result<u64> {
+    Ok(u64::from_le_bytes(slice(bytes, at, 8)?.try_into()?))
+}
+
+fn read_str(table: &[u8], at: u64) -> Result<String> {
+    let rest = table
+        .get(usize::try_from(at)?..)
+        .context("ELF string table is truncated")?;
+    let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+/// Demangles a Rust symbol without its hash, `_ZN4core3fmt5write17h0123456789abcdefE`
+/// to `core::fmt::write`. Other names are returned as they are.
+pub fn demangle(symbol: &str) -> String {
+    format!("{:#}", rustc_demangle::demangle(symbol))
+}
+
+/// The unstripped builds a `cargo build-sbf` run with `options` leaves for
+/// the program `.so` named `file_name`: the `.debug` file next to it under
+/// `--debug`, then the release builds in the target directory, newest
+/// toolchain first.
+pub fn unstripped_paths(options: &BuildOptions, file_name: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if options.debug {
+        let debug = Path::new(file_name).with_extension("debug");
+        paths.push(options.deploy_dir().join(debug));
+    }
+    let target_dir = options.target_dir();
+    paths.extend(
+        ["sbpf-solana-solana", "sbf-solana-solana"]
+            .iter()
+            .map(|triple| target_dir.join(triple).join("release").join(file_name)),
+    );
+    paths
+}
+
+/// Reads the size report of the program `.so` at `path`, relative to `root`.
+/// Symbols of a stripped `.so` are read from the unstripped build `options`
+/// left, when there is one.
+pub fn program_size(
+    root: &Path,
+    program: &str,
+    path: &Path,
+    options: &BuildOptions,
+) -> Result<ProgramSize> {
+    let bytes =
+        fs::read(root.join(path)).with_context(|| format!("Failed to read {}", path.display()))?;
+    let elf = parse_elf(&bytes).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let mut symbols = elf.symbols;
+    let mut symbols_from = None;
+    if elf.stripped {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let unstripped = unstripped_paths(options, &file_name)
+            .into_iter()
+            .find(|candidate| root.join(candidate).exists());
+        if let Some(unstripped) = unstripped {
+            let bytes = fs::read(root.join(&unstripped))
+                .with_context(|| format!("Failed to read {}", unstripped.display()))?;
+            symbols = parse_elf(&bytes)
+                .with_context(|| format!("Failed to parse {}", unstripped.display()))?
+                .symbols;
+            symbols_from = Some(unstripped);
+        }
+    }
+
+    Ok(ProgramSize {
+        program: program.to_string(),
+        path: path.to_path_buf(),
+        size: bytes.len() as u64,
+        sections: elf.sections,
+        symbols: symbols.into_iter().take(TOP_SYMBOLS).collect(),
+        symbols_from,
+    })
+}
+
+/// Reads the programs a build in `root` with `options` wrote, limited to the
+/// selected workspace `program`.
+pub fn program_sizes(
+    root: &Path,
+    program: Option<&str>,
+    options: &BuildOptions,
+) -> Result<Vec<ProgramSize>> {
+    let names: Vec<String> = match ChioConfig::load(root)? {
+        Some(config) if config.is_workspace() => match program {
+            Some(name) => vec![config.member(name)?.name.clone()],
+            None => config
+                .program_configs()
+                .iter()
+                .map(|member| member.name.clone())
+                .collect(),
+        },
+        Some(config) if config.program.is_some() => config
+            .program
+            .iter()
+            .map(|program| program.name.clone())
+            .collect(),
+        _ => vec![Project::open(root)?.crate_name],
+    };
+
+    let deploy_dir = options.deploy_dir();
+    names
+        .iter()
+        .map(|name| {
+            let path = deploy_dir.join(format!("{}.so", crate_name(name)));
+            if !root.join(&path).exists() {
+                anyhow::bail!(
+                    "The build finished but {} was not found. Check sbf_out_dir under [build] in Chio.toml.",
+                    path.display()
+                );
+            }
+            program_size(root, name, &path, options)
+        })
+        .collect()
+}
+
+/// Fails when a program is larger than `max_size` bytes.
+pub fn check_max_size(programs: &[ProgramSize], max_size: u64) -> Result<()> {
+    let over: Vec<String> = programs
+        .iter()
+        .filter(|program| program.size > max_size)
+        .map(|program| format!("{} is {} bytes", program.path.display(), program.size))
+        .collect();
+    if !over.is_empty() {
+        anyhow::bail!(
+            "Program size budget of {} bytes exceeded: {}",
+            max_size,
+            over.join(", ")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,472 @@
+//! Renders the templates projects are scaffolded from.
+//!
+//! Templates are plain text with three kinds of tags:
+//!
+//! - `{{ name }}` inserts a value of the [`TemplateContext`];
+//! - `{% if cond %}...{% else %}...{% endif %}` keeps one branch, where
+//!   `cond` is `name`, `not name`, `name == "text"` or `name != "text"`;
+//! - `{% for item in list %}...{% endfor %}` repeats its body for every item
+//!   of a list, whose fields are read as `{{ item.field }}`;
+//! - `{% raw %}...{% endraw %}` keeps its body as it is, so a template can
+//!   hold a literal `{{` or `{%`, as in `format!("{{}}")`.
+//!
+//! A template is parsed up front and rendered in a single pass, so text coming
+//! from the context is never scanned for tags. A block tag alone on its line
+//! takes the whole line with it, so conditionals do not leave blank lines.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+use crate::TestFramework;
+
+/// A value a template can refer to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Bool(bool),
+    /// Items of a `{% for %}` loop, each a set of named fields.
+    List(Vec<BTreeMap<String, Value>>),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Str(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::List(items) => !items.is_empty(),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<Vec<BTreeMap<String, Value>>> for Value {
+    fn from(items: Vec<BTreeMap<String, Value>>) -> Self {
+        Value::List(items)
+    }
+}
+
+/// Values a template is rendered with.
+///
+/// The values every project has are typed fields; anything specific to one
+/// template goes in `vars` through [`TemplateContext::set`]. Feature flags are
+/// read as `features.<name>` and are off unless enabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TemplateContext {
+    pub project_name: Option<String>,
+    /// Rust identifier of the crate: the project name with `-` mapped to `_`.
+    pub crate_name: Option<String>,
+    pub program_id: Option<String>,
+    /// Fee payer address used by the generated tests.
+    pub payer: Option<String>,
+    pub test_framework: Option<TestFramework>,
+    pub features: BTreeMap<String, bool>,
+    pub vars: BTreeMap<String, Value>,
+}
+
+impl TemplateContext {
+    pub fn new(project_name: &str) -> Self {
+        Self {
+            project_name: Some(project_name.to_string()),
+            crate_name: Some(project_name.replace('-', "_")),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_program_id(mut self, program_id: &str) -> Self {
+        self.program_id = Some(program_id.to_string());
+        self
+    }
+
+    pub fn with_payer(mut self, payer: &str) -> Self {
+        self.payer = Some(payer.to_string());
+        self
+    }
+
+    pub fn with_test_framework(mut self, test_framework: TestFramework) -> Self {
+        self.test_framework = Some(test_framework);
+        self
+    }
+
+    pub fn with_feature(mut self, name: &str, enabled: bool) -> Self {
+        self.features.insert(name.to_string(), enabled);
+        self
+    }
+
+    pub fn set(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.vars.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Looks up a name outside of any loop.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        if let Some(flag) = name.strip_prefix("features.") {
+            return Some(Value::Bool(
+                self.features.get(flag).copied().unwrap_or(false),
+            ));
+        }
+
+        let typed = match name {
+            "project_name" => self.project_name.clone(),
+            "crate_name" => self.crate_name.clone(),
+            "program_id" => self.program_id.clone(),
+            "payer" => self.payer.clone(),
+            "test_framework" => self.test_framework.map(|framework| {
+                match framework {
+                    TestFramework::Mollusk => "mollusk",
+                    TestFramework::Litesvm => "litesvm",
+                }
+                .to_string()
+            }),
+            _ => return self.vars.get(name).cloned(),
+        };
+        typed.map(Value::Str)
+    }
+}
+
+/// Renders `template` with the values of `context`.
+pub fn render(template: &str, context: &TemplateContext) -> Result<String> {
+    let tokens = tokenize(template)?;
+    let mut tokens = tokens.into_iter();
+    let (nodes, end) = parse(&mut tokens)?;
+    if let Some(end) = end {
+        anyhow::bail!("Unexpected {{% {} %}} on line {}", end.tag, end.line);
+    }
+
+    let mut out = String::with_capacity(template.len());
+    render_nodes(&nodes, context, &mut Vec::new(), &mut out)?;
+    Ok(out)
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Var { expr: &'a str, line: usize },
+    Tag { tag: &'a str, line: usize },
+}
+
+fn tokenize(template: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = next_tag(&template[pos..]) {
+        let start = pos + offset;
+        let is_block = template[start..].starts_with("{%");
+        let line = template[..start].matches('\n').count() + 1;
+        let (inner, mut end) = read_tag(template, start).ok_or_else(|| {
+            anyhow::anyhow!("Unclosed {} on line {}", &template[start..start + 2], line)
+        })?;
+
+        let mut text_end = start;
+        if is_block {
+            // A block tag alone on its line removes the line.
+            if let Some((line_start, line_end)) = own_line(template, start, end) {
+                text_end = line_start.max(pos);
+                end = line_end;
+            }
+        }
+
+        if text_end > pos {
+            tokens.push(Token::Text(&template[pos..text_end]));
+        }
+        if is_block && inner == "raw" {
+            let (raw_end, after) = find_endraw(template, end)
+                .ok_or_else(|| anyhow::anyhow!("Unclosed {{% raw %}} on line {}", line))?;
+            if raw_end > end {
+                tokens.push(Token::Text(&template[end..raw_end]));
+            }
+            pos = after;
+            continue;
+        }
+        tokens.push(if is_block {
+            Token::Tag { tag: inner, line }
+        } else {
+            Token::Var { expr: inner, line }
+        });
+        pos = end;
+    }
+
+    if pos < template.len() {
+        tokens.push(Token::Text(&template[pos..]));
+    }
+    Ok(tokens)
+}
+
+/// The trimmed contents of the tag opening at `start` and the offset after
+/// it, or `None` when it is never closed.
+fn read_tag(template: &str, start: usize) -> Option<(&str, usize)> {
+    let close = if template[start..].starts_with("{%") {
+        "%}"
+    } else {
+        "}}"
+    };
+    let len = template[start + 2..].find(close)?;
+    Some((
+        template[start + 2..start + 2 + len].trim(),
+        start + 2 + len + 2,
+    ))
+}
+
+/// The line around the tag at `start..end` when nothing else is on it.
+fn own_line(template: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+    let line_start = template[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = template[end..]
+        .find('\n')
+        .map_or(template.len(), |i| end + i + 1);
+    (template[line_start..start].trim().is_empty() && template[end..line_end].trim().is_empty())
+        .then_some((line_start, line_end))
+}
+
+/// Where the raw text starting at `from` ends and where the text after its
+/// `{% endraw %}` starts.
+fn find_endraw(template: &str, from: usize) -> Option<(usize, usize)> {
+    let mut pos = from;
+    while let Some(offset) = template[pos..].find("{%") {
+        let start = pos + offset;
+        let (inner, end) = read_tag(template, start)?;
+        if inner == "endraw" {
+            return Some(match own_line(template, start, end) {
+                Some((line_start, line_end)) => (line_start.max(from), line_end),
+                None => (start, end),
+            });
+        }
+        pos = start + 2;
+    }
+    None
+}
+
+fn next_tag(src: &str) -> Option<usize> {
+    match (src.find("{{"), src.find("{%")) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+enum Node {
+    Text(String),
+    Var {
+        name: String,
+        line: usize,
+    },
+    If {
+        condition: Condition,
+        line: usize,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    For {
+        item: String,
+        list: String,
+        line: usize,
+        body: Vec<Node>,
+    },
+}
+
+enum Condition {
+    Truthy(String),
+    Not(String),
+    Eq(String, String),
+    Ne(String, String),
+}
+
+/// The tag that ended a block.
+struct BlockEnd {
+    tag: String,
+    line: usize,
+}
+
+fn parse<'a>(
+    tokens: &mut impl Iterator<Item = Token<'a>>,
+) -> Result<(Vec<Node>, Option<BlockEnd>)> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text.to_string())),
+            Token::Var { expr, line } => nodes.push(Node::Var {
+                name: expr.to_string(),
+                line,
+            }),
+            Token::Tag { tag, line } => {
+                let words: Vec<&str> = tag.split_whitespace().collect();
+                match words.first().copied() {
+                    Some("if") => {
+                        let condition = parse_condition(&tag[2..], line)?;
+                        let (then, end) = parse(tokens)?;
+                        let otherwise = match end.as_ref().map(|end| end.tag.as_str()) {
+                            Some("endif") => Vec::new(),
+                            Some("else") => {
+                                let (otherwise, end) = parse(tokens)?;
+                                expect_end(end, "endif", "if", line)?;
+                                otherwise
+                            }
+                            _ => return Err(unclosed("if", "endif", line, end)),
+                        };
+                        nodes.push(Node::If {
+                            condition,
+                            line,
+                            then,
+                            otherwise,
+                        });
+                    }
+                    Some("for") => {
+                        let [_, item, "in", list] = words[..] else {
+                            anyhow::bail!("Expected {{% for <item> in <list> %}} on line {}", line);
+                        };
+                        let (body, end) = parse(tokens)?;
+                        expect_end(end, "endfor", "for", line)?;
+                        nodes.push(Node::For {
+                            item: item.to_string(),
+                            list: list.to_string(),
+                            line,
+                            body,
+                        });
+                    }
+                    Some("else") | Some("endif") | Some("endfor") if words.len() == 1 => {
+                        return Ok((
+                            nodes,
+                            Some(BlockEnd {
+                                tag: words[0].to_string(),
+                                line,
+                            }),
+                        ));
+                    }
+                    _ => anyhow::bail!("Unknown tag {{% {} %}} on line {}", tag, line),
+                }
+            }
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn parse_condition(condition: &str, line: usize) -> Result<Condition> {
+    let condition = condition.trim();
+    let literal = |value: &str| -> Result<String> {
+        let value = value.trim();
+        value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Expected a quoted string on line {}", line))
+    };
+
+    if let Some((name, value)) = condition.split_once("==") {
+        Ok(Condition::Eq(name.trim().to_string(), literal(value)?))
+    } else if let Some((name, value)) = condition.split_once("!=") {
+        Ok(Condition::Ne(name.trim().to_string(), literal(value)?))
+    } else if let Some(name) = condition.strip_prefix("not ") {
+        Ok(Condition::Not(name.trim().to_string()))
+    } else if !condition.is_empty() && !condition.contains(char::is_whitespace) {
+        Ok(Condition::Truthy(condition.to_string()))
+    } else {
+        anyhow::bail!("Invalid condition '{}' on line {}", condition, line)
+    }
+}
+
+fn expect_end(end: Option<BlockEnd>, expected: &str, block: &str, line: usize) -> Result<()> {
+    match end {
+        Some(end) if end.tag == expected => Ok(()),
+        end => Err(unclosed(block, expected, line, end)),
+    }
+}
+
+fn unclosed(block: &str, expected: &str, line: usize, end: Option<BlockEnd>) -> anyhow::Error {
+    match end {
+        Some(end) => anyhow::anyhow!(
+            "Expected {{% {} %}} for the {{% {} %}} on line {}, found {{% {} %}} on line {}",
+            expected,
+            block,
+            line,
+            end.tag,
+            end.line
+        ),
+        None => anyhow::anyhow!("Unclosed {{% {} %}} on line {}", block, line),
+    }
+}
+
+/// Loop items in scope, innermost last.
+type Scope = Vec<(String, BTreeMap<String, Value>)>;
+
+fn render_nodes(
+    nodes: &[Node],
+    context: &TemplateContext,
+    scope: &mut Scope,
+    out: &mut String,
+) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var { name, line } => match lookup(name, context, scope, *line)? {
+                Value::Str(s) => out.push_str(&s),
+                Value::Bool(b) => out.push_str(if b { "true" } else { "false" }),
+                Value::List(_) => anyhow::bail!(
+                    "'{}' on line {} is a list; loop over it with {{% for %}}",
+                    name,
+                    line
+                ),
+            },
+            Node::If {
+                condition,
+                line,
+                then,
+                otherwise,
+            } => {
+                let holds = match condition {
+                    Condition::Truthy(name) => lookup(name, context, scope, *line)?.is_truthy(),
+                    Condition::Not(name) => !lookup(name, context, scope, *line)?.is_truthy(),
+                    Condition::Eq(name, value) => {
+                        lookup(name, context, scope, *line)? == Value::Str(value.clone())
+                    }
+                    Condition::Ne(name, value) => {
+                        lookup(name, context, scope, *line)? != Value::Str(value.clone())
+                    }
+                };
+                render_nodes(if holds { then } else { otherwise }, context, scope, out)?;
+            }
+            Node::For {
+                item,
+                list,
+                line,
+                body,
+            } => {
+                let Value::List(items) = lookup(list, context, scope, *line)? else {
+                    anyhow::bail!("'{}' on line {} is not a list", list, line);
+                };
+                for fields in items {
+                    scope.push((item.clone(), fields));
+                    let rendered = render_nodes(body, context, scope, out);
+                    scope.pop();
+                    rendered?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lookup(name: &str, context: &TemplateContext, scope: &Scope, line: usize) -> Result<Value> {
+    let in_scope = name.split_once('.').and_then(|(head, field)| {
+        let (_, fields) = scope.iter().rev().find(|(item, _)| item == head)?;
+        fields.get(field).cloned()
+    });
+
+    in_scope
+        .or_else(|| context.get(name))
+        .ok_or_else(|| anyhow::anyhow!("Unknown template variable '{}' on line {}", name, line))
+}
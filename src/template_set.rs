@@ -0,0 +1,399 @@
+//! Sets of template files a project is scaffolded from.
+//!
+//! A template set is a directory holding a `template.toml` manifest next to
+//! the template files:
+//!
+//! ```toml
+//! name = "house-style"
+//! description = "Our account layout and error crate"
+//!
+//! [variables]
+//! author = "Platform team"     # default, override with --var author=...
+//!
+//! [[files]]
+//! source = "lib.rs"
+//! target = "src/lib.rs"        # itself a template, defaults to `source`
+//!
+//! [[files]]
+//! source = "tests/mollusk.rs"
+//! target = "tests/{{ crate_name }}.rs"
+//! when = 'test_framework == "mollusk"'
+//! ```
+//!
+//! Files are rendered with [`crate::template`] and get the same context as
+//! the built-in templates, plus the manifest variables. Without `[[files]]`
+//! every file of the directory is rendered to the same relative path. The
+//! built-in templates are a set too, see [`TemplateSet::builtin`], and can be
+//! written out with [`TemplateSet::export`] as a starting point.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::content::templates;
+use crate::runner::{CommandRunner, ProcessCommand};
+use crate::template::{self, TemplateContext};
+
+pub const MANIFEST_FILE: &str = "template.toml";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TemplateManifest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Variables the templates use on top of the built-in context, with
+    /// their default values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<FileMapping>,
+}
+
+/// Maps a template file to the project file it renders.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FileMapping {
+    /// Template file, relative to the template directory.
+    pub source: PathBuf,
+    /// Project file, relative to the project root. Rendered as a template;
+    /// defaults to `source`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Condition, as in `{% if %}`, the file is only generated when it holds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Copy the file as is instead of rendering it.
+    #[serde(default = "default_render", skip_serializing_if = "is_default_render")]
+    pub render: bool,
+}
+
+fn default_render() -> bool {
+    true
+}
+
+fn is_default_render(render: &bool) -> bool {
+    *render
+}
+
+impl FileMapping {
+    fn new(source: &str, target: Option<&str>, when: Option<&str>) -> Self {
+        Self {
+            source: PathBuf::from(source),
+            target: target.map(str::to_string),
+            when: when.map(str::to_string),
+            render: true,
+        }
+    }
+}
+
+/// A manifest with the contents of its template files.
+#[derive(Clone, Debug)]
+pub struct TemplateSet {
+    pub manifest: TemplateManifest,
+    /// Template sources keyed by their path in the manifest.
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl TemplateSet {
+    /// The templates `chio init` uses when no `--template` is given.
+    pub fn builtin() -> Self {
+        const MOLLUSK: &str = "test_framework == \"mollusk\"";
+        const LITESVM: &str = "test_framework == \"litesvm\"";
+        const SINGLE: &str = "not features.workspace";
+
+        let sources: [(&str, Option<&str>, Option<&str>, &str); 14] = [
+            ("README.md", None, Some(SINGLE), templates::readme_md()),
+            (".gitignore", None, Some(SINGLE), templates::gitignore()),
+            ("Cargo.toml", None, None, templates::CARGO_TOML),
+            ("src/lib.rs", None, None, templates::LIB_RS),
+            ("src/entrypoint.rs", None, None, templates::entrypoint_rs()),
+            ("src/errors.rs", None, None, templates::errors_rs()),
+            (
+                "src/instructions/mod.rs",
+                None,
+                None,
+                templates::instructions::instructions_mod_rs(),
+            ),
+            (
+                "src/instructions/initialize.rs",
+                None,
+                None,
+                templates::instructions::initialize(),
+            ),
+            (
+                "src/states/mod.rs",
+                None,
+                None,
+                templates::states::states_mod_rs(),
+            ),
+            (
+                "src/states/utils.rs",
+                None,
+                None,
+                templates::states::utils_rs(),
+            ),
+            (
+                "src/states/state.rs",
+                None,
+                None,
+                templates::states::state_rs(),
+            ),
+            (
+                "tests/mollusk.rs",
+                Some("tests/tests.rs"),
+                Some(MOLLUSK),
+                templates::unit_tests::UNIT_TEST_RS,
+            ),
+            (
+                "tests/litesvm.rs",
+                Some("tests/initialize.rs"),
+                Some(LITESVM),
+                templates::unit_tests::LITESVM_INITIALIZE_RS,
+            ),
+            (
+                "benches/compute_units.rs",
+                None,
+                Some(MOLLUSK),
+                templates::benches::COMPUTE_UNITS_RS,
+            ),
+        ];
+
+        let mut files = BTreeMap::new();
+        let mut mappings = Vec::new();
+        for (source, target, when, contents) in sources {
+            mappings.push(FileMapping::new(source, target, when));
+            files.insert(PathBuf::from(source), contents.to_string());
+        }
+
+        Self {
+            manifest: TemplateManifest {
+                name: "default".to_string(),
+                description: Some("Pinocchio program with Mollusk or LiteSVM tests".to_string()),
+                variables: BTreeMap::new(),
+                files: mappings,
+            },
+            files,
+        }
+    }
+
+    /// Reads the manifest in `dir` and every template file it refers to.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("No {} found in {}", MANIFEST_FILE, dir.display()))?;
+        let mut manifest: TemplateManifest = toml::from_str(&manifest)
+            .with_context(|| format!("Invalid {}", manifest_path.display()))?;
+
+        if manifest.files.is_empty() {
+            manifest.files = template_files(dir)?
+                .into_iter()
+                .map(|source| FileMapping {
+                    source,
+                    target: None,
+                    when: None,
+                    render: true,
+                })
+                .collect();
+        }
+
+        // Sources are resolved through symlinks, so a template cannot copy
+        // host files such as a wallet into the project.
+        let root = dir
+            .canonicalize()
+            .with_context(|| format!("Failed to read {}", dir.display()))?;
+        let mut files = BTreeMap::new();
+        for mapping in &manifest.files {
+            let path = dir.join(&mapping.source);
+            let inside = is_inside(&mapping.source)
+                && path
+                    .canonicalize()
+                    .with_context(|| format!("Failed to read template file {}", path.display()))?
+                    .starts_with(&root);
+            if !inside {
+                anyhow::bail!(
+                    "Template file {} must be inside the template directory",
+                    mapping.source.display()
+                );
+            }
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template file {}", path.display()))?;
+            files.insert(mapping.source.clone(), contents);
+        }
+
+        Ok(Self { manifest, files })
+    }
+
+    /// Checks that every override names a variable the manifest declares, as
+    /// anything else is most likely a typo.
+    pub fn check_variables(&self, overrides: &BTreeMap<String, String>) -> Result<()> {
+        if let Some(unknown) = overrides
+            .keys()
+            .find(|name| !self.manifest.variables.contains_key(*name))
+        {
+            anyhow::bail!(
+                "Template '{}' has no variable '{}'",
+                self.manifest.name,
+                unknown
+            );
+        }
+        Ok(())
+    }
+
+    /// Clones the git repository at `url` into a temporary checkout and
+    /// loads the set from it. The checkout lives in a fresh private directory
+    /// that is removed afterwards.
+    pub fn load_git(runner: &dyn CommandRunner, url: &str) -> Result<Self> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("chio-template-")
+            .tempdir()
+            .context("Failed to create a directory for the template checkout")?;
+        let checkout = temp_dir.path().join("template");
+        // `--` keeps a source like `--upload-pack=...` from being read as an option.
+        let clone = ProcessCommand::new("git", ["clone", "--depth", "1", "--", url])
+            .arg(checkout.as_os_str());
+        let output = runner.output(&clone)?;
+
+        if !output.is_success() {
+            anyhow::bail!("Failed to clone {}: {}", url, output.stderr.trim());
+        }
+        Self::load(&checkout)
+    }
+
+    /// Adds the manifest variables to `context`, taking `overrides` over
+    /// their defaults.
+    pub fn context(
+        &self,
+        context: TemplateContext,
+        overrides: &BTreeMap<String, String>,
+    ) -> Result<TemplateContext> {
+        self.check_variables(overrides)?;
+
+        let mut context = context;
+        for (name, default) in &self.manifest.variables {
+            let value = overrides.get(name).unwrap_or(default);
+            context = context.set(name, value.as_str());
+        }
+        Ok(context)
+    }
+
+    /// Renders the files of the set, returning each project path with its
+    /// contents, in manifest order.
+    pub fn render(&self, context: &TemplateContext) -> Result<Vec<(PathBuf, String)>> {
+        let mut rendered = Vec::new();
+
+        for mapping in &self.manifest.files {
+            if let Some(condition) = &mapping.when {
+                let check = format!("{{% if {} %}}yes{{% endif %}}", condition);
+                let holds = template::render(&check, context)
+                    .with_context(|| format!("Invalid `when` for {}", mapping.source.display()))?;
+                if holds.is_empty() {
+                    continue;
+                }
+            }
+
+            let target = match &mapping.target {
+                Some(target) => template::render(target, context)
+                    .with_context(|| format!("Invalid target for {}", mapping.source.display()))?,
+                None => mapping.source.to_string_lossy().into_owned(),
+            };
+            let target = PathBuf::from(target);
+            if !is_inside(&target) {
+                anyhow::bail!("Target {} must be inside the project", target.display());
+            }
+
+            let source = &self.files[&mapping.source];
+            let contents = if mapping.render {
+                template::render(source, context)
+                    .with_context(|| format!("Failed to render {}", mapping.source.display()))?
+            } else {
+                source.clone()
+            };
+            rendered.push((target, contents));
+        }
+
+        Ok(rendered)
+    }
+
+    /// Renders the set into `dir`, returning the paths written relative to it.
+    pub fn write(&self, dir: &Path, context: &TemplateContext) -> Result<Vec<PathBuf>> {
+        let rendered = self.render(context)?;
+        for (path, contents) in &rendered {
+            let full_path = dir.join(path);
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&full_path, contents)
+                .with_context(|| format!("Failed to write {}", full_path.display()))?;
+        }
+        Ok(rendered.into_iter().map(|(path, _)| path).collect())
+    }
+
+    /// Writes the manifest and template files to `dir`, so the set can be
+    /// loaded back with [`TemplateSet::load`].
+    pub fn export(&self, dir: &Path) -> Result<()> {
+        for (source, contents) in &self.files {
+            let path = dir.join(source);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        fs::write(dir.join(MANIFEST_FILE), toml::to_string(&self.manifest)?)
+            .with_context(|| format!("Failed to write {}", dir.join(MANIFEST_FILE).display()))
+    }
+}
+
+/// Whether a `--template` argument names a git repository rather than a
+/// local directory.
+pub fn is_git_url(source: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "git@"]
+        .iter()
+        .any(|prefix| source.starts_with(prefix))
+        || (source.ends_with(".git") && !Path::new(source).is_dir())
+}
+
+/// Parses a `--var NAME=VALUE` argument.
+pub fn parse_variable(spec: &str) -> Result<(String, String)> {
+    match spec.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => anyhow::bail!("Invalid variable '{}'. Expected NAME=VALUE", spec),
+    }
+}
+
+/// Whether the relative `path` stays inside the directory it is joined to.
+fn is_inside(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Lists the files under `dir` other than the manifest and `.git`, relative
+/// to `dir` and sorted.
+fn template_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+
+    while let Some(relative) = pending.pop() {
+        let full_dir = dir.join(&relative);
+        for entry in fs::read_dir(&full_dir)
+            .with_context(|| format!("Failed to read {}", full_dir.display()))?
+        {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                if entry.file_name() != ".git" {
+                    pending.push(path);
+                }
+            } else if path != Path::new(MANIFEST_FILE) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,356 @@
+//! Tests for the `chio add` code generators
+//!
+//! These scaffold a project from the templates into a temp directory and
+//! check that generators patch it in place without dropping user edits.
+
+mod common;
+
+#[cfg(test)]
+mod add {
+    use crate::common::{generator, scaffold, PROGRAM_ID};
+    use chio::add::{self, FieldType, Project, StateField};
+    use chio::content::templates;
+    use chio::TestFramework;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn mollusk_project() -> (TempDir, Project) {
+        scaffold(generator("test_project"))
+    }
+
+    #[test]
+    fn next_discriminator_follows_initialize() {
+        let mod_rs = templates::instructions::instructions_mod_rs();
+        assert_eq!(add::next_discriminator(mod_rs).unwrap(), 1);
+    }
+
+    #[test]
+    fn patch_instructions_mod_registers_module_variant_and_arm() {
+        let mod_rs = templates::instructions::instructions_mod_rs();
+        let patched = add::patch_instructions_mod(mod_rs, "deposit", "Deposit", 1).unwrap();
+
+        assert!(patched.contains("pub mod initialize;\npub mod deposit;"));
+        assert!(patched.contains("pub use initialize::*;\npub use deposit::*;"));
+        assert!(patched.contains(
+            "    InitializeState,\n    #[account(0, signer, name = \"signer\", desc = \"Signer of the instruction\")]\n    Deposit,\n}"
+        ));
+        assert!(
+            patched.contains("            1 => Ok(ProgramInstruction::Deposit),\n            _ =>")
+        );
+        assert_eq!(add::next_discriminator(&patched).unwrap(), 2);
+    }
+
+    #[test]
+    fn patch_instructions_mod_rejects_existing_variant() {
+        let mod_rs = templates::instructions::instructions_mod_rs();
+        assert!(add::patch_instructions_mod(mod_rs, "init_state", "InitializeState", 1).is_err());
+    }
+
+    #[test]
+    fn patch_entrypoint_adds_dispatch_arm() {
+        let patched =
+            add::patch_entrypoint(templates::entrypoint_rs(), "deposit", "Deposit").unwrap();
+
+        assert!(patched.contains("        ProgramInstruction::Deposit => {"));
+        assert!(patched.contains("            instructions::deposit(accounts, instruction_data)"));
+        assert!(patched.find("InitializeState").unwrap() < patched.find("Deposit").unwrap());
+    }
+
+    #[test]
+    fn add_instruction_creates_handler_and_mollusk_test() {
+        let (temp_dir, project) = mollusk_project();
+        let generated = add::add_instruction(&project, "deposit").unwrap();
+
+        assert_eq!(generated.created.len(), 2);
+        let handler =
+            fs::read_to_string(temp_dir.path().join("src/instructions/deposit.rs")).unwrap();
+        assert!(handler.contains("pub struct Deposit"));
+        assert!(handler.contains("impl DataLen for Deposit"));
+        assert!(handler.contains("pub fn deposit(accounts: &[AccountInfo], data: &[u8])"));
+
+        let test = fs::read_to_string(temp_dir.path().join("tests/deposit.rs")).unwrap();
+        assert!(test.contains("use mollusk_svm"));
+        assert!(test.contains(PROGRAM_ID));
+        assert!(test.contains("use test_project::instructions::Deposit"));
+        assert!(test.contains("vec![1]"));
+    }
+
+    #[test]
+    fn add_instruction_registers_a_compute_unit_bench() {
+        let (temp_dir, project) = mollusk_project();
+        assert!(temp_dir.path().join("benches/compute_units.rs").exists());
+
+        let generated = add::add_instruction(&project, "deposit").unwrap();
+        assert!(generated
+            .updated
+            .contains(&Path::new("benches").join("compute_units.rs")));
+        let bench = fs::read_to_string(temp_dir.path().join("benches/compute_units.rs")).unwrap();
+        assert!(bench.contains("fn deposit(_mollusk: &Mollusk) -> Case {"));
+        assert!(bench.contains("test_project::instructions::Deposit {}"));
+        assert!(bench.contains("}\n\nfn main() {"));
+        assert!(bench.contains(
+            "        (\"initialize\", initialize(&mollusk)),\n        (\"deposit\", deposit(&mollusk)),\n    ];"
+        ));
+
+        let error = add::patch_bench(&bench, "deposit", "").unwrap_err();
+        assert!(error.to_string().contains("already benchmarked"));
+    }
+
+    #[test]
+    fn add_instruction_uses_litesvm_when_project_does() {
+        let (temp_dir, project) =
+            scaffold(generator("test_project").with_test_framework(TestFramework::Litesvm));
+
+        add::add_instruction(&project, "withdraw").unwrap();
+
+        let test = fs::read_to_string(temp_dir.path().join("tests/withdraw.rs")).unwrap();
+        assert!(test.contains("use litesvm"));
+        assert!(test.contains("vec![1u8]"));
+    }
+
+    #[test]
+    fn add_instruction_keeps_user_edits() {
+        let (temp_dir, project) = mollusk_project();
+        let entrypoint_path = temp_dir.path().join("src/entrypoint.rs");
+        let edited = templates::entrypoint_rs()
+            .replace("msg!(\"initialize\");", "msg!(\"initialize {custom}\");");
+        fs::write(&entrypoint_path, edited).unwrap();
+
+        add::add_instruction(&project, "deposit").unwrap();
+        add::add_instruction(&project, "withdraw").unwrap();
+
+        let entrypoint = fs::read_to_string(&entrypoint_path).unwrap();
+        assert!(entrypoint.contains("msg!(\"initialize {custom}\");"));
+        assert!(entrypoint.contains("ProgramInstruction::Withdraw"));
+
+        let mod_rs = fs::read_to_string(temp_dir.path().join("src/instructions/mod.rs")).unwrap();
+        assert!(mod_rs.contains("2 => Ok(ProgramInstruction::Withdraw)"));
+    }
+
+    #[test]
+    fn add_instruction_rejects_duplicates_and_bad_names() {
+        let (_temp_dir, project) = mollusk_project();
+        add::add_instruction(&project, "deposit").unwrap();
+
+        assert!(add::add_instruction(&project, "deposit").is_err());
+        assert!(add::add_instruction(&project, "Deposit").is_err());
+        assert!(add::add_instruction(&project, "fn").is_err());
+        for keyword in ["try", "gen", "yield", "abstract", "typeof"] {
+            assert!(
+                add::add_instruction(&project, keyword).is_err(),
+                "{} was accepted",
+                keyword
+            );
+        }
+    }
+
+    fn fields(specs: &[&str]) -> Vec<StateField> {
+        specs
+            .iter()
+            .map(|spec| StateField::parse(spec).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn field_type_parses_supported_types() {
+        assert_eq!(FieldType::parse("u64").unwrap(), FieldType::U64);
+        assert_eq!(FieldType::parse("Pubkey").unwrap(), FieldType::Pubkey);
+        assert_eq!(FieldType::parse("[u8; 16]").unwrap(), FieldType::Bytes(16));
+        assert!(FieldType::parse("bool").is_err());
+        assert!(FieldType::parse("u128").is_err());
+        assert!(FieldType::parse("String").is_err());
+    }
+
+    #[test]
+    fn state_layout_accepts_padding_free_layouts() {
+        assert_eq!(
+            add::state_layout(&fields(&["owner:Pubkey", "amount:u64"])).unwrap(),
+            40
+        );
+        assert_eq!(
+            add::state_layout(&fields(&["amount:u64", "owner:Pubkey"])).unwrap(),
+            40
+        );
+        assert_eq!(
+            add::state_layout(&fields(&["owner:Pubkey", "bump:u8"])).unwrap(),
+            33
+        );
+    }
+
+    #[test]
+    fn state_layout_rejects_padding() {
+        let interior = add::state_layout(&fields(&["bump:u8", "amount:u64"])).unwrap_err();
+        assert!(interior
+            .to_string()
+            .contains("--field amount:u64 --field bump:u8"));
+
+        let trailing = add::state_layout(&fields(&["amount:u64", "bump:u8"])).unwrap_err();
+        assert!(trailing.to_string().contains("_padding:[u8; 7]"));
+
+        assert!(add::state_layout(&fields(&["amount:u64", "bump:u8", "_padding:[u8; 7]"])).is_ok());
+        assert!(add::state_layout(&[]).is_err());
+        assert!(add::state_layout(&fields(&["a:u8", "a:u8"])).is_err());
+    }
+
+    #[test]
+    fn add_state_generates_struct_and_registers_module() {
+        let (temp_dir, project) = mollusk_project();
+        let generated = add::add_state(
+            &project,
+            "UserVault",
+            &fields(&["owner:Pubkey", "amount:u64"]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            generated.created,
+            vec![Path::new("src/states/user_vault.rs")]
+        );
+
+        let state = fs::read_to_string(temp_dir.path().join("src/states/user_vault.rs")).unwrap();
+        assert!(state.contains("#[repr(C)]"));
+        assert!(state
+            .contains("pub struct UserVault {\n    pub owner: Pubkey,\n    pub amount: u64,\n}"));
+        assert!(state.contains("impl DataLen for UserVault"));
+        assert!(state.contains("pub const SEED: &'static str = \"user_vault\";"));
+        assert!(state.contains("fn validate_pda"));
+        assert!(state.contains("load_acc_unchecked::<Self>"));
+        assert!(state.contains("load_acc_mut_unchecked::<Self>"));
+
+        let mod_rs = fs::read_to_string(temp_dir.path().join("src/states/mod.rs")).unwrap();
+        assert!(mod_rs.contains("pub mod user_vault;"));
+        assert!(mod_rs.contains("pub use user_vault::*;"));
+    }
+
+    #[test]
+    fn add_state_refuses_bad_layouts_without_writing() {
+        let (temp_dir, project) = mollusk_project();
+
+        assert!(
+            add::add_state(&project, "Vault", &fields(&["bump:u8", "amount:u64"]), None).is_err()
+        );
+        assert!(add::add_state(&project, "vault", &fields(&["amount:u64"]), None).is_err());
+        assert!(!temp_dir.path().join("src/states/vault.rs").exists());
+
+        let error = add::add_state(&project, "Type", &fields(&["amount:u64"]), None).unwrap_err();
+        assert!(error.to_string().contains("`type` is a Rust keyword"));
+        for seed in ["a\"b", "a\\b", "tab\there", "", &"s".repeat(33)] {
+            assert!(
+                add::add_state(&project, "Vault", &fields(&["amount:u64"]), Some(seed)).is_err()
+            );
+        }
+        assert!(!temp_dir.path().join("src/states/vault.rs").exists());
+        assert!(!temp_dir.path().join("src/states/type.rs").exists());
+    }
+
+    #[test]
+    fn to_snake_case_keeps_acronyms_together() {
+        assert_eq!(add::to_snake_case("MyState"), "my_state");
+        assert_eq!(add::to_snake_case("HTTPConfig"), "http_config");
+        assert_eq!(add::to_snake_case("PDA"), "pda");
+        assert_eq!(add::to_snake_case("VaultV2State"), "vault_v2_state");
+        assert_eq!(add::to_snake_case("Vault2"), "vault2");
+    }
+
+    #[test]
+    fn parse_errors_assigns_implicit_codes() {
+        let variants = add::parse_errors(templates::errors_rs()).unwrap();
+        let codes: Vec<(u32, &str)> = variants.iter().map(|v| (v.code, v.name.as_str())).collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                (0, "InvalidInstructionData"),
+                (1, "PdaMismatch"),
+                (2, "InvalidOwner")
+            ]
+        );
+        assert_eq!(variants[1].message, "Pda mismatch");
+    }
+
+    #[test]
+    fn parse_errors_ignores_trailing_comments() {
+        let src = templates::errors_rs().replace("InvalidOwner,", "InvalidOwner, // owner check");
+        let variants = add::parse_errors(&src).unwrap();
+        assert_eq!(variants.len(), 3);
+        assert_eq!(variants[2].name, "InvalidOwner");
+
+        let patched = add::patch_errors(&src, None).unwrap();
+        assert!(patched.contains("    InvalidOwner = 2, // owner check\n"));
+        assert_eq!(add::parse_errors(&patched).unwrap(), variants);
+    }
+
+    #[test]
+    fn patch_errors_makes_codes_explicit_and_generates_helpers() {
+        let patched = add::patch_errors(templates::errors_rs(), None).unwrap();
+
+        assert!(patched.contains(
+            "    InvalidInstructionData = 0,\n    PdaMismatch = 1,\n    InvalidOwner = 2,\n}"
+        ));
+        assert!(patched.contains("Self::PdaMismatch => \"Pda mismatch\","));
+        assert!(patched.contains("impl core::fmt::Display for MyProgramError"));
+        assert!(patched.contains("impl From<MyProgramError> for ProgramError"));
+
+        // Regenerating is idempotent.
+        assert_eq!(add::patch_errors(&patched, None).unwrap(), patched);
+    }
+
+    #[test]
+    fn add_error_appends_variant_and_writes_table() {
+        let (temp_dir, project) = mollusk_project();
+        add::add_error(
+            &project,
+            "InsufficientFunds",
+            None,
+            Some("Not enough lamports"),
+        )
+        .unwrap();
+        add::add_error(&project, "Frozen", Some(6000), None).unwrap();
+
+        let errors = fs::read_to_string(temp_dir.path().join("src/errors.rs")).unwrap();
+        assert!(errors.contains("    /// Not enough lamports\n    InsufficientFunds = 3,"));
+        assert!(errors.contains("    Frozen = 6000,"));
+        assert_eq!(errors.matches("impl MyProgramError").count(), 1);
+
+        let table: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.path().join("idl/errors.table.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(table["errors"][3]["code"], 3);
+        assert_eq!(table["errors"][3]["name"], "InsufficientFunds");
+        assert_eq!(table["errors"][3]["msg"], "Not enough lamports");
+        assert_eq!(table["errors"][4]["code"], 6000);
+    }
+
+    #[test]
+    fn add_error_rejects_taken_names_and_codes() {
+        let (_temp_dir, project) = mollusk_project();
+
+        assert!(add::add_error(&project, "PdaMismatch", None, None).is_err());
+        assert!(add::add_error(&project, "Other", Some(1), None).is_err());
+        assert!(add::add_error(&project, "bad_name", None, None).is_err());
+    }
+
+    #[test]
+    fn patch_workspace_members_appends_member() {
+        let manifest = templates::workspace::cargo_toml();
+        let patched = add::patch_workspace_members(manifest, "programs/vault").unwrap();
+        assert!(patched.contains("members = [\"test-helpers\", \"programs/vault\"]"));
+        assert!(patched.contains("resolver = \"2\""));
+
+        assert!(add::patch_workspace_members(&patched, "programs/vault").is_err());
+        assert!(add::patch_workspace_members("[package]\n", "programs/vault").is_err());
+    }
+
+    #[test]
+    fn add_instruction_in_workspace_program_uses_test_helpers() {
+        let (temp_dir, project) = scaffold(generator("vault").with_feature("workspace", true));
+        let program_dir = temp_dir.path().join("programs/vault");
+        add::add_instruction(&project, "deposit").unwrap();
+
+        let test = fs::read_to_string(program_dir.join("tests/deposit.rs")).unwrap();
+        assert!(test.contains("Mollusk::new(&PROGRAM, &test_helpers::program_path(\"vault\"))"));
+        assert!(!test.contains("\"target/deploy"));
+    }
+}
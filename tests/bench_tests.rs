@@ -0,0 +1,148 @@
+//! Tests for `chio bench`
+//!
+//! Covers parsing the mollusk-svm-bencher report, comparing it with the
+//! committed baseline and running the bench script through a recording
+//! runner.
+
+#[cfg(test)]
+mod bench {
+    use chio::bench::{self, BenchResult, Comparison};
+    use chio::generator::ProjectGenerator;
+    use chio::runner::{CommandOutput, RecordingRunner};
+    use chio::TestFramework;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const REPORT: &str = "#### Compute Units: 2025-01-10 09:30:12.345 UTC
+
+| Name | CUs | Delta |
+|------|------|-------|
+| initialize | 1612 | +32 |
+| deposit | 130 | - new - |
+
+#### Compute Units: 2025-01-09 18:02:44.101 UTC
+
+| Name | CUs | Delta |
+|------|------|-------|
+| initialize | 1580 | - new - |
+";
+
+    fn result(name: &str, compute_units: u64) -> BenchResult {
+        BenchResult {
+            name: name.to_string(),
+            compute_units,
+        }
+    }
+
+    #[test]
+    fn parses_the_latest_table() {
+        assert_eq!(
+            bench::parse_report(REPORT),
+            vec![result("initialize", 1612), result("deposit", 130)]
+        );
+        assert_eq!(
+            bench::parse_report("| Name | CUs |\n|---|---|\n| big | 1,200,000 |\n"),
+            vec![result("big", 1_200_000)]
+        );
+        assert!(bench::parse_report("no benchmarks yet").is_empty());
+    }
+
+    #[test]
+    fn compares_with_the_baseline() {
+        let current = [result("initialize", 1612), result("deposit", 130)];
+        let baseline = [result("initialize", 1580), result("withdraw", 90)];
+
+        let comparisons = bench::compare(&current, &baseline);
+        let names: Vec<&str> = comparisons.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["initialize", "deposit", "withdraw"]);
+
+        assert_eq!(comparisons[0].delta(), Some(32));
+        assert!(comparisons[0].is_regression());
+        assert_eq!(
+            comparisons[0].to_string(),
+            "1612 CUs, +32 (+2.0%) from 1580"
+        );
+        assert_eq!(comparisons[1].to_string(), "130 CUs, new");
+        assert_eq!(comparisons[2].to_string(), "removed, was 90 CUs");
+
+        let improved = Comparison {
+            name: "initialize".to_string(),
+            compute_units: Some(1500),
+            baseline: Some(1580),
+        };
+        assert!(!improved.is_regression());
+        assert_eq!(improved.to_string(), "1500 CUs, -80 (-5.1%) from 1580");
+    }
+
+    #[test]
+    fn runs_the_bench_script_and_compares_with_head() {
+        let temp_dir = TempDir::new().unwrap();
+        ProjectGenerator::new("vault")
+            .with_output_dir(temp_dir.path())
+            .generate()
+            .unwrap()
+            .write()
+            .unwrap();
+        fs::write(temp_dir.path().join("benches/compute_units.md"), REPORT).unwrap();
+        let baseline =
+            "| Name | CUs | Delta |\n|------|------|-------|\n| initialize | 1612 | -- |\n";
+        let runner = RecordingRunner::new().with_output(
+            "git show HEAD:./benches/compute_units.md",
+            CommandOutput::success(baseline),
+        );
+
+        let benches = bench::run_bench(&runner, temp_dir.path(), None).unwrap();
+        assert_eq!(
+            runner.command_lines(),
+            vec![
+                "cargo bench --bench compute_units",
+                "git show HEAD:./benches/compute_units.md"
+            ]
+        );
+        assert_eq!(benches.len(), 1);
+        assert_eq!(benches[0].program, "vault");
+        assert!(benches[0].has_baseline);
+        assert_eq!(benches[0].comparisons[0].delta(), Some(0));
+        assert_eq!(benches[0].comparisons[1].baseline, None);
+    }
+
+    #[test]
+    fn without_a_commit_every_benchmark_is_new() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("benches")).unwrap();
+        fs::write(temp_dir.path().join("benches/compute_units.md"), REPORT).unwrap();
+        let runner = RecordingRunner::new().with_output(
+            "git show",
+            CommandOutput::failure(128, "fatal: invalid object name 'HEAD'."),
+        );
+
+        let benches = bench::run_bench(&runner, temp_dir.path(), None).unwrap();
+        assert!(!benches[0].has_baseline);
+        assert!(benches[0].comparisons.iter().all(|c| c.baseline.is_none()));
+    }
+
+    #[test]
+    fn needs_a_mollusk_program_and_a_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let runner = RecordingRunner::new();
+        let error = bench::run_bench(&runner, temp_dir.path(), None).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("benches/compute_units.md was not written"));
+
+        ProjectGenerator::new("vault")
+            .with_test_framework(TestFramework::Litesvm)
+            .with_output_dir(temp_dir.path())
+            .generate()
+            .unwrap()
+            .write()
+            .unwrap();
+        assert!(!temp_dir.path().join("benches").exists());
+        let runner = RecordingRunner::new();
+        let error = bench::run_bench(&runner, temp_dir.path(), None).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("no program here is tested with Mollusk"));
+        assert!(runner.commands().is_empty());
+    }
+}
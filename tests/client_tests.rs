@@ -0,0 +1,171 @@
+//! Tests for client generation
+//!
+//! Clients are rendered from the IDL of a scaffolded project; these check the
+//! builders, decoders and error tables follow the program source.
+
+mod common;
+
+#[cfg(test)]
+mod client {
+    use crate::common::{project, PROGRAM_ID};
+    use chio::add::{self, FieldType, StateField};
+    use chio::client;
+    use chio::idl::{Idl, IdlAccountMeta, IdlInstruction, IdlStruct};
+    use std::fs;
+    use std::path::Path;
+
+    fn field(name: &str, ty: FieldType) -> StateField {
+        StateField {
+            name: name.to_string(),
+            ty,
+        }
+    }
+
+    #[test]
+    fn layout_matches_repr_c() {
+        let args = IdlStruct {
+            name: "Deposit".to_string(),
+            fields: vec![
+                field("bump", FieldType::U8),
+                field("amount", FieldType::U64),
+                field("flag", FieldType::U16),
+            ],
+        };
+        assert_eq!(args.layout(), (vec![0, 8, 16], 24));
+
+        let packed = IdlStruct {
+            name: "Initialize".to_string(),
+            fields: vec![
+                field("owner", FieldType::Pubkey),
+                field("bump", FieldType::U8),
+            ],
+        };
+        assert_eq!(packed.layout(), (vec![0, 32], 33));
+    }
+
+    #[test]
+    fn generates_the_rust_client() {
+        let (temp_dir, project) = project();
+        let fields = [
+            StateField::parse("owner:Pubkey").unwrap(),
+            StateField::parse("amount:u64").unwrap(),
+        ];
+        add::add_state(&project, "Vault", &fields, None).unwrap();
+
+        let generated = client::generate_rust(&project).unwrap();
+        assert!(generated
+            .created
+            .contains(&Path::new("clients/rust/src/instructions.rs").to_path_buf()));
+
+        let dir = temp_dir.path().join("clients/rust");
+        let manifest = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"vault_client\""));
+        assert!(manifest.contains("[workspace]"));
+        let lib = fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+        assert!(lib.contains(&format!("pubkey!(\"{}\")", PROGRAM_ID)));
+
+        let instructions = fs::read_to_string(dir.join("src/instructions.rs")).unwrap();
+        assert!(instructions.contains("pub const INITIALIZE_STATE_DISCRIMINATOR: u8 = 0;"));
+        assert!(instructions.contains(
+            "pub fn initialize_state(accounts: &InitializeStateAccounts, args: &Initialize) -> Instruction {"
+        ));
+        assert!(instructions.contains("AccountMeta::new(accounts.payer, true),"));
+        assert!(instructions.contains("AccountMeta::new_readonly(accounts.system_program, false),"));
+        assert!(instructions.contains("data[32..33].copy_from_slice(&self.bump.to_le_bytes());"));
+
+        let accounts = fs::read_to_string(dir.join("src/accounts.rs")).unwrap();
+        assert!(accounts.contains("pub const SEED: &'static str = \"init\";"));
+        assert!(accounts.contains("pub const SEED: &'static str = \"vault\";"));
+        assert!(accounts.contains(
+            "Pubkey::find_program_address(&[Self::SEED.as_bytes(), owner.as_ref()], &crate::ID)"
+        ));
+        assert!(accounts.contains("amount: u64::from_le_bytes(data[32..40].try_into().unwrap()),"));
+
+        let errors = fs::read_to_string(dir.join("src/errors.rs")).unwrap();
+        assert!(errors.contains("1 => Some(Self::PdaMismatch),"));
+
+        let regenerated = client::generate_rust(&project).unwrap();
+        assert!(regenerated.created.is_empty());
+        assert_eq!(regenerated.updated.len(), generated.created.len());
+    }
+
+    #[test]
+    fn generates_the_js_client() {
+        let (temp_dir, project) = project();
+        let fields = [
+            StateField::parse("owner:Pubkey").unwrap(),
+            StateField::parse("amount:u64").unwrap(),
+            StateField::parse("tag:[u8; 8]").unwrap(),
+        ];
+        add::add_state(&project, "Vault", &fields, None).unwrap();
+
+        let generated = client::generate(&project, client::Language::Js).unwrap();
+        assert!(generated
+            .created
+            .contains(&Path::new("clients/js/package.json").to_path_buf()));
+
+        let dir = temp_dir.path().join("clients/js");
+        let package: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("package.json")).unwrap()).unwrap();
+        assert_eq!(package["name"], "vault-client");
+        assert!(package["peerDependencies"]["@solana/kit"].is_string());
+        let program = fs::read_to_string(dir.join("src/program.ts")).unwrap();
+        assert!(program.contains(&format!("address(\"{}\")", PROGRAM_ID)));
+
+        let instructions = fs::read_to_string(dir.join("src/instructions.ts")).unwrap();
+        assert!(instructions.contains("export const INITIALIZE_STATE_DISCRIMINATOR = 0;"));
+        assert!(instructions.contains("  systemProgram: Address;"));
+        assert!(instructions
+            .contains("{ address: accounts.payer, role: AccountRole.WRITABLE_SIGNER },"));
+        assert!(instructions.contains(
+            "data: new Uint8Array([INITIALIZE_STATE_DISCRIMINATOR, ...encodeInitialize(args)]),"
+        ));
+
+        let accounts = fs::read_to_string(dir.join("src/accounts.ts")).unwrap();
+        assert!(accounts.contains("export const MY_STATE_SEED = \"init\";"));
+        assert!(accounts.contains("seeds: [VAULT_SEED, addressEncoder.encode(owner)],"));
+        assert!(accounts.contains("  amount: bigint;"));
+        assert!(accounts.contains("view.setBigUint64(32, value.amount, true);"));
+        assert!(accounts.contains("tag: data.slice(40, 48),"));
+
+        let errors = fs::read_to_string(dir.join("src/errors.ts")).unwrap();
+        assert!(errors.contains("  PdaMismatch = 1,"));
+        assert!(errors.contains("  1: \"Pda mismatch\","));
+    }
+
+    #[test]
+    fn optional_accounts_fall_back_to_the_program_id() {
+        let idl = Idl {
+            name: "vault".to_string(),
+            version: "0.1.0".to_string(),
+            address: PROGRAM_ID.to_string(),
+            instructions: vec![IdlInstruction {
+                name: "Close".to_string(),
+                docs: Vec::new(),
+                discriminator: 3,
+                accounts: vec![IdlAccountMeta {
+                    name: "authority".to_string(),
+                    writable: false,
+                    signer: true,
+                    optional: true,
+                    docs: Vec::new(),
+                }],
+                args: None,
+            }],
+            accounts: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let files = client::render_rust_client(&idl).unwrap();
+        let (_, instructions) = files
+            .iter()
+            .find(|(path, _)| path.ends_with("instructions.rs"))
+            .unwrap();
+        assert!(instructions.contains("pub authority: Option<Pubkey>,"));
+        assert!(instructions.contains(
+            "accounts.authority.map_or(AccountMeta::new_readonly(crate::ID, false), |key| AccountMeta::new_readonly(key, true)),"
+        ));
+        assert!(instructions.contains("/// Builds `ProgramInstruction::Close`."));
+        assert!(instructions.contains("let data = vec![CLOSE_DISCRIMINATOR];"));
+    }
+}
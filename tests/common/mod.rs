@@ -0,0 +1,45 @@
+//! Fixtures shared by the integration tests
+//!
+//! Test files that scaffold a project declare `mod common;` and start from
+//! [`generator`], adjusting it before [`scaffold`] writes it.
+
+#![allow(dead_code)]
+
+use chio::add::Project;
+use chio::generator::ProjectGenerator;
+use tempfile::TempDir;
+
+pub const PROGRAM_ID: &str = "11111111111111111111111111111112";
+
+/// A generator for the program `name`, declared as [`PROGRAM_ID`] and paying
+/// from it, so nothing depends on a fresh keypair.
+pub fn generator(name: &str) -> ProjectGenerator {
+    ProjectGenerator::new(name)
+        .with_program_id(PROGRAM_ID)
+        .with_payer(PROGRAM_ID)
+}
+
+/// Writes `generator` into a new temp directory, as `chio init` would
+/// without running git, and opens the program.
+pub fn scaffold(generator: ProjectGenerator) -> (TempDir, Project) {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let generated = generator
+        .with_output_dir(temp_dir.path())
+        .generate()
+        .expect("Failed to generate project");
+    generated.write_files().expect("Failed to write project");
+
+    let project = match generated.name.as_str() {
+        name if temp_dir.path().join("programs").join(name).exists() => {
+            Project::open_member(temp_dir.path(), name)
+        }
+        _ => Project::open(temp_dir.path()),
+    }
+    .expect("Failed to open project");
+    (temp_dir, project)
+}
+
+/// The Mollusk program `vault`.
+pub fn project() -> (TempDir, Project) {
+    scaffold(generator("vault"))
+}
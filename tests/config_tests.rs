@@ -0,0 +1,285 @@
+//! Tests for the `Chio.toml` project configuration
+//!
+//! Covers what `chio init` writes and how build, test and deploy read it
+//! back.
+
+#[cfg(test)]
+mod config {
+    use chio::add::Project;
+    use chio::build::{BuildOptions, SbfArch};
+    use chio::config::{self, ChioConfig, ProgramConfig, CONFIG_FILE};
+    use chio::content::templates;
+    use chio::deploy::{Cluster, DeployOptions};
+    use chio::TestFramework;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    const PROGRAM_ID: &str = "11111111111111111111111111111112";
+
+    #[test]
+    fn new_config_records_program_and_localnet_id() {
+        let config = ChioConfig::new("my_program", TestFramework::Litesvm, PROGRAM_ID);
+        let toml = config.to_toml().unwrap();
+
+        assert!(toml.contains("name = \"my_program\""));
+        assert!(toml.contains("test_framework = \"litesvm\""));
+        assert!(toml.contains("keypair = \"target/deploy/my_program-keypair.json\""));
+        assert!(toml.contains("cluster = \"localnet\""));
+        assert!(toml.contains("[programs.localnet]"));
+        assert!(toml.contains(&format!("my_program = \"{}\"", PROGRAM_ID)));
+        assert!(toml.contains("build = \"cargo build-sbf\""));
+    }
+
+    #[test]
+    fn config_round_trips_through_disk() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let mut config = ChioConfig::new("my_program", TestFramework::Mollusk, PROGRAM_ID);
+        config.record_program_id(&Cluster::Devnet, "my_program", "Devnet111");
+        config.save(temp_dir.path()).unwrap();
+
+        let loaded = ChioConfig::load(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(loaded.programs["devnet"]["my_program"], "Devnet111");
+    }
+
+    #[test]
+    fn save_keeps_comments_and_only_touches_changed_keys() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let original = "# Shared by the whole team\n\
+                        [workspace]\n\n\
+                        [[workspace.members]]\n\
+                        name = \"vault\" # the first program\n\
+                        test_framework = \"mollusk\"\n\
+                        keypair = \"target/deploy/vault-keypair.json\"\n\n\
+                        [provider]\n\
+                        cluster = \"localnet\"   # switch to devnet to share\n\n\
+                        [scripts]\n\
+                        build = \"cargo build-sbf\"\n\
+                        test = \"cargo test\"\n\
+                        bench = \"cargo bench --bench compute_units\"\n\n\
+                        [programs.localnet]\n\
+                        vault = \"Vault111\" # keep in sync with declare_id!\n";
+        fs::write(temp_dir.path().join(CONFIG_FILE), original).unwrap();
+
+        let mut config = ChioConfig::load(temp_dir.path()).unwrap().unwrap();
+        config.save(temp_dir.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(CONFIG_FILE)).unwrap(),
+            original
+        );
+
+        config.record_program_id(&Cluster::Localnet, "vault", "Vault222");
+        config
+            .add_member(
+                ProgramConfig::new("escrow", TestFramework::Litesvm),
+                "Escrow111",
+            )
+            .unwrap();
+        config.save(temp_dir.path()).unwrap();
+
+        let saved = fs::read_to_string(temp_dir.path().join(CONFIG_FILE)).unwrap();
+        assert!(saved.starts_with("# Shared by the whole team\n"));
+        assert!(saved.contains("name = \"vault\" # the first program\n"));
+        assert!(saved.contains("cluster = \"localnet\"   # switch to devnet to share\n"));
+        assert!(saved.contains("vault = \"Vault222\" # keep in sync with declare_id!\n"));
+        let loaded = ChioConfig::load(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(loaded.programs["localnet"]["escrow"], "Escrow111");
+    }
+
+    #[test]
+    fn missing_sections_fall_back_to_defaults() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        assert!(ChioConfig::load(temp_dir.path()).unwrap().is_none());
+
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE),
+            "[program]\nname = \"p\"\ntest_framework = \"mollusk\"\nkeypair = \"k.json\"\n",
+        )
+        .unwrap();
+        let config = ChioConfig::load(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(config.scripts.test, "cargo test");
+        assert!(config.provider.cluster.is_none());
+
+        fs::write(temp_dir.path().join(CONFIG_FILE), "[program]\nname = 1\n").unwrap();
+        assert!(ChioConfig::load(temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn script_command_splits_program_and_args() {
+        let (program, args) = config::script_command("cargo test --features std").unwrap();
+        assert_eq!(program, "cargo");
+        assert_eq!(args, vec!["test", "--features", "std"]);
+        assert!(config::script_command("  ").is_err());
+    }
+
+    #[test]
+    fn project_takes_test_framework_from_config() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            templates::cargo_toml_mollusk("p"),
+        )
+        .unwrap();
+        ChioConfig::new("p", TestFramework::Litesvm, PROGRAM_ID)
+            .save(temp_dir.path())
+            .unwrap();
+
+        let project = Project::open(temp_dir.path()).unwrap();
+        assert_eq!(project.test_framework, TestFramework::Litesvm);
+        assert!(project.config.is_some());
+    }
+
+    #[test]
+    fn deploy_options_fall_back_to_config() {
+        let mut config = ChioConfig::new("my_program", TestFramework::Mollusk, PROGRAM_ID);
+        config.provider.wallet = Some(PathBuf::from("wallet.json"));
+
+        let options = DeployOptions::default().or_config(&config, "my_program");
+        assert_eq!(options.cluster, Some(Cluster::Localnet));
+        assert_eq!(options.keypair, Some(PathBuf::from("wallet.json")));
+        assert_eq!(
+            options.program_keypair.as_deref(),
+            Some(Path::new("target/deploy/my_program-keypair.json"))
+        );
+
+        let explicit = DeployOptions {
+            cluster: Some(Cluster::Devnet),
+            ..DeployOptions::default()
+        };
+        let options = explicit.or_config(&config, "other_program");
+        assert_eq!(options.cluster, Some(Cluster::Devnet));
+        assert!(options.program_keypair.is_none());
+    }
+
+    #[test]
+    fn build_options_fall_back_to_config() {
+        let mut config = ChioConfig::new("my_program", TestFramework::Mollusk, PROGRAM_ID);
+        assert!(!config.to_toml().unwrap().contains("[build]"));
+        config.build = BuildOptions {
+            features: vec!["metrics".to_string()],
+            arch: Some(SbfArch::Sbfv2),
+            debug: true,
+            max_size: Some(200_000),
+            args: vec!["--locked".to_string()],
+            ..BuildOptions::default()
+        };
+        let toml = config.to_toml().unwrap();
+        assert!(toml.contains("[build]"));
+        assert!(toml.contains("arch = \"sbfv2\""));
+        assert_eq!(toml::from_str::<ChioConfig>(&toml).unwrap(), config);
+
+        let options = BuildOptions::default().or_config(&config);
+        assert_eq!(options.features, vec!["metrics"]);
+        assert_eq!(options.max_size, Some(200_000));
+        assert_eq!(options.deploy_dir(), Path::new("target/deploy"));
+        assert_eq!(
+            options.args(),
+            vec![
+                "--features",
+                "metrics",
+                "--arch",
+                "sbfv2",
+                "--debug",
+                "--",
+                "--locked"
+            ]
+        );
+
+        let explicit = BuildOptions {
+            features: vec!["a".to_string(), "b".to_string()],
+            sbf_out_dir: Some(PathBuf::from("dist")),
+            release: true,
+            ..BuildOptions::default()
+        };
+        let options = explicit.or_config(&config);
+        assert!(!options.debug);
+        assert_eq!(options.deploy_dir(), Path::new("dist"));
+        assert_eq!(
+            options.args(),
+            vec![
+                "--features",
+                "a,b",
+                "--arch",
+                "sbfv2",
+                "--sbf-out-dir",
+                "dist",
+                "--",
+                "--locked"
+            ]
+        );
+    }
+
+    #[test]
+    fn workspace_config_tracks_members() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let mut config = ChioConfig::new_workspace();
+        config
+            .add_member(
+                ProgramConfig::new("vault", TestFramework::Mollusk),
+                PROGRAM_ID,
+            )
+            .unwrap();
+        config
+            .add_member(
+                ProgramConfig::new("escrow", TestFramework::Litesvm),
+                "Escrow111",
+            )
+            .unwrap();
+        assert!(config
+            .add_member(
+                ProgramConfig::new("vault", TestFramework::Litesvm),
+                PROGRAM_ID
+            )
+            .is_err());
+        config.save(temp_dir.path()).unwrap();
+
+        let loaded = ChioConfig::load(temp_dir.path()).unwrap().unwrap();
+        assert!(loaded.is_workspace());
+        assert!(loaded.program.is_none());
+        let names: Vec<&str> = loaded
+            .program_configs()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["vault", "escrow"]);
+        assert_eq!(
+            loaded.program_config("escrow").unwrap().test_framework,
+            TestFramework::Litesvm
+        );
+        assert_eq!(loaded.programs["localnet"]["escrow"], "Escrow111");
+
+        let single = ChioConfig::new("p", TestFramework::Mollusk, PROGRAM_ID);
+        assert!(single
+            .clone()
+            .add_member(ProgramConfig::new("q", TestFramework::Mollusk), PROGRAM_ID)
+            .is_err());
+    }
+
+    #[test]
+    fn workspace_program_reads_workspace_config() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let program_dir = temp_dir.path().join("programs/escrow");
+        fs::create_dir_all(&program_dir).unwrap();
+        fs::write(
+            program_dir.join("Cargo.toml"),
+            templates::cargo_toml_mollusk("escrow"),
+        )
+        .unwrap();
+
+        let mut config = ChioConfig::new_workspace();
+        config
+            .add_member(
+                ProgramConfig::new("escrow", TestFramework::Litesvm),
+                PROGRAM_ID,
+            )
+            .unwrap();
+        config.save(temp_dir.path()).unwrap();
+
+        let project = Project::open_member(temp_dir.path(), "escrow").unwrap();
+        assert_eq!(project.test_framework, TestFramework::Litesvm);
+        assert_eq!(project.base_dir(), temp_dir.path());
+        assert!(Project::open_member(temp_dir.path(), "vault").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Tests for deploy pre-flight checks
+//!
+//! Builds a fake `target/deploy` next to a scaffolded `src/lib.rs` and
+//! checks that program id mismatches are caught before deploying.
+
+mod common;
+
+#[cfg(test)]
+mod deploy {
+    use crate::common::{generator, scaffold};
+    use chio::add::Project;
+    use chio::deploy::{self, Cluster, DeployOptions};
+    use chio::keypair::Keypair;
+    use chio::runner::{CommandOutput, RecordingRunner};
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn project_with_program(declared: Option<&str>) -> (TempDir, Project, String) {
+        let keypair = Keypair::generate().unwrap();
+        let address = keypair.address();
+        let (temp_dir, project) =
+            scaffold(generator("test_project").with_program_id(declared.unwrap_or(&address)));
+
+        let deploy_dir = temp_dir.path().join("target/deploy");
+        fs::create_dir_all(&deploy_dir).unwrap();
+        keypair
+            .write(&deploy_dir.join("test_project-keypair.json"))
+            .unwrap();
+        fs::write(deploy_dir.join("test_project.so"), b"\x7fELF").unwrap();
+        (temp_dir, project, address)
+    }
+
+    #[test]
+    fn keypair_for_so_sits_next_to_binary() {
+        assert_eq!(
+            deploy::keypair_for_so(Path::new("target/deploy/my_program.so")).unwrap(),
+            Path::new("target/deploy/my_program-keypair.json")
+        );
+    }
+
+    #[test]
+    fn matching_program_id_passes() {
+        let (temp_dir, project, address) = project_with_program(None);
+        let so_path = temp_dir.path().join("target/deploy/test_project.so");
+
+        let check = deploy::check_program_id(&project, &so_path, None).unwrap();
+        assert!(check.is_match());
+        assert_eq!(check.address, address);
+    }
+
+    #[test]
+    fn mismatched_program_id_is_reported() {
+        let stale = "11111111111111111111111111111112";
+        let (temp_dir, project, address) = project_with_program(Some(stale));
+        let so_path = temp_dir.path().join("target/deploy/test_project.so");
+
+        let check = deploy::check_program_id(&project, &so_path, None).unwrap();
+        assert!(!check.is_match());
+        assert_eq!(check.address, address);
+        assert_eq!(check.declared, stale);
+    }
+
+    #[test]
+    fn missing_keypair_is_an_error() {
+        let (temp_dir, project, _) = project_with_program(None);
+        let so_path = temp_dir.path().join("target/deploy/other.so");
+        fs::write(&so_path, b"\x7fELF").unwrap();
+
+        assert!(deploy::check_program_id(&project, &so_path, None).is_err());
+    }
+
+    #[test]
+    fn program_keypair_override_is_checked_instead() {
+        let (temp_dir, project, _) = project_with_program(None);
+        let so_path = temp_dir.path().join("target/deploy/test_project.so");
+        let other = temp_dir.path().join("other-keypair.json");
+        Keypair::generate().unwrap().write(&other).unwrap();
+
+        let check = deploy::check_program_id(&project, &so_path, Some(&other)).unwrap();
+        assert_eq!(check.keypair_path, other);
+        assert!(!check.is_match());
+    }
+
+    #[test]
+    fn cluster_parses_monikers_and_urls() {
+        assert_eq!("devnet".parse::<Cluster>().unwrap(), Cluster::Devnet);
+        assert_eq!("mainnet-beta".parse::<Cluster>().unwrap(), Cluster::Mainnet);
+        assert_eq!(
+            "http://localhost:8899".parse::<Cluster>().unwrap(),
+            Cluster::Custom("http://localhost:8899".to_string())
+        );
+        assert!("moon".parse::<Cluster>().is_err());
+
+        assert_eq!(Cluster::Devnet.url(), "https://api.devnet.solana.com");
+        assert!(Cluster::Mainnet.is_mainnet());
+        assert!(Cluster::Custom("https://my-mainnet.rpc".to_string()).is_mainnet());
+        assert!(!Cluster::Testnet.is_mainnet());
+    }
+
+    #[test]
+    fn cluster_falls_back_to_the_solana_config() {
+        let configured = DeployOptions {
+            cluster: Some(Cluster::Devnet),
+            ..DeployOptions::default()
+        };
+        let runner = RecordingRunner::new();
+        assert_eq!(
+            deploy::resolve_cluster(&runner, &configured).unwrap(),
+            Cluster::Devnet
+        );
+        assert!(runner.commands().is_empty());
+
+        let runner = RecordingRunner::new().with_output(
+            "solana config get",
+            CommandOutput::success("Config File: config.yml\nRPC URL: https://api.mainnet-beta.solana.com \nKeypair Path: id.json\n"),
+        );
+        assert_eq!(
+            deploy::resolve_cluster(&runner, &DeployOptions::default()).unwrap(),
+            Cluster::Mainnet
+        );
+
+        let runner = RecordingRunner::new().with_missing("solana");
+        let error = deploy::resolve_cluster(&runner, &DeployOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("Pass --cluster"));
+
+        assert_eq!(
+            Cluster::from_url("http://localhost:8899"),
+            Cluster::Localnet
+        );
+        assert_eq!(
+            Cluster::from_url("https://rpc.example.com/"),
+            Cluster::Custom("https://rpc.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn custom_clusters_are_checked_by_genesis_hash() {
+        let custom = Cluster::Custom("https://rpc.example.com".to_string());
+        let mainnet = RecordingRunner::new().with_output(
+            "solana genesis-hash",
+            CommandOutput::success(&format!("{}\n", deploy::MAINNET_GENESIS_HASH)),
+        );
+        assert!(deploy::targets_mainnet(&mainnet, &custom));
+        assert_eq!(
+            mainnet.command_lines(),
+            vec!["solana genesis-hash --url https://rpc.example.com"]
+        );
+
+        let devnet = RecordingRunner::new().with_output(
+            "solana genesis-hash",
+            CommandOutput::success("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG\n"),
+        );
+        assert!(!deploy::targets_mainnet(&devnet, &custom));
+        assert!(!deploy::targets_mainnet(&devnet, &Cluster::Devnet));
+
+        let unreachable = RecordingRunner::new()
+            .with_output("solana genesis-hash", CommandOutput::failure(1, ""));
+        assert!(deploy::targets_mainnet(&unreachable, &custom));
+    }
+
+    #[test]
+    fn deploy_args_default_to_program_keypair_only() {
+        let args = deploy::deploy_args(
+            Path::new("target/deploy/p.so"),
+            Path::new("target/deploy/p-keypair.json"),
+            &DeployOptions::default(),
+        );
+
+        let expected: Vec<OsString> = [
+            "program",
+            "deploy",
+            "target/deploy/p.so",
+            "--program-id",
+            "target/deploy/p-keypair.json",
+        ]
+        .iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn deploy_args_pass_every_option_through() {
+        let options = DeployOptions {
+            cluster: Some(Cluster::Testnet),
+            keypair: Some(PathBuf::from("payer.json")),
+            program_keypair: None,
+            upgrade_authority: Some(PathBuf::from("authority.json")),
+            is_final: true,
+            max_len: Some(200_000),
+            yes: false,
+        };
+        let args: Vec<String> =
+            deploy::deploy_args(Path::new("p.so"), Path::new("p-keypair.json"), &options)
+                .into_iter()
+                .map(|arg| arg.into_string().unwrap())
+                .collect();
+
+        assert_eq!(
+            args[5..],
+            [
+                "--url",
+                "https://api.testnet.solana.com",
+                "--keypair",
+                "payer.json",
+                "--upgrade-authority",
+                "authority.json",
+                "--final",
+                "--max-len",
+                "200000",
+            ]
+        );
+    }
+
+    fn deploy_dir_with(programs: &[&str]) -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        for program in programs {
+            fs::write(temp_dir.path().join(format!("{}.so", program)), b"\x7fELF").unwrap();
+            fs::write(
+                temp_dir.path().join(format!("{}-keypair.json", program)),
+                "[]",
+            )
+            .unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn select_program_matches_package_name() {
+        let dir = deploy_dir_with(&["aaa_old_name", "my_program", "zzz"]);
+
+        let selected = deploy::select_program(dir.path(), "my-program", None).unwrap();
+        assert_eq!(selected, dir.path().join("my_program.so"));
+    }
+
+    #[test]
+    fn select_program_honours_explicit_choice() {
+        let dir = deploy_dir_with(&["my_program", "other"]);
+
+        let selected = deploy::select_program(dir.path(), "my_program", Some("other")).unwrap();
+        assert_eq!(selected, dir.path().join("other.so"));
+    }
+
+    #[test]
+    fn select_program_lists_candidates_when_undecided() {
+        let dir = deploy_dir_with(&["beta", "alpha"]);
+
+        let err = deploy::select_program(dir.path(), "my_program", None).unwrap_err();
+        assert!(err.to_string().contains("Available programs: alpha, beta"));
+        assert!(err.to_string().contains("--program"));
+
+        let empty = deploy_dir_with(&[]);
+        assert!(deploy::select_program(empty.path(), "my_program", None).is_err());
+    }
+}
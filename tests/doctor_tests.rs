@@ -0,0 +1,243 @@
+//! Tests for `chio doctor`
+//!
+//! Covers tool detection and versions, platform tools, the wallet and the
+//! dependency versions of a project, with a recording runner standing in for
+//! the toolchain.
+
+#[cfg(test)]
+mod doctor {
+    use chio::config::ChioConfig;
+    use chio::doctor::{diagnose, Severity, Version};
+    use chio::generator::ProjectGenerator;
+    use chio::keypair::Keypair;
+    use chio::runner::{CommandOutput, RecordingRunner};
+    use chio::TestFramework;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// A toolchain with every tool installed and up to date.
+    fn toolchain(home: &Path) -> RecordingRunner {
+        let wallet = home.join("id.json");
+        Keypair::generate().unwrap().write(&wallet).unwrap();
+        fs::create_dir_all(home.join(".cache/solana/v1.43/platform-tools")).unwrap();
+
+        RecordingRunner::new()
+            .with_output("cargo --version", CommandOutput::success("cargo 1.84.0 (66221abde 2024-11-19)\n"))
+            .with_output("solana --version", CommandOutput::success("solana-cli 2.1.7 (src:00000000; feat:1416569292, client:Agave)\n"))
+            .with_output("solana-keygen --version", CommandOutput::success("solana-keygen 2.1.7\n"))
+            .with_output("git --version", CommandOutput::success("git version 2.43.0\n"))
+            .with_output(
+                "cargo build-sbf --version",
+                CommandOutput::success("solana-cargo-build-sbf 2.1.7\nplatform-tools v1.43\nrustc 1.79.0\n"),
+            )
+            .with_output(
+                "solana config get",
+                CommandOutput::success(&format!(
+                    "Config File: {0}/config.yml\nRPC URL: https://api.devnet.solana.com\nKeypair Path: {1}\n",
+                    home.display(),
+                    wallet.display()
+                )),
+            )
+    }
+
+    #[test]
+    fn parses_versions_from_tool_output_and_requirements() {
+        assert_eq!(
+            Version::find("solana-cli 2.1.7 (src:00000000)"),
+            Some(Version(2, 1, 7))
+        );
+        assert_eq!(Version::find("^0.9"), Some(Version(0, 9, 0)));
+        assert_eq!(Version::find("git version 2.43.0"), Some(Version(2, 43, 0)));
+        assert_eq!(Version::find("no version"), None);
+
+        assert!(Version(0, 9, 2).is_compatible(&Version(0, 9, 0)));
+        assert!(!Version(0, 8, 0).is_compatible(&Version(0, 9, 2)));
+        assert!(Version(2, 3, 0).is_compatible(&Version(2, 0, 0)));
+    }
+
+    #[test]
+    fn healthy_toolchain_has_no_problems() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+
+        let report = diagnose(&toolchain(home.path()), project.path(), Some(home.path())).unwrap();
+        assert_eq!(report.errors(), 0, "{:?}", report);
+        assert_eq!(report.warnings(), 0, "{:?}", report);
+        assert!(report
+            .check("platform-tools")
+            .unwrap()
+            .detail
+            .starts_with("v1.43"));
+        assert!(report
+            .check("wallet")
+            .unwrap()
+            .detail
+            .contains("solana config"));
+    }
+
+    #[test]
+    fn missing_solana_cli_is_blocking() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let runner = toolchain(home.path())
+            .with_missing("solana --version")
+            .with_missing("solana-keygen --version")
+            .with_missing("solana config get")
+            .with_output(
+                "cargo build-sbf --version",
+                CommandOutput::failure(101, "no such command: `build-sbf`"),
+            );
+
+        let report = diagnose(&runner, project.path(), Some(home.path())).unwrap();
+        let solana = report.check("solana").unwrap();
+        assert_eq!(solana.severity, Severity::Error);
+        assert!(solana
+            .fix
+            .as_deref()
+            .unwrap()
+            .contains("Install the Solana CLI"));
+        assert_eq!(
+            report.check("solana-keygen").unwrap().severity,
+            Severity::Warning
+        );
+        assert_eq!(
+            report.check("cargo build-sbf").unwrap().severity,
+            Severity::Error
+        );
+        assert_eq!(report.check("wallet").unwrap().severity, Severity::Warning);
+        assert_eq!(report.errors(), 2);
+    }
+
+    #[test]
+    fn old_solana_cli_is_blocking() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let runner = toolchain(home.path()).with_output(
+            "solana --version",
+            CommandOutput::success("solana-cli 1.18.26 (src:00000000)\n"),
+        );
+
+        let report = diagnose(&runner, project.path(), Some(home.path())).unwrap();
+        let solana = report.check("solana").unwrap();
+        assert_eq!(solana.severity, Severity::Error);
+        assert!(solana.detail.contains("older than 2.1.0"));
+        assert!(solana
+            .fix
+            .as_deref()
+            .unwrap()
+            .contains("agave-install update"));
+    }
+
+    #[test]
+    fn missing_platform_tools_and_wallet_are_warnings() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let runner = toolchain(home.path())
+            .with_output(
+                "cargo build-sbf --version",
+                CommandOutput::success("solana-cargo-build-sbf 2.1.7\nplatform-tools v1.44\n"),
+            )
+            .with_output(
+                "solana config get",
+                CommandOutput::success("Keypair Path: /nonexistent/id.json\n"),
+            );
+
+        let report = diagnose(&runner, project.path(), Some(home.path())).unwrap();
+        let platform_tools = report.check("platform-tools").unwrap();
+        assert_eq!(platform_tools.severity, Severity::Warning);
+        assert!(platform_tools
+            .fix
+            .as_deref()
+            .unwrap()
+            .contains("--force-tools-install"));
+        let wallet = report.check("wallet").unwrap();
+        assert_eq!(wallet.severity, Severity::Warning);
+        assert!(wallet
+            .fix
+            .as_deref()
+            .unwrap()
+            .contains("solana-keygen new -o /nonexistent/id.json"));
+        assert_eq!(report.errors(), 0);
+    }
+
+    #[test]
+    fn project_wallet_takes_precedence_and_must_be_valid() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join("payer.json"), "[1, 2, 3]").unwrap();
+        let mut config = ChioConfig::new_workspace();
+        config.provider.wallet = Some("payer.json".into());
+        config.save(project.path()).unwrap();
+
+        let report = diagnose(&toolchain(home.path()), project.path(), Some(home.path())).unwrap();
+        let wallet = report.check("wallet").unwrap();
+        assert_eq!(wallet.severity, Severity::Error);
+        assert!(wallet.detail.contains("Keypair must be 64 bytes"));
+        assert!(wallet.fix.as_deref().unwrap().contains("Chio.toml"));
+    }
+
+    #[test]
+    fn reports_pinned_dependency_versions() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        ProjectGenerator::new("vault")
+            .with_test_framework(TestFramework::Litesvm)
+            .with_output_dir(project.path())
+            .generate()
+            .unwrap()
+            .write()
+            .unwrap();
+        let manifest_path = project.path().join("Cargo.toml");
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+        fs::write(
+            &manifest_path,
+            manifest.replace("pinocchio = \"0.9.2\"", "pinocchio = \"0.8.4\""),
+        )
+        .unwrap();
+
+        let report = diagnose(&toolchain(home.path()), project.path(), Some(home.path())).unwrap();
+        let pinocchio = report.check("vault pinocchio").unwrap();
+        assert_eq!(pinocchio.severity, Severity::Warning);
+        assert_eq!(
+            pinocchio.detail,
+            "0.8.4 pinned, chio generates code for 0.9.2"
+        );
+        let litesvm = report.check("vault litesvm").unwrap();
+        assert_eq!(litesvm.severity, Severity::Ok);
+        assert!(report.check("vault mollusk-svm").is_none());
+    }
+
+    #[test]
+    fn broken_manifests_are_reported_without_stopping_the_report() {
+        let home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        fs::write(
+            project.path().join("Chio.toml"),
+            "[program\nname = \"vault\"\n",
+        )
+        .unwrap();
+
+        let runner = toolchain(home.path());
+        let report = diagnose(&runner, project.path(), Some(home.path())).unwrap();
+        let config = report.check("Chio.toml").unwrap();
+        assert_eq!(config.severity, Severity::Error);
+        assert!(config.detail.contains("Invalid"));
+        assert!(config.fix.as_deref().unwrap().contains("Chio.toml"));
+        assert_eq!(report.check("wallet").unwrap().severity, Severity::Ok);
+        assert_eq!(report.check("cargo").unwrap().severity, Severity::Ok);
+        assert_eq!(report.errors(), 1);
+
+        fs::remove_file(project.path().join("Chio.toml")).unwrap();
+        ChioConfig::new("vault", TestFramework::Mollusk, "Vault111")
+            .save(project.path())
+            .unwrap();
+        fs::write(project.path().join("Cargo.toml"), "[dependencies\n").unwrap();
+        let report = diagnose(&runner, project.path(), Some(home.path())).unwrap();
+        let vault = report.check("vault").unwrap();
+        assert_eq!(vault.severity, Severity::Error);
+        assert!(vault.detail.starts_with("Invalid Cargo.toml"));
+        assert_eq!(vault.fix.as_deref(), Some("Fix the error in Cargo.toml"));
+        assert!(report.check("Chio.toml").is_none());
+    }
+}
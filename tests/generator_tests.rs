@@ -0,0 +1,451 @@
+//! Tests for the library project generator
+//!
+//! Covers the files generated for programs, workspace members and
+//! workspaces, both in memory and written to disk.
+
+#[cfg(test)]
+mod generator {
+    use chio::config::ChioConfig;
+    use chio::generator::{ConflictMode, FileAction, ProjectGenerator};
+    use chio::keypair::Keypair;
+    use chio::template_set::{TemplateSet, MANIFEST_FILE};
+    use chio::TestFramework;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    #[test]
+    fn generates_a_program_in_memory() {
+        let project = ProjectGenerator::new("vault")
+            .with_test_framework(TestFramework::Litesvm)
+            .generate()
+            .unwrap();
+        let program_id = project.program_id.clone().unwrap();
+
+        assert_eq!(project.files.root, PathBuf::from("vault"));
+        assert!(project
+            .files
+            .get("src/lib.rs")
+            .unwrap()
+            .contains(&program_id));
+        assert!(project.files.get("tests/initialize.rs").is_some());
+        assert!(project.files.get("tests/tests.rs").is_none());
+        assert!(!Path::new("vault").exists());
+
+        let keypair_path = Path::new("target/deploy/vault-keypair.json");
+        let keypair: Vec<u8> =
+            serde_json::from_str(project.files.get(keypair_path).unwrap()).unwrap();
+        assert_eq!(Keypair::from_bytes(&keypair).unwrap().address(), program_id);
+        assert!(project.files.private.contains(keypair_path));
+
+        let config: ChioConfig = toml::from_str(project.files.get("Chio.toml").unwrap()).unwrap();
+        assert_eq!(
+            config.program_config("vault").unwrap().test_framework,
+            TestFramework::Litesvm
+        );
+        assert_eq!(config.programs["localnet"]["vault"], program_id);
+    }
+
+    #[test]
+    fn uses_the_given_keys() {
+        let keypair = Keypair::generate().unwrap();
+        let project = ProjectGenerator::new("vault")
+            .with_program_keypair(keypair.clone())
+            .generate()
+            .unwrap();
+        assert_eq!(project.program_id, Some(keypair.address()));
+
+        let project = ProjectGenerator::new("vault")
+            .with_program_id("Prog111")
+            .with_payer("Payer111")
+            .generate()
+            .unwrap();
+        assert_eq!(project.program_id.as_deref(), Some("Prog111"));
+        assert!(project
+            .files
+            .get("tests/tests.rs")
+            .unwrap()
+            .contains("Payer111"));
+        assert!(project.files.get("src/lib.rs").unwrap().contains("Prog111"));
+        assert!(project.files.private.is_empty());
+    }
+
+    #[test]
+    fn writes_the_project_to_the_output_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("out");
+        let project = ProjectGenerator::new("vault")
+            .with_output_dir(&output_dir)
+            .generate()
+            .unwrap();
+
+        assert_eq!(project.write().unwrap(), None);
+        for path in project.files.paths() {
+            assert_eq!(
+                fs::read_to_string(output_dir.join(path)).unwrap(),
+                project.files.get(path).unwrap()
+            );
+        }
+        assert!(!output_dir.join(".git").exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let keypair = output_dir.join("target/deploy/vault-keypair.json");
+            assert_eq!(
+                fs::metadata(keypair).unwrap().permissions().mode() & 0o777,
+                0o600
+            );
+        }
+    }
+
+    #[test]
+    fn workspace_members_go_under_programs() {
+        let project = ProjectGenerator::new("escrow")
+            .with_output_dir(".")
+            .with_feature("workspace", true)
+            .generate()
+            .unwrap();
+        let paths: Vec<&Path> = project.files.paths().collect();
+
+        assert!(paths.contains(&Path::new("programs/escrow/src/lib.rs")));
+        assert!(paths.contains(&Path::new("target/deploy/escrow-keypair.json")));
+        assert!(!paths.contains(&Path::new("Chio.toml")));
+        assert!(!paths.contains(&Path::new("programs/escrow/README.md")));
+        assert!(project
+            .files
+            .get("programs/escrow/Cargo.toml")
+            .unwrap()
+            .contains("test-helpers"));
+    }
+
+    #[test]
+    fn generates_an_empty_workspace() {
+        let project = ProjectGenerator::workspace("defi").generate().unwrap();
+
+        assert_eq!(project.program_id, None);
+        assert!(project.files.directories.contains(Path::new("programs")));
+        assert!(project.files.get("test-helpers/src/lib.rs").is_some());
+        let config: ChioConfig = toml::from_str(project.files.get("Chio.toml").unwrap()).unwrap();
+        assert!(config.is_workspace());
+        assert!(config.program_configs().is_empty());
+    }
+
+    #[test]
+    fn renders_custom_templates_with_variables() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(MANIFEST_FILE),
+            "name = \"mini\"\n[variables]\nauthor = \"someone\"\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "// {{ author }}: {{ program_id }}",
+        )
+        .unwrap();
+        let set = TemplateSet::load(temp_dir.path()).unwrap();
+
+        let project = ProjectGenerator::new("vault")
+            .with_program_id("Prog111")
+            .with_template(set.clone())
+            .with_variables(BTreeMap::from([("author".to_string(), "Ann".to_string())]))
+            .generate()
+            .unwrap();
+        assert_eq!(project.files.get("lib.rs"), Some("// Ann: Prog111"));
+
+        let error = ProjectGenerator::new("vault")
+            .with_template(set)
+            .with_variables(BTreeMap::from([("autor".to_string(), "Ann".to_string())]))
+            .generate()
+            .unwrap_err();
+        assert!(error.to_string().contains("no variable 'autor'"));
+    }
+
+    #[test]
+    fn plans_directories_files_and_commands() {
+        let project = ProjectGenerator::new("vault")
+            .with_git(true)
+            .generate()
+            .unwrap();
+        let plan = project.plan();
+
+        assert_eq!(plan.root, PathBuf::from("vault"));
+        assert!(plan
+            .directories
+            .contains(&PathBuf::from("src/instructions")));
+        assert!(plan.directories.contains(&PathBuf::from("target/deploy")));
+        let keypair = plan
+            .files
+            .iter()
+            .find(|file| file.path == Path::new("target/deploy/vault-keypair.json"))
+            .unwrap();
+        assert!(keypair.private);
+
+        let commands: Vec<String> = plan
+            .commands
+            .iter()
+            .map(|command| command.to_string())
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                "git init",
+                "git add .",
+                "git commit -m \"Initial commit: Setup Pinocchio project 'vault'\"",
+            ]
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&plan.to_json().unwrap()).unwrap();
+        assert_eq!(json["files"].as_array().unwrap().len(), plan.files.len());
+        assert_eq!(json["commands"][0]["program"], "git");
+        assert!(!plan.to_json().unwrap().contains("declare_id!"));
+    }
+
+    #[test]
+    fn draws_the_plan_as_a_tree() {
+        let tree = ProjectGenerator::workspace("defi")
+            .generate()
+            .unwrap()
+            .plan()
+            .to_tree();
+
+        assert_eq!(
+            tree,
+            "defi/\n\
+             ├── .gitignore\n\
+             ├── Cargo.toml\n\
+             ├── Chio.toml\n\
+             ├── README.md\n\
+             ├── programs/\n\
+             └── test-helpers/\n\
+             \u{20}   ├── Cargo.toml\n\
+             \u{20}   └── src/\n\
+             \u{20}       └── lib.rs\n"
+        );
+    }
+
+    #[test]
+    fn hyphenated_names_use_the_crate_name_in_code() {
+        let project = ProjectGenerator::new("my-vault").generate().unwrap();
+
+        assert!(project
+            .files
+            .get("Cargo.toml")
+            .unwrap()
+            .contains("name = \"my-vault\""));
+        assert!(project
+            .files
+            .get("tests/tests.rs")
+            .unwrap()
+            .contains("use my_vault::"));
+        assert!(project
+            .files
+            .get("target/deploy/my_vault-keypair.json")
+            .is_some());
+
+        let config: ChioConfig = toml::from_str(project.files.get("Chio.toml").unwrap()).unwrap();
+        let program = config.program_config("my-vault").unwrap();
+        assert_eq!(
+            program.keypair,
+            PathBuf::from("target/deploy/my_vault-keypair.json")
+        );
+    }
+
+    /// A directory with a Cargo.toml, .gitignore and README of its own.
+    fn existing_crate(dir: &Path) {
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"vault\"\nversion = \"0.2.0\" # keep\n",
+        )
+        .unwrap();
+        fs::write(dir.join(".gitignore"), ".env\n").unwrap();
+        fs::write(dir.join("README.md"), "# Vault\n").unwrap();
+    }
+
+    fn generate_into(dir: &Path, conflicts: ConflictMode) -> chio::generator::GeneratedProject {
+        ProjectGenerator::new("vault")
+            .with_output_dir(dir)
+            .with_conflict_mode(conflicts)
+            .generate()
+            .unwrap()
+    }
+
+    #[test]
+    fn refuses_to_overwrite_existing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        existing_crate(temp_dir.path());
+
+        let project = generate_into(temp_dir.path(), ConflictMode::Abort);
+        let error = project.write().unwrap_err().to_string();
+        assert!(
+            error.contains("  .gitignore\n  Cargo.toml\n  README.md\n"),
+            "{}",
+            error
+        );
+        assert!(error.contains("--skip-existing"));
+        assert!(!temp_dir.path().join("src").exists());
+
+        // Files that already have the generated contents are not conflicts.
+        fs::remove_dir_all(temp_dir.path()).unwrap();
+        project.files.write().unwrap();
+        let report = project.write_files().unwrap();
+        assert!(report
+            .files
+            .iter()
+            .all(|(_, action)| *action == FileAction::Unchanged));
+    }
+
+    #[test]
+    fn force_and_skip_existing_resolve_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        existing_crate(temp_dir.path());
+        let report = generate_into(temp_dir.path(), ConflictMode::SkipExisting)
+            .write_files()
+            .unwrap();
+        assert_eq!(report.action("README.md"), Some(FileAction::Skipped));
+        assert_eq!(report.action("src/lib.rs"), Some(FileAction::Created));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("README.md")).unwrap(),
+            "# Vault\n"
+        );
+
+        let project = generate_into(temp_dir.path(), ConflictMode::Force);
+        let report = project.write_files().unwrap();
+        assert_eq!(report.action("README.md"), Some(FileAction::Overwritten));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("README.md")).unwrap(),
+            project.files.get("README.md").unwrap()
+        );
+    }
+
+    #[test]
+    fn merge_keeps_existing_values_and_adds_the_rest() {
+        let temp_dir = TempDir::new().unwrap();
+        existing_crate(temp_dir.path());
+        let report = generate_into(temp_dir.path(), ConflictMode::Merge)
+            .write_files()
+            .unwrap();
+
+        assert_eq!(report.action("Cargo.toml"), Some(FileAction::Merged));
+        assert_eq!(report.action(".gitignore"), Some(FileAction::Merged));
+        assert_eq!(report.action("README.md"), Some(FileAction::Skipped));
+        let manifest = fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("version = \"0.2.0\" # keep"));
+        assert!(manifest.contains("pinocchio = "));
+        let gitignore = fs::read_to_string(temp_dir.path().join(".gitignore")).unwrap();
+        assert!(gitignore.starts_with(".env\n"));
+        assert!(gitignore.contains("target"));
+    }
+
+    /// The entries of `dir`, recursively, with their contents.
+    fn snapshot(dir: &Path) -> BTreeMap<PathBuf, String> {
+        let mut entries = BTreeMap::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                entries.insert(path.clone(), String::new());
+                entries.extend(snapshot(&path));
+            } else {
+                entries.insert(path.clone(), fs::read_to_string(&path).unwrap());
+            }
+        }
+        entries
+    }
+
+    #[test]
+    fn failed_write_rolls_back_the_existing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("vault");
+        fs::create_dir(&root).unwrap();
+        existing_crate(&root);
+        // A file where the generator needs the src directory.
+        fs::write(root.join("src"), "").unwrap();
+        let before = snapshot(temp_dir.path());
+
+        let error = generate_into(&root, ConflictMode::Force)
+            .write_files()
+            .unwrap_err();
+        let message = error.to_string();
+        assert!(
+            message.starts_with("Failed to write src/entrypoint.rs"),
+            "{}",
+            message
+        );
+        assert!(message.ends_with("was left as it was."));
+        assert_eq!(snapshot(temp_dir.path()), before);
+    }
+
+    #[test]
+    fn failed_merge_changes_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        existing_crate(temp_dir.path());
+        fs::write(temp_dir.path().join("Cargo.toml"), "[package\n").unwrap();
+        let before = snapshot(temp_dir.path());
+
+        let error = generate_into(temp_dir.path(), ConflictMode::Merge)
+            .write_files()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Failed to merge Cargo.toml");
+        assert_eq!(snapshot(temp_dir.path()), before);
+    }
+
+    #[test]
+    fn existing_roots_are_staged_inside_the_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("vault");
+        fs::create_dir(&root).unwrap();
+        existing_crate(&root);
+        // Entries where staging next to the root would go, so using the
+        // parent fails.
+        let id = std::process::id();
+        fs::write(
+            temp_dir.path().join(format!(".vault.chio-staging-{}", id)),
+            "",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join(format!(".vault.chio-backup-{}", id)),
+            "",
+        )
+        .unwrap();
+
+        let report = generate_into(&root, ConflictMode::Force)
+            .write_files()
+            .unwrap();
+        assert_eq!(report.action("src/lib.rs"), Some(FileAction::Created));
+        assert!(!fs::read_dir(&root).unwrap().any(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with(".chio-")));
+    }
+
+    #[test]
+    fn new_projects_are_moved_into_place_whole() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("vault");
+        let report = generate_into(&root, ConflictMode::Abort)
+            .write_files()
+            .unwrap();
+
+        assert!(report
+            .files
+            .iter()
+            .all(|(_, action)| *action == FileAction::Created));
+        let entries: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["vault"]);
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        let error = ProjectGenerator::new("my vault").generate().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Invalid project name 'my vault'"));
+    }
+}
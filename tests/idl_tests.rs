@@ -0,0 +1,178 @@
+//! Tests for Shank IDL generation
+//!
+//! These scaffold a project from the templates and check the interface
+//! `chio idl build` reads from its source.
+
+mod common;
+
+#[cfg(test)]
+mod idl {
+    use crate::common::{generator, project, scaffold, PROGRAM_ID};
+    use chio::add::{self, FieldType, StateField};
+    use chio::idl::{self, Idl, IdlAccountMeta, PdaSeed};
+    use std::fs;
+
+    #[test]
+    fn reads_the_scaffolded_program() {
+        let (_temp_dir, project) = project();
+        let idl = Idl::parse(&project).unwrap();
+
+        assert_eq!(idl.name, "vault");
+        assert_eq!(idl.address, PROGRAM_ID);
+        assert_eq!(idl.instructions.len(), 1);
+
+        let initialize = &idl.instructions[0];
+        assert_eq!(initialize.name, "InitializeState");
+        assert_eq!(initialize.discriminator, 0);
+        let names: Vec<&str> = initialize
+            .accounts
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(names, ["payer", "state", "rent", "system_program"]);
+        assert!(initialize.accounts[0].writable && initialize.accounts[0].signer);
+        assert!(!initialize.accounts[2].writable && !initialize.accounts[2].signer);
+
+        let args = initialize.args.as_ref().unwrap();
+        assert_eq!(args.name, "Initialize");
+        assert_eq!(
+            args.fields,
+            vec![
+                StateField {
+                    name: "owner".to_string(),
+                    ty: FieldType::Pubkey
+                },
+                StateField {
+                    name: "bump".to_string(),
+                    ty: FieldType::U8
+                },
+            ]
+        );
+
+        assert_eq!(idl.accounts.len(), 1);
+        assert_eq!(idl.accounts[0].ty.name, "MyState");
+        assert_eq!(
+            idl.accounts[0].seeds,
+            vec![
+                PdaSeed::Constant("init".to_string()),
+                PdaSeed::Pubkey("owner".to_string())
+            ]
+        );
+        assert_eq!(idl.errors.len(), 3);
+    }
+
+    #[test]
+    fn follows_generated_instructions_and_states() {
+        let (_temp_dir, project) = project();
+        add::add_instruction(&project, "deposit").unwrap();
+        let fields = [
+            StateField::parse("amount:u64").unwrap(),
+            StateField::parse("tag:[u8; 8]").unwrap(),
+        ];
+        add::add_state(&project, "Vault", &fields, Some("vault")).unwrap();
+
+        let idl = Idl::parse(&project).unwrap();
+        let deposit = &idl.instructions[1];
+        assert_eq!(deposit.name, "Deposit");
+        assert_eq!(deposit.discriminator, 1);
+        assert_eq!(deposit.args, None);
+        assert_eq!(
+            deposit.accounts,
+            vec![IdlAccountMeta {
+                name: "signer".to_string(),
+                writable: false,
+                signer: true,
+                optional: false,
+                docs: vec!["Signer of the instruction".to_string()],
+            }]
+        );
+
+        let vault = &idl.accounts[1];
+        assert_eq!(vault.ty.name, "Vault");
+        assert_eq!(vault.ty.fields[1].ty, FieldType::Bytes(8));
+        assert_eq!(vault.seeds[0], PdaSeed::Constant("vault".to_string()));
+    }
+
+    #[test]
+    fn parses_shank_account_attributes() {
+        let mod_rs = r#"
+#[repr(u8)]
+#[derive(shank::ShankInstruction)]
+pub enum ProgramInstruction {
+    /// Moves lamports
+    #[account(1, name = "to", desc = "Receives, \"gladly\"")]
+    #[account(0, mut, sig, name = "from")]
+    #[account(
+        2,
+        optional_signer,
+        name = "authority"
+    )]
+    Transfer,
+}
+
+impl TryFrom<&u8> for ProgramInstruction {
+    type Error = ProgramError;
+
+    fn try_from(value: &u8) -> Result<Self, Self::Error> {
+        match *value {
+            7 => Ok(ProgramInstruction::Transfer),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}"#;
+        let instructions = idl::parse_instructions(mod_rs).unwrap();
+        assert_eq!(instructions[0].discriminator, 7);
+        assert_eq!(instructions[0].docs, ["Moves lamports"]);
+
+        let accounts = &instructions[0].accounts;
+        assert_eq!(accounts[0].name, "from");
+        assert!(accounts[0].writable && accounts[0].signer);
+        assert_eq!(accounts[1].docs, ["Receives, \"gladly\""]);
+        assert!(accounts[2].signer && accounts[2].optional);
+
+        let unnamed = mod_rs.replace(", name = \"to\"", "");
+        assert!(idl::parse_instructions(&unnamed)
+            .unwrap_err()
+            .to_string()
+            .contains("has no name"));
+    }
+
+    #[test]
+    fn writes_shank_json() {
+        let (temp_dir, project) = project();
+        let generated = idl::build(&project).unwrap();
+        assert_eq!(generated.created, vec![idl::idl_path(&project)]);
+
+        let json = fs::read_to_string(temp_dir.path().join("idl/vault.json")).unwrap();
+        let idl: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(idl["metadata"]["origin"], "shank");
+        assert_eq!(idl["metadata"]["address"], PROGRAM_ID);
+
+        let initialize = &idl["instructions"][0];
+        assert_eq!(initialize["discriminant"]["value"], 0);
+        assert_eq!(initialize["accounts"][3]["name"], "systemProgram");
+        assert_eq!(initialize["accounts"][0]["isSigner"], true);
+        assert_eq!(initialize["args"][0]["type"]["defined"], "Initialize");
+        assert_eq!(idl["types"][0]["type"]["fields"][0]["type"], "publicKey");
+        assert_eq!(idl["accounts"][0]["name"], "MyState");
+        assert_eq!(idl["errors"][1]["name"], "PdaMismatch");
+
+        assert_eq!(
+            idl::build(&project).unwrap().updated,
+            vec![idl::idl_path(&project)]
+        );
+    }
+
+    #[test]
+    fn idl_and_error_table_do_not_collide() {
+        let (temp_dir, project) = scaffold(generator("errors"));
+        add::add_error(&project, "Frozen", None, None).unwrap();
+        idl::build(&project).unwrap();
+
+        assert_ne!(idl::idl_path(&project), add::errors_table_path());
+        let idl = fs::read_to_string(temp_dir.path().join(idl::idl_path(&project))).unwrap();
+        assert!(idl.contains("\"origin\": \"shank\""));
+        let table = fs::read_to_string(temp_dir.path().join(add::errors_table_path())).unwrap();
+        assert!(table.contains("\"Frozen\""));
+    }
+}
@@ -0,0 +1,84 @@
+//! Tests for program keypair generation
+//!
+//! Keypairs must round-trip through the `solana-keygen` JSON format and
+//! derive the same base58 address the Solana CLI would print.
+
+#[cfg(test)]
+mod keypair {
+    use chio::keypair::Keypair;
+    use std::fs;
+    use tempfile::TempDir;
+
+    // RFC 8032, test 1
+    const SECRET: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn from_bytes_derives_known_public_key() {
+        let mut bytes = hex(SECRET);
+        bytes.extend(hex(PUBLIC));
+
+        let keypair = Keypair::from_bytes(&bytes).unwrap();
+        assert_eq!(keypair.pubkey().to_vec(), hex(PUBLIC));
+        assert_eq!(
+            bs58::decode(keypair.address()).into_vec().unwrap(),
+            hex(PUBLIC)
+        );
+    }
+
+    #[test]
+    fn from_bytes_rejects_mismatched_public_key() {
+        let mut bytes = hex(SECRET);
+        bytes.extend([0u8; 32]);
+        assert!(Keypair::from_bytes(&bytes).is_err());
+        assert!(Keypair::from_bytes(&hex(SECRET)).is_err());
+    }
+
+    #[test]
+    fn generated_keypairs_are_distinct() {
+        let a = Keypair::generate().unwrap();
+        let b = Keypair::generate().unwrap();
+        assert_ne!(a.address(), b.address());
+    }
+
+    #[test]
+    fn keypair_file_round_trips_in_solana_keygen_format() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("program-keypair.json");
+
+        let keypair = Keypair::generate().unwrap();
+        keypair.write(&path).unwrap();
+
+        let json = fs::read_to_string(&path).unwrap();
+        let bytes: Vec<u8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(bytes.len(), 64);
+        assert!(json.starts_with('[') && !json.contains(' '));
+
+        assert_eq!(Keypair::read(&path).unwrap().address(), keypair.address());
+    }
+
+    #[test]
+    fn keypair_files_are_private_and_never_overwritten() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("program-keypair.json");
+
+        let keypair = Keypair::generate().unwrap();
+        keypair.write(&path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(Keypair::generate().unwrap().write(&path).is_err());
+        assert_eq!(Keypair::read(&path).unwrap().address(), keypair.address());
+    }
+}
@@ -0,0 +1,130 @@
+//! Tests for program id syncing
+//!
+//! Scaffolds a project whose source references a stale program id and
+//! checks that `chio keys` finds and rewrites it.
+
+#[cfg(test)]
+mod keys {
+    use chio::add::Project;
+    use chio::content::templates;
+    use chio::keypair::Keypair;
+    use chio::keys;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    const STALE: &str = "11111111111111111111111111111112";
+
+    fn project_with_keypair() -> (TempDir, Project, String) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("tests")).unwrap();
+        fs::create_dir_all(root.join("target/deploy")).unwrap();
+
+        fs::write(
+            root.join("Cargo.toml"),
+            templates::cargo_toml_mollusk("test_project"),
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), templates::lib_rs(STALE)).unwrap();
+        fs::write(
+            root.join("tests/tests.rs"),
+            templates::unit_tests::unit_test_rs(STALE, STALE, "test_project"),
+        )
+        .unwrap();
+
+        let keypair = Keypair::generate().unwrap();
+        keypair
+            .write(&root.join("target/deploy/test_project-keypair.json"))
+            .unwrap();
+
+        let project = Project::open(root).unwrap();
+        (temp_dir, project, keypair.address())
+    }
+
+    #[test]
+    fn program_id_literals_only_match_program_constants() {
+        let src = "pinocchio_pubkey::declare_id!(\"AAA\");\n\
+                   pub const PROGRAM: Pubkey = pubkey!(\"BBB\");\n\
+                   pub const PAYER: Pubkey = pubkey!(\"CCC\");\n";
+
+        assert_eq!(
+            keys::program_id_literals(src),
+            vec![(1, "AAA".to_string()), (2, "BBB".to_string())]
+        );
+        assert_eq!(
+            keys::replace_program_ids(src, "ZZZ"),
+            src.replace("AAA", "ZZZ").replace("BBB", "ZZZ")
+        );
+    }
+
+    #[test]
+    fn keypair_address_reads_deploy_keypair() {
+        let (_temp_dir, project, address) = project_with_keypair();
+        assert_eq!(keys::keypair_address(&project).unwrap(), address);
+        assert_eq!(
+            keys::keypair_path(&project),
+            Path::new("target/deploy/test_project-keypair.json")
+        );
+    }
+
+    #[test]
+    fn find_program_ids_covers_lib_and_tests() {
+        let (_temp_dir, project, _) = project_with_keypair();
+        let paths: Vec<PathBuf> = keys::find_program_ids(&project)
+            .unwrap()
+            .into_iter()
+            .map(|literal| literal.path)
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("tests/tests.rs")]
+        );
+    }
+
+    #[test]
+    fn check_reports_without_writing() {
+        let (temp_dir, project, _) = project_with_keypair();
+        let stale =
+            keys::sync_program_ids(&project, &keys::keypair_address(&project).unwrap(), true)
+                .unwrap();
+
+        assert_eq!(stale.len(), 2);
+        let lib_rs = fs::read_to_string(temp_dir.path().join("src/lib.rs")).unwrap();
+        assert!(lib_rs.contains(STALE));
+    }
+
+    #[test]
+    fn sync_rewrites_program_ids_but_not_payer() {
+        let (temp_dir, project, address) = project_with_keypair();
+        keys::sync_program_ids(&project, &address, false).unwrap();
+
+        let lib_rs = fs::read_to_string(temp_dir.path().join("src/lib.rs")).unwrap();
+        assert!(lib_rs.contains(&format!("declare_id!(\"{}\")", address)));
+
+        let tests_rs = fs::read_to_string(temp_dir.path().join("tests/tests.rs")).unwrap();
+        assert!(tests_rs.contains(&format!("PROGRAM: Pubkey = pubkey!(\"{}\")", address)));
+        assert!(tests_rs.contains(&format!("PAYER: Pubkey = pubkey!(\"{}\")", STALE)));
+
+        assert!(keys::sync_program_ids(&project, &address, true)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn sync_leaves_other_program_constants_alone() {
+        let (temp_dir, project, address) = project_with_keypair();
+        let authority = format!(
+            "pub const PROGRAM_AUTHORITY: Pubkey = pubkey!(\"{STALE}\");\n\
+             pub const PROGRAM : Pubkey = pubkey!(\"{STALE}\");\n"
+        );
+        fs::write(temp_dir.path().join("src/authority.rs"), &authority).unwrap();
+
+        keys::sync_program_ids(&project, &address, false).unwrap();
+        let synced = fs::read_to_string(temp_dir.path().join("src/authority.rs")).unwrap();
+        assert!(synced.contains(&format!("PROGRAM_AUTHORITY: Pubkey = pubkey!(\"{STALE}\")")));
+        assert!(synced.contains(&format!("PROGRAM : Pubkey = pubkey!(\"{address}\")")));
+    }
+}
@@ -0,0 +1,59 @@
+//! Tests for merging generated files into existing ones
+//!
+//! Covers TOML files, line lists such as .gitignore and files chio does not
+//! merge.
+
+#[cfg(test)]
+mod merge {
+    use chio::merge::{can_merge, merge, merge_lines, merge_toml};
+    use std::path::Path;
+
+    #[test]
+    fn toml_keeps_existing_values_and_comments() {
+        let existing = "# My program\n[package]\nname = \"vault\"\nedition = \"2018\" # pinned\n\n[dependencies]\nserde = \"1\"\n";
+        let generated = "[package]\nname = \"vault\"\nedition = \"2021\"\n\n[dependencies]\npinocchio = \"0.9.2\"\n\n[features]\nstd = []\n";
+
+        let merged = merge_toml(existing, generated).unwrap();
+        assert!(merged.starts_with("# My program\n"));
+        assert!(merged.contains("edition = \"2018\" # pinned"));
+        assert!(!merged.contains("2021"));
+        assert!(merged.contains("serde = \"1\"\npinocchio = \"0.9.2\""));
+        assert!(merged.contains("[features]\nstd = []"));
+        assert_eq!(merge_toml(&merged, generated).unwrap(), merged);
+    }
+
+    #[test]
+    fn toml_arrays_get_missing_values() {
+        let existing = "[workspace]\nmembers = [\"programs/vault\"]\n";
+        let generated = "[workspace]\nmembers = [\"programs/*\", \"test-helpers\"]\n";
+
+        let merged = merge_toml(existing, generated).unwrap();
+        let manifest: toml::Table = toml::from_str(&merged).unwrap();
+        let members = manifest["workspace"]["members"].as_array().unwrap();
+        assert_eq!(members.len(), 3);
+        assert_eq!(members[0].as_str(), Some("programs/vault"));
+    }
+
+    #[test]
+    fn lines_are_appended_once() {
+        assert_eq!(
+            merge_lines(".env\ntarget", "/target\ntarget\n.env\n"),
+            ".env\ntarget\n/target\n"
+        );
+        assert_eq!(merge_lines("target\n", "target\n"), "target\n");
+    }
+
+    #[test]
+    fn only_known_files_are_merged() {
+        assert!(can_merge(Path::new("Cargo.toml")));
+        assert!(can_merge(Path::new("programs/vault/Cargo.toml")));
+        assert!(can_merge(Path::new(".gitignore")));
+        assert!(!can_merge(Path::new("README.md")));
+        assert!(merge(Path::new("src/lib.rs"), "a", "b").is_none());
+
+        let error = merge(Path::new("Chio.toml"), "not = = toml", "")
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Failed to merge Chio.toml");
+    }
+}
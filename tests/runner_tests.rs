@@ -0,0 +1,280 @@
+//! Tests for the flows that run external commands
+//!
+//! Covers init, build, test and deploy through a recording runner, so the
+//! Solana toolchain, cargo-build-sbf and git do not need to be installed.
+
+#[cfg(test)]
+mod runner {
+    use chio::build::{self, BuildOptions};
+    use chio::config::{ChioConfig, ProgramConfig};
+    use chio::deploy::{self, Cluster, DeployOptions};
+    use chio::generator::ProjectGenerator;
+    use chio::keypair;
+    use chio::runner::{CommandOutput, CommandRunner, ProcessCommand, RecordingRunner};
+    use chio::scripts::{self, Script};
+    use chio::template_set::TemplateSet;
+    use chio::TestFramework;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    #[test]
+    fn recording_runner_answers_by_longest_prefix() {
+        let runner = RecordingRunner::new()
+            .with_output("git", CommandOutput::failure(1, "not a repository"))
+            .with_output("git init", CommandOutput::success("Initialized"))
+            .with_missing("solana");
+
+        let init = runner
+            .output(&ProcessCommand::new("git", ["init"]))
+            .unwrap();
+        assert_eq!(init.stdout, "Initialized");
+        let status = runner
+            .output(&ProcessCommand::new("git", ["status"]))
+            .unwrap();
+        assert_eq!(status.code, Some(1));
+        assert!(runner
+            .status(&ProcessCommand::new("cargo", ["test"]))
+            .unwrap()
+            .is_success());
+
+        let error = runner
+            .output(&ProcessCommand::new("solana", ["address"]))
+            .unwrap_err();
+        assert!(error.to_string().contains("'solana' was not found"));
+
+        assert_eq!(
+            runner.command_lines(),
+            vec!["git init", "git status", "cargo test", "solana address"]
+        );
+    }
+
+    #[test]
+    fn init_runs_git_in_the_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("vault");
+        let project = ProjectGenerator::new("vault")
+            .with_output_dir(&root)
+            .with_git(true)
+            .generate()
+            .unwrap();
+        let runner = RecordingRunner::new();
+
+        assert_eq!(project.write_with(&runner).unwrap(), None);
+        assert!(root.join("src/lib.rs").exists());
+        assert_eq!(
+            runner.command_lines(),
+            vec![
+                "git init",
+                "git add .",
+                "git commit -m \"Initial commit: Setup Pinocchio project 'vault'\"",
+            ]
+        );
+        assert!(runner
+            .commands()
+            .iter()
+            .all(|command| command.dir.as_deref() == Some(root.as_path())));
+    }
+
+    #[test]
+    fn init_reports_a_failed_git_step_without_failing() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = ProjectGenerator::new("vault")
+            .with_output_dir(temp_dir.path())
+            .with_git(true)
+            .generate()
+            .unwrap();
+        let runner = RecordingRunner::new().with_output(
+            "git commit",
+            CommandOutput::failure(128, "Please tell me who you are. user.email"),
+        );
+
+        let warning = project.write_with(&runner).unwrap().unwrap();
+        assert!(warning.starts_with("Failed to make initial commit"));
+        assert!(warning.contains("user.email"));
+    }
+
+    #[test]
+    fn build_and_test_run_the_default_scripts() {
+        let temp_dir = TempDir::new().unwrap();
+        let runner = RecordingRunner::new();
+
+        scripts::run_script(&runner, temp_dir.path(), Script::Build, None).unwrap();
+        scripts::run_script(&runner, temp_dir.path(), Script::Test, None).unwrap();
+
+        assert_eq!(
+            runner.command_lines(),
+            vec!["cargo build-sbf", "cargo test"]
+        );
+        assert_eq!(runner.commands()[0].dir.as_deref(), Some(temp_dir.path()));
+    }
+
+    #[test]
+    fn build_runs_the_configured_script_for_the_selected_program() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = ChioConfig::new_workspace();
+        config.scripts.build = "cargo build-sbf --arch v3".to_string();
+        config
+            .add_member(
+                ProgramConfig::new("vault", TestFramework::Mollusk),
+                "Vault111",
+            )
+            .unwrap();
+        config.save(temp_dir.path()).unwrap();
+        let runner = RecordingRunner::new();
+
+        scripts::run_script(&runner, temp_dir.path(), Script::Build, Some("vault")).unwrap();
+        assert_eq!(
+            runner.command_lines(),
+            vec!["cargo build-sbf --arch v3 --manifest-path programs/vault/Cargo.toml"]
+        );
+
+        let error = scripts::run_script(&runner, temp_dir.path(), Script::Test, Some("escrow"))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("No program 'escrow' in this workspace. Programs: vault"));
+    }
+
+    #[test]
+    fn build_passes_options_and_saves_them_as_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let options = BuildOptions {
+            no_default_features: true,
+            manifest_path: Some(PathBuf::from("program/Cargo.toml")),
+            args: vec!["--locked".to_string()],
+            ..BuildOptions::default()
+        };
+        assert!(build::save_defaults(temp_dir.path(), &options).is_err());
+
+        ChioConfig::new("vault", TestFramework::Mollusk, "Vault111")
+            .save(temp_dir.path())
+            .unwrap();
+        build::save_defaults(temp_dir.path(), &options).unwrap();
+        let runner = RecordingRunner::new();
+
+        let debug = BuildOptions {
+            debug: true,
+            ..BuildOptions::default()
+        };
+        build::run_build(&runner, temp_dir.path(), None, &debug).unwrap();
+        assert_eq!(
+            runner.command_lines(),
+            vec!["cargo build-sbf --no-default-features --manifest-path program/Cargo.toml --debug -- --locked"]
+        );
+        assert_eq!(
+            ChioConfig::load(temp_dir.path()).unwrap().unwrap().build,
+            options
+        );
+
+        let dist = BuildOptions {
+            sbf_out_dir: Some(PathBuf::from("dist")),
+            ..BuildOptions::default()
+        };
+        let error = build::save_defaults(temp_dir.path(), &dist).unwrap_err();
+        assert!(error.to_string().contains("--sbf-out-dir cannot be saved"));
+        assert_eq!(
+            ChioConfig::load(temp_dir.path()).unwrap().unwrap().build,
+            options
+        );
+
+        let error = build::run_build(&runner, temp_dir.path(), Some("vault"), &debug).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("--manifest-path cannot be combined with --program"));
+    }
+
+    #[test]
+    fn build_fails_with_the_exit_code_or_missing_tool() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let runner =
+            RecordingRunner::new().with_output("cargo test", CommandOutput::failure(101, ""));
+        let error = scripts::run_script(&runner, temp_dir.path(), Script::Test, None).unwrap_err();
+        assert_eq!(error.to_string(), "Test failed with exit code: Some(101)");
+
+        let runner = RecordingRunner::new().with_missing("cargo");
+        let error = scripts::run_script(&runner, temp_dir.path(), Script::Build, None).unwrap_err();
+        assert!(error.to_string().contains("'cargo' was not found"));
+    }
+
+    #[test]
+    fn deploy_runs_solana_program_deploy() {
+        let options = DeployOptions {
+            cluster: Some(Cluster::Devnet),
+            keypair: Some(PathBuf::from("payer.json")),
+            ..DeployOptions::default()
+        };
+        let runner = RecordingRunner::new();
+
+        deploy::run_deploy(
+            &runner,
+            Path::new("target/deploy/vault.so"),
+            Path::new("target/deploy/vault-keypair.json"),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            runner.command_lines(),
+            vec![
+                "solana program deploy target/deploy/vault.so \
+                 --program-id target/deploy/vault-keypair.json \
+                 --url https://api.devnet.solana.com --keypair payer.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn deploy_fails_without_the_solana_cli() {
+        let so_path = Path::new("vault.so");
+        let keypair_path = Path::new("vault-keypair.json");
+        let options = DeployOptions::default();
+
+        let runner = RecordingRunner::new().with_missing("solana");
+        let error = deploy::run_deploy(&runner, so_path, keypair_path, &options).unwrap_err();
+        assert!(error.to_string().contains("'solana' was not found"));
+
+        let runner = RecordingRunner::new()
+            .with_output("solana program deploy", CommandOutput::failure(1, ""));
+        let error = deploy::run_deploy(&runner, so_path, keypair_path, &options).unwrap_err();
+        assert_eq!(error.to_string(), "Deploy failed with exit code: Some(1)");
+    }
+
+    #[test]
+    fn wallet_address_comes_from_the_solana_cli() {
+        let runner = RecordingRunner::new()
+            .with_output("solana address", CommandOutput::success("Wallet111\n"));
+        assert_eq!(
+            keypair::wallet_address(&runner).as_deref(),
+            Some("Wallet111")
+        );
+    }
+
+    #[test]
+    fn git_templates_report_a_failed_clone() {
+        let runner = RecordingRunner::new().with_output(
+            "git clone",
+            CommandOutput::failure(128, "repository not found\n"),
+        );
+
+        let error = TemplateSet::load_git(&runner, "https://example.com/missing.git").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to clone https://example.com/missing.git: repository not found"
+        );
+        let clone = &runner.commands()[0];
+        assert_eq!(
+            clone.args[..5],
+            [
+                "clone",
+                "--depth",
+                "1",
+                "--",
+                "https://example.com/missing.git"
+            ]
+        );
+        assert!(!Path::new(&clone.args[5]).exists());
+
+        assert!(TemplateSet::load_git(&runner, "https://example.com/missing.git").is_err());
+        assert_ne!(runner.commands()[1].args[5], clone.args[5]);
+    }
+}
@@ -0,0 +1,295 @@
+//! Tests for the program size report
+//!
+//! Builds small ELF files in memory, so reading sections and symbols and the
+//! report `chio build` prints can be checked without cargo-build-sbf.
+
+#[cfg(test)]
+mod size {
+    use chio::build::BuildOptions;
+    use chio::config::ChioConfig;
+    use chio::size::{self, SizeEntry};
+    use chio::TestFramework;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    const SHT_PROGBITS: u32 = 1;
+    const SHT_SYMTAB: u32 = 2;
+    const SHT_STRTAB: u32 = 3;
+    const SHT_DYNSYM: u32 = 11;
+
+    struct Section {
+        name: &'static str,
+        kind: u32,
+        data: Vec<u8>,
+        link: u32,
+    }
+
+    /// A symbol table and its string table for `(name, st_info type, size)`.
+    fn symbols(entries: &[(&str, u8, u64)]) -> (Vec<u8>, Vec<u8>) {
+        let mut table = vec![0u8; 24];
+        let mut names = vec![0u8];
+        for (name, kind, size) in entries {
+            let mut entry = Vec::new();
+            entry.extend((names.len() as u32).to_le_bytes());
+            entry.push(0x10 | kind);
+            entry.push(0);
+            entry.extend(1u16.to_le_bytes());
+            entry.extend(0u64.to_le_bytes());
+            entry.extend(size.to_le_bytes());
+            table.extend(entry);
+            names.extend(name.as_bytes());
+            names.push(0);
+        }
+        (table, names)
+    }
+
+    /// A 64-bit little-endian ELF with `sections` after the null section,
+    /// followed by `.shstrtab`.
+    fn elf(sections: Vec<Section>) -> Vec<u8> {
+        let mut names = vec![0u8];
+        let mut name_offsets = Vec::new();
+        for section in &sections {
+            name_offsets.push(names.len() as u32);
+            names.extend(section.name.as_bytes());
+            names.push(0);
+        }
+        let shstrtab_name = names.len() as u32;
+        names.extend(b".shstrtab\0");
+
+        let mut bytes = vec![0u8; 64];
+        let mut headers = vec![0u8; 64];
+        let mut push = |bytes: &mut Vec<u8>, name: u32, kind: u32, data: &[u8], link: u32| {
+            let offset = bytes.len() as u64;
+            bytes.extend(data);
+            headers.extend(name.to_le_bytes());
+            headers.extend(kind.to_le_bytes());
+            headers.extend([0u8; 16]);
+            headers.extend(offset.to_le_bytes());
+            headers.extend((data.len() as u64).to_le_bytes());
+            headers.extend(link.to_le_bytes());
+            headers.extend([0u8; 20]);
+        };
+        for (section, name) in sections.iter().zip(&name_offsets) {
+            push(&mut bytes, *name, section.kind, &section.data, section.link);
+        }
+        push(&mut bytes, shstrtab_name, SHT_STRTAB, &names, 0);
+
+        let section_offset = bytes.len() as u64;
+        let count = sections.len() as u16 + 2;
+        bytes.extend(headers);
+        bytes[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        bytes[0x28..0x30].copy_from_slice(&section_offset.to_le_bytes());
+        bytes[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        bytes[0x3c..0x3e].copy_from_slice(&count.to_le_bytes());
+        bytes[0x3e..0x40].copy_from_slice(&(count - 1).to_le_bytes());
+        bytes
+    }
+
+    fn program(text: usize, symbol_table: Option<(u32, Vec<u8>, Vec<u8>)>) -> Vec<u8> {
+        let mut sections = vec![
+            Section {
+                name: ".text",
+                kind: SHT_PROGBITS,
+                data: vec![0x95; text],
+                link: 0,
+            },
+            Section {
+                name: ".rodata",
+                kind: SHT_PROGBITS,
+                data: vec![1; 24],
+                link: 0,
+            },
+        ];
+        if let Some((kind, table, names)) = symbol_table {
+            let strtab = if kind == SHT_SYMTAB {
+                ".strtab"
+            } else {
+                ".dynstr"
+            };
+            let symtab = if kind == SHT_SYMTAB {
+                ".symtab"
+            } else {
+                ".dynsym"
+            };
+            sections.push(Section {
+                name: strtab,
+                kind: SHT_STRTAB,
+                data: names,
+                link: 0,
+            });
+            sections.push(Section {
+                name: symtab,
+                kind,
+                data: table,
+                link: 3,
+            });
+        }
+        elf(sections)
+    }
+
+    fn entry(name: &str, size: u64) -> SizeEntry {
+        SizeEntry {
+            name: name.to_string(),
+            size,
+        }
+    }
+
+    #[test]
+    fn reads_sections_and_symbols_largest_first() {
+        let (table, names) = symbols(&[
+            ("entrypoint", 2, 64),
+            (
+                "_ZN5vault12instructions10initialize17h0123456789abcdefE",
+                2,
+                512,
+            ),
+            ("SEED", 1, 4),
+            ("vault.c", 4, 0),
+        ]);
+        let elf = size::parse_elf(&program(1024, Some((SHT_SYMTAB, table, names)))).unwrap();
+
+        assert!(!elf.stripped);
+        assert_eq!(elf.sections[0], entry(".text", 1024));
+        assert_eq!(elf.sections[1].name, ".symtab");
+        assert!(elf.sections.contains(&entry(".rodata", 24)));
+        assert_eq!(
+            elf.symbols,
+            vec![
+                entry("vault::instructions::initialize", 512),
+                entry("entrypoint", 64),
+                entry("SEED", 4)
+            ]
+        );
+
+        let (table, names) = symbols(&[("entrypoint", 2, 64)]);
+        let stripped = size::parse_elf(&program(16, Some((SHT_DYNSYM, table, names)))).unwrap();
+        assert!(stripped.stripped);
+        assert_eq!(stripped.symbols, vec![entry("entrypoint", 64)]);
+
+        assert!(size::parse_elf(b"#!/bin/sh")
+            .unwrap_err()
+            .to_string()
+            .contains("Not an ELF"));
+        let truncated = program(16, None);
+        assert!(size::parse_elf(&truncated[..truncated.len() - 32]).is_err());
+    }
+
+    #[test]
+    fn deploy_deposit_covers_program_data_and_program_accounts() {
+        assert_eq!(size::rent_exempt_minimum(0), 890_880);
+        assert_eq!(size::rent_exempt_minimum(165), 2_039_280);
+        assert_eq!(
+            size::deploy_deposit(1000),
+            size::rent_exempt_minimum(1045) + size::rent_exempt_minimum(36)
+        );
+        assert_eq!(size::format_sol(2_039_280), "0.002039280 SOL");
+        assert_eq!(size::format_sol(1_500_000_000), "1.500000000 SOL");
+        assert_eq!(size::format_bytes(24_576), "24576 bytes (24.0 KiB)");
+    }
+
+    #[test]
+    fn reports_built_programs_and_checks_the_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        ChioConfig::new("vault", TestFramework::Mollusk, "Vault111")
+            .save(temp_dir.path())
+            .unwrap();
+        let error =
+            size::program_sizes(temp_dir.path(), None, &BuildOptions::default()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("target/deploy/vault.so was not found"));
+
+        let (table, names) = symbols(&[("entrypoint", 2, 64)]);
+        let deployed = program(2048, Some((SHT_DYNSYM, table, names)));
+        let (table, names) = symbols(&[
+            ("entrypoint", 2, 64),
+            ("_ZN5vault7process17h0123456789abcdefE", 2, 900),
+        ]);
+        let unstripped = program(4096, Some((SHT_SYMTAB, table, names)));
+        fs::create_dir_all(temp_dir.path().join("dist")).unwrap();
+        fs::write(temp_dir.path().join("dist/vault.so"), &deployed).unwrap();
+        let release = temp_dir.path().join("target/sbpf-solana-solana/release");
+        fs::create_dir_all(&release).unwrap();
+        fs::write(release.join("vault.so"), &unstripped).unwrap();
+
+        let options = BuildOptions {
+            sbf_out_dir: Some(PathBuf::from("dist")),
+            ..BuildOptions::default()
+        };
+        let sizes = size::program_sizes(temp_dir.path(), None, &options).unwrap();
+        assert_eq!(sizes.len(), 1);
+        assert_eq!(sizes[0].program, "vault");
+        assert_eq!(sizes[0].path, Path::new("dist/vault.so"));
+        assert_eq!(sizes[0].size, deployed.len() as u64);
+        assert_eq!(sizes[0].sections[0], entry(".text", 2048));
+        assert_eq!(sizes[0].symbols[0], entry("vault::process", 900));
+        assert_eq!(
+            sizes[0].symbols_from.as_deref(),
+            Some(Path::new("target/sbpf-solana-solana/release/vault.so"))
+        );
+        assert_eq!(
+            sizes[0].deploy_deposit(),
+            size::deploy_deposit(deployed.len() as u64)
+        );
+
+        assert!(size::check_max_size(&sizes, deployed.len() as u64).is_ok());
+        let error = size::check_max_size(&sizes, 2048).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Program size budget of 2048 bytes exceeded: dist/vault.so is {} bytes",
+                deployed.len()
+            )
+        );
+    }
+
+    #[test]
+    fn symbols_follow_the_debug_build_and_target_dir() {
+        let options = BuildOptions {
+            debug: true,
+            sbf_out_dir: Some(PathBuf::from("dist")),
+            args: vec!["--locked".to_string(), "--target-dir=build".to_string()],
+            ..BuildOptions::default()
+        };
+        assert_eq!(options.target_dir(), Path::new("build"));
+        assert_eq!(
+            size::unstripped_paths(&options, "vault.so"),
+            vec![
+                PathBuf::from("dist/vault.debug"),
+                PathBuf::from("build/sbpf-solana-solana/release/vault.so"),
+                PathBuf::from("build/sbf-solana-solana/release/vault.so"),
+            ]
+        );
+        let split = BuildOptions {
+            args: vec!["--target-dir".to_string(), "/tmp/cargo".to_string()],
+            ..BuildOptions::default()
+        };
+        assert_eq!(
+            size::unstripped_paths(&split, "vault.so")[0],
+            Path::new("/tmp/cargo/sbpf-solana-solana/release/vault.so")
+        );
+
+        let temp_dir = TempDir::new().unwrap();
+        let (table, names) = symbols(&[("entrypoint", 2, 64)]);
+        let deployed = program(2048, Some((SHT_DYNSYM, table, names)));
+        let (table, names) = symbols(&[("_ZN5vault5debug17h0123456789abcdefE", 2, 700)]);
+        let debug = program(4096, Some((SHT_SYMTAB, table, names)));
+        fs::create_dir_all(temp_dir.path().join("dist")).unwrap();
+        fs::write(temp_dir.path().join("dist/vault.so"), &deployed).unwrap();
+        fs::write(temp_dir.path().join("dist/vault.debug"), &debug).unwrap();
+
+        let report = size::program_size(
+            temp_dir.path(),
+            "vault",
+            Path::new("dist/vault.so"),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(report.symbols[0], entry("vault::debug", 700));
+        assert_eq!(
+            report.symbols_from.as_deref(),
+            Some(Path::new("dist/vault.debug"))
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Tests for the template rendering engine
+//!
+//! Covers variable substitution, conditionals, loops, raw blocks and the
+//! errors reported for malformed templates.
+
+#[cfg(test)]
+mod template_engine {
+    use chio::template::{render, TemplateContext, Value};
+    use chio::TestFramework;
+    use std::collections::BTreeMap;
+
+    fn context() -> TemplateContext {
+        TemplateContext::new("my-program")
+            .with_program_id("Prog111")
+            .with_payer("Payer111")
+            .with_test_framework(TestFramework::Litesvm)
+    }
+
+    #[test]
+    fn substitutes_typed_values() {
+        let output = render(
+            "{{ project_name }} {{crate_name}} {{ program_id }} {{ payer }}",
+            &context(),
+        )
+        .unwrap();
+        assert_eq!(output, "my-program my_program Prog111 Payer111");
+    }
+
+    #[test]
+    fn inserted_values_are_not_rendered_again() {
+        let context = context().set("message", "{{ payer }} {address}");
+        assert_eq!(
+            render("{{ message }}", &context).unwrap(),
+            "{{ payer }} {address}"
+        );
+        assert_eq!(render("{address} {}", &context).unwrap(), "{address} {}");
+    }
+
+    #[test]
+    fn conditionals_pick_a_branch() {
+        let template = "{% if test_framework == \"mollusk\" %}mollusk{% else %}litesvm{% endif %}";
+        assert_eq!(render(template, &context()).unwrap(), "litesvm");
+
+        let template = "{% if features.workspace %}ws{% endif %}{% if not features.workspace %}single{% endif %}";
+        assert_eq!(render(template, &context()).unwrap(), "single");
+        assert_eq!(
+            render(template, &context().with_feature("workspace", true)).unwrap(),
+            "ws"
+        );
+    }
+
+    #[test]
+    fn block_tags_alone_on_a_line_leave_no_blank_line() {
+        let template = "a\n{% if features.std %}\n  std\n{% else %}\n  no_std\n{% endif %}\nb\n";
+        assert_eq!(render(template, &context()).unwrap(), "a\n  no_std\nb\n");
+    }
+
+    #[test]
+    fn loops_repeat_over_items() {
+        let fields: Vec<BTreeMap<String, Value>> = ["owner", "amount"]
+            .iter()
+            .map(|name| BTreeMap::from([("name".to_string(), Value::from(*name))]))
+            .collect();
+        let context = context().set("fields", fields);
+
+        let template =
+            "{% for field in fields %}\n{{ field.name }} of {{ crate_name }}\n{% endfor %}\n";
+        assert_eq!(
+            render(template, &context).unwrap(),
+            "owner of my_program\namount of my_program\n"
+        );
+    }
+
+    #[test]
+    fn raw_blocks_keep_tags_as_text() {
+        let template = "let s = {% raw %}format!(\"{{}}\"){% endraw %};";
+        assert_eq!(
+            render(template, &context()).unwrap(),
+            "let s = format!(\"{{}}\");"
+        );
+
+        let template = "fn {{ crate_name }}() {\n{% raw %}\n    \
+                        println!(\"{{ payer }} {%\");\n{% endraw %}\n}\n";
+        assert_eq!(
+            render(template, &context()).unwrap(),
+            "fn my_program() {\n    println!(\"{{ payer }} {%\");\n}\n"
+        );
+
+        let unclosed = render("a\n{% raw %}{{ payer }}", &context()).unwrap_err();
+        assert!(unclosed
+            .to_string()
+            .contains("Unclosed {% raw %} on line 2"));
+        assert!(render("{% endraw %}", &context()).is_err());
+    }
+
+    #[test]
+    fn reports_unknown_variables_and_unbalanced_blocks() {
+        let unknown = render("line\n{{ missing }}", &context()).unwrap_err();
+        assert!(unknown.to_string().contains("'missing' on line 2"));
+
+        assert!(render("{% if payer %}", &context()).is_err());
+        assert!(render("{% endif %}", &context()).is_err());
+        assert!(render("{% for x in payer %}{% endfor %}", &context()).is_err());
+        assert!(render("{% while payer %}", &context()).is_err());
+        assert!(render("{{ payer", &context()).is_err());
+    }
+}
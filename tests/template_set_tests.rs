@@ -0,0 +1,259 @@
+//! Tests for template sets
+//!
+//! Covers the built-in set, loading sets from a directory with their
+//! manifest, and the variables and paths a manifest may use.
+
+#[cfg(test)]
+mod template_set {
+    use chio::template::TemplateContext;
+    use chio::template_set::{is_git_url, parse_variable, TemplateSet, MANIFEST_FILE};
+    use chio::TestFramework;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn context(test_framework: TestFramework) -> TemplateContext {
+        TemplateContext::new("my_program")
+            .with_program_id("Prog111")
+            .with_payer("Payer111")
+            .with_test_framework(test_framework)
+    }
+
+    fn context_default() -> TemplateContext {
+        context(TestFramework::Mollusk)
+    }
+
+    fn paths(rendered: &[(PathBuf, String)]) -> Vec<&Path> {
+        rendered.iter().map(|(path, _)| path.as_path()).collect()
+    }
+
+    fn write_set(dir: &Path, manifest: &str, files: &[(&str, &str)]) {
+        fs::write(dir.join(MANIFEST_FILE), manifest).unwrap();
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn builtin_renders_tests_for_the_chosen_framework() {
+        let set = TemplateSet::builtin();
+
+        let mollusk = set.render(&context(TestFramework::Mollusk)).unwrap();
+        assert!(paths(&mollusk).contains(&Path::new("tests/tests.rs")));
+        assert!(!paths(&mollusk).contains(&Path::new("tests/initialize.rs")));
+
+        let litesvm = set.render(&context(TestFramework::Litesvm)).unwrap();
+        assert!(paths(&litesvm).contains(&Path::new("tests/initialize.rs")));
+        assert!(!paths(&litesvm).contains(&Path::new("tests/tests.rs")));
+
+        let (_, lib_rs) = litesvm
+            .iter()
+            .find(|(path, _)| path == Path::new("src/lib.rs"))
+            .unwrap();
+        assert!(lib_rs.contains("Prog111"));
+    }
+
+    #[test]
+    fn builtin_leaves_readme_and_gitignore_to_the_workspace() {
+        let context = context(TestFramework::Mollusk).with_feature("workspace", true);
+        let rendered = TemplateSet::builtin().render(&context).unwrap();
+
+        assert!(!paths(&rendered).contains(&Path::new("README.md")));
+        assert!(!paths(&rendered).contains(&Path::new(".gitignore")));
+        assert!(paths(&rendered).contains(&Path::new("Cargo.toml")));
+    }
+
+    #[test]
+    fn exported_builtin_loads_back_and_renders_the_same() {
+        let temp_dir = TempDir::new().unwrap();
+        let builtin = TemplateSet::builtin();
+        builtin.export(temp_dir.path()).unwrap();
+
+        let loaded = TemplateSet::load(temp_dir.path()).unwrap();
+        assert_eq!(loaded.manifest, builtin.manifest);
+
+        let context = context(TestFramework::Litesvm);
+        assert_eq!(
+            loaded.render(&context).unwrap(),
+            builtin.render(&context).unwrap()
+        );
+    }
+
+    #[test]
+    fn manifest_maps_files_with_conditions_and_variables() {
+        let temp_dir = TempDir::new().unwrap();
+        write_set(
+            temp_dir.path(),
+            r#"
+name = "house-style"
+
+[variables]
+author = "Platform team"
+
+[[files]]
+source = "lib.rs"
+target = "src/lib.rs"
+
+[[files]]
+source = "mollusk.rs"
+target = "tests/{{ crate_name }}.rs"
+when = 'test_framework == "mollusk"'
+
+[[files]]
+source = "raw.txt"
+render = false
+"#,
+            &[
+                ("lib.rs", "// {{ author }}: {{ program_id }}\n"),
+                ("mollusk.rs", "// mollusk\n"),
+                ("raw.txt", "{{ kept }}\n"),
+            ],
+        );
+        let set = TemplateSet::load(temp_dir.path()).unwrap();
+
+        let vars = BTreeMap::from([("author".to_string(), "Ann".to_string())]);
+        let context = set.context(context(TestFramework::Litesvm), &vars).unwrap();
+        assert_eq!(
+            set.render(&context).unwrap(),
+            vec![
+                (PathBuf::from("src/lib.rs"), "// Ann: Prog111\n".to_string()),
+                (PathBuf::from("raw.txt"), "{{ kept }}\n".to_string()),
+            ]
+        );
+
+        let context = set.context(context_default(), &BTreeMap::new()).unwrap();
+        let rendered = set.render(&context).unwrap();
+        assert_eq!(rendered[0].1, "// Platform team: Prog111\n");
+        assert_eq!(rendered[1].0, PathBuf::from("tests/my_program.rs"));
+    }
+
+    #[test]
+    fn manifest_without_files_renders_the_whole_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        write_set(
+            temp_dir.path(),
+            "name = \"plain\"\n",
+            &[
+                ("src/lib.rs", "{{ crate_name }}"),
+                ("Cargo.toml", "{{ project_name }}"),
+            ],
+        );
+        fs::create_dir_all(temp_dir.path().join(".git")).unwrap();
+        fs::write(temp_dir.path().join(".git/HEAD"), "ref").unwrap();
+
+        let set = TemplateSet::load(temp_dir.path()).unwrap();
+        let written_to = TempDir::new().unwrap();
+        let written = set.write(written_to.path(), &context_default()).unwrap();
+
+        assert_eq!(
+            written,
+            vec![PathBuf::from("Cargo.toml"), PathBuf::from("src/lib.rs")]
+        );
+        assert_eq!(
+            fs::read_to_string(written_to.path().join("src/lib.rs")).unwrap(),
+            "my_program"
+        );
+    }
+
+    #[test]
+    fn rejects_undeclared_variables() {
+        let set = TemplateSet::builtin();
+        let vars = BTreeMap::from([("autor".to_string(), "Ann".to_string())]);
+
+        let error = set.check_variables(&vars).unwrap_err();
+        assert!(error.to_string().contains("no variable 'autor'"));
+        assert!(set.context(context_default(), &vars).is_err());
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_template_and_project() {
+        let temp_dir = TempDir::new().unwrap();
+        write_set(
+            temp_dir.path(),
+            "name = \"escape\"\n[[files]]\nsource = \"../secret\"\n",
+            &[],
+        );
+        let error = TemplateSet::load(temp_dir.path()).unwrap_err();
+        assert!(error.to_string().contains("inside the template directory"));
+
+        write_set(
+            temp_dir.path(),
+            "name = \"escape\"\n[[files]]\nsource = \"lib.rs\"\ntarget = \"../lib.rs\"\n",
+            &[("lib.rs", "")],
+        );
+        let set = TemplateSet::load(temp_dir.path()).unwrap();
+        assert!(set.render(&context_default()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_sources_that_link_outside_the_template() {
+        use std::os::unix::fs::symlink;
+
+        let host = TempDir::new().unwrap();
+        fs::write(host.path().join("id.json"), "[1,2,3]").unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        write_set(
+            temp_dir.path(),
+            "name = \"escape\"\n[[files]]\nsource = \"wallet.json\"\n",
+            &[],
+        );
+        symlink(
+            host.path().join("id.json"),
+            temp_dir.path().join("wallet.json"),
+        )
+        .unwrap();
+        let error = TemplateSet::load(temp_dir.path()).unwrap_err();
+        assert!(error.to_string().contains("inside the template directory"));
+
+        // Listed files are checked too, as are linked directories.
+        fs::write(temp_dir.path().join(MANIFEST_FILE), "name = \"escape\"\n").unwrap();
+        assert!(TemplateSet::load(temp_dir.path()).is_err());
+        fs::remove_file(temp_dir.path().join("wallet.json")).unwrap();
+        symlink(host.path(), temp_dir.path().join("config")).unwrap();
+        write_set(
+            temp_dir.path(),
+            "name = \"escape\"\n[[files]]\nsource = \"config/id.json\"\n",
+            &[],
+        );
+        assert!(TemplateSet::load(temp_dir.path()).is_err());
+
+        // Links that stay inside the template are fine.
+        fs::remove_file(temp_dir.path().join("config")).unwrap();
+        write_set(
+            temp_dir.path(),
+            "name = \"linked\"\n[[files]]\nsource = \"alias.rs\"\n",
+            &[("lib.rs", "pub fn f() {}\n")],
+        );
+        symlink(
+            temp_dir.path().join("lib.rs"),
+            temp_dir.path().join("alias.rs"),
+        )
+        .unwrap();
+        let set = TemplateSet::load(temp_dir.path()).unwrap();
+        assert_eq!(set.files[Path::new("alias.rs")], "pub fn f() {}\n");
+    }
+
+    #[test]
+    fn parses_variable_arguments() {
+        assert_eq!(
+            parse_variable("author=Ann = Lee").unwrap(),
+            ("author".to_string(), "Ann = Lee".to_string())
+        );
+        assert_eq!(parse_variable("empty=").unwrap().1, "");
+        assert!(parse_variable("author").is_err());
+        assert!(parse_variable("=Ann").is_err());
+    }
+
+    #[test]
+    fn recognizes_git_urls() {
+        assert!(is_git_url("https://github.com/org/template"));
+        assert!(is_git_url("git@github.com:org/template.git"));
+        assert!(is_git_url("ssh://git@host/template"));
+        assert!(!is_git_url("./templates/house-style"));
+        assert!(!is_git_url("/opt/templates"));
+    }
+}
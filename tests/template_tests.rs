@@ -419,4 +419,31 @@ mod templates {
         let test_output = unit_tests::litesvm_initialize_rs(proj_name);
         assert!(test_output.contains(&format!("use {}", proj_name)));
     }
+
+    #[test]
+    fn workspace_tests_load_programs_through_test_helpers() {
+        let context = chio::template::TemplateContext::new("proj")
+            .with_program_id("prog")
+            .with_payer("user")
+            .with_feature("workspace", true);
+
+        let mollusk_test = chio::template::render(unit_tests::UNIT_TEST_RS, &context).unwrap();
+        assert!(
+            mollusk_test.contains("Mollusk::new(&PROGRAM, &test_helpers::program_path(\"proj\"))")
+        );
+        assert!(!mollusk_test.contains("\"target/deploy"));
+
+        let litesvm_test =
+            chio::template::render(unit_tests::LITESVM_INITIALIZE_RS, &context).unwrap();
+        assert!(litesvm_test.contains("test_helpers::deploy_dir().join(\"proj.so\")"));
+        assert!(!litesvm_test.contains("PathBuf"));
+    }
+
+    #[test]
+    fn workspace_cargo_toml_lists_test_helpers() {
+        assert!(workspace::cargo_toml().contains("[workspace]"));
+        assert!(workspace::cargo_toml().contains("\"test-helpers\""));
+        assert!(workspace::test_helpers_cargo_toml().contains("name = \"test-helpers\""));
+        assert!(workspace::test_helpers_lib_rs().contains("pub fn program_path"));
+    }
 }
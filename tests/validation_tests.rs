@@ -6,23 +6,84 @@
 #[cfg(test)]
 mod validation {
     use chio::is_valid_project_name;
+    use chio::name::{validate_project_name, InvalidProjectName};
 
     #[test]
     fn valid_project_name() {
         assert!(is_valid_project_name("my_project"));
         assert!(is_valid_project_name("MyProject"));
         assert!(is_valid_project_name("project123"));
+        assert!(is_valid_project_name("my-project"));
+        assert!(is_valid_project_name("_project"));
     }
 
     #[test]
     fn invalid_project_name_empty() {
         assert!(!is_valid_project_name(""));
+        assert_eq!(validate_project_name(""), Err(InvalidProjectName::Empty));
     }
 
     #[test]
     fn invalid_project_name_with_special_chars() {
-        assert!(!is_valid_project_name("my-project"));
         assert!(!is_valid_project_name("my.project"));
         assert!(!is_valid_project_name("my project"));
+        assert_eq!(
+            validate_project_name("café"),
+            Err(InvalidProjectName::InvalidCharacter {
+                name: "café".to_string(),
+                character: 'é',
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_project_name_start() {
+        assert!(matches!(
+            validate_project_name("123abc"),
+            Err(InvalidProjectName::InvalidStart { .. })
+        ));
+        assert!(matches!(
+            validate_project_name("-project"),
+            Err(InvalidProjectName::InvalidStart { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_project_name_keyword_or_crate() {
+        for name in ["fn", "self", "Self", "async", "_"] {
+            assert!(
+                matches!(
+                    validate_project_name(name),
+                    Err(InvalidProjectName::Keyword { .. })
+                ),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            validate_project_name("pinocchio-system"),
+            Err(InvalidProjectName::ReservedCrate {
+                name: "pinocchio-system".to_string(),
+                ident: "pinocchio_system".to_string(),
+            })
+        );
+        assert!(is_valid_project_name("fn-program"));
+    }
+
+    #[test]
+    fn hyphens_map_to_the_crate_name() {
+        let name = validate_project_name("my-pinocchio-app").unwrap();
+        assert_eq!(name.package(), "my-pinocchio-app");
+        assert_eq!(name.crate_name(), "my_pinocchio_app");
+    }
+
+    #[test]
+    fn errors_explain_the_problem() {
+        let error = validate_project_name("123abc").unwrap_err().to_string();
+        assert!(error.contains("must start with a letter or an underscore"));
+        let error = validate_project_name("fn").unwrap_err().to_string();
+        assert!(error.contains("'fn' is a Rust keyword"));
+        let error = validate_project_name("my project").unwrap_err().to_string();
+        assert!(error.contains("' ' is not allowed"));
     }
 }